@@ -0,0 +1,216 @@
+# English text of the GUI, the fallback of every other language
+# Numbers arrive formatted, so they are not localized here
+
+## App
+
+app-title = R-Ising Model
+app-description = Ising Model simulation built with Rust and egui.
+app-credits = Made by Husni smoll brain
+light-mode = ☀ Light mode
+dark-mode = 🌙 Dark mode
+language = Language
+new-tab = + New
+close-tab = Close
+tab-name = Name
+default-tab-name = Simulation { $number }
+
+## Restore prompt
+
+restore-heading = Restore last session?
+restore-simulations = Simulations: { $names }
+restore-older = Saved by an older version (layout { $saved }), migrated to layout { $current }
+restore-newer = Saved by a newer version (layout { $saved }), some settings may be lost
+restore = Restore
+start-fresh = Start fresh
+restore-failed-heading = Last session could not be restored
+restore-failed = Failed to read the state of layout { $saved }. Error { $error }
+
+## Run controls
+
+resume = Resume
+pause = Pause
+reset = Reset
+sweeps-done = Sweeps: { $sweeps }
+flips = Flips: { $accepted } accepted / { $attempted } attempted
+acceptance-rate = Acceptance rate: { $rate }%
+cluster-acceptance-rate = Cluster acceptance rate: { $rate }% (clusters flip up to a lattice of spins per sweep)
+running-time = Running time: { $seconds } s ({ $speed } sweeps/s)
+reset-counters = Reset counters
+
+## Parameters
+
+seed = Seed
+reseed = Reseed
+random-seed = Random
+active-seed = Active seed: { $seed }
+presets = Presets
+preset-ordered = Deep in ordered phase
+preset-critical = Near Tc
+preset-disordered = Disordered
+algorithm = Algorithm
+algorithm-metropolis = Metropolis
+algorithm-heat-bath = Heat-bath
+algorithm-wolff = Wolff
+boundary = Boundary
+boundary-mirror = Mirror
+boundary-open = Open
+boundary-periodic = Periodic
+boundary-fixed = Fixed
+compare-replica = Compare replica
+replica-temperature = Replica temperature (K)
+lattice-size = Lattice Size
+temperature = Temperature (K)
+interactivity = Interactivity
+external-field = External field h / |J|
+flip-field = Flip field
+reduced-temperature = Reduced temperature k_B T / J: { $value }
+steps-per-frame = Steps per frame
+
+## Tools
+
+temperature-scan = Temperature scan
+phase-diagram = Phase diagram
+save = Save
+load = Load
+pixels-per-spin = Pixels per spin
+export-png = Export PNG
+export-data = Export data
+export-data-hint = Sweep, energy, magnetization, and acceptance of the recorded sweeps
+frame-every = Frame every
+sweeps = sweeps
+frame-rate = Frame rate
+scale = Scale
+record-gif = Record GIF
+stop-gif = Stop GIF ({ $frames } frames)
+
+## Timeline
+
+timeline = Timeline
+checkpoint-every = Checkpoint every
+keep = Keep
+checkpoints = checkpoints
+checkpoint-at = Checkpoint at sweep { $sweep }
+resume-from-here = Resume from here
+back-to-live = Back to live
+live-at = Live at sweep { $sweep }
+showing-checkpoint = Showing the checkpoint at sweep { $sweep }
+
+## Painting and view
+
+brush-size = Brush size
+brush-hint = Left drag paints up, right drag paints down
+touch-paints = Touch paints
+up = Up
+down = Down
+undo = Undo
+redo = Redo
+view = View
+view-spins = Spins
+view-clusters = Clusters
+view-energy = Energy
+colormap = Colormap
+colormap-classic = Classic
+colormap-viridis = Viridis
+colormap-color-blind = Color-blind safe
+low = Low
+high = High
+largest-cluster-size = Largest cluster: { $spins } spins ({ $percent }%)
+legends = Legends:
+spin-up = Spin up (+)
+spin-down = Spin down (-)
+
+## Canvas
+
+show-controls = ☰ Controls
+show-observables = Observables
+show-dashboard = Dashboard
+show-performance = Performance
+touch-hint = Drag to paint, pinch to zoom, two fingers to pan
+hover-hint = Hover on a tile to see the detail
+navigation-hint = | Scroll to zoom, middle or ctrl drag to pan
+reset-view = Reset view
+replica-temperatures = Left T = { $left } K | Right replica T = { $right } K
+site-up = x: { $x }, y: { $y } Spin up (+)
+site-down = x: { $x }, y: { $y } Spin down (-)
+neighbours = Neighbours left: { $left } | right: { $right } | above: { $above } | below: { $below }
+neighbour-sum = Neighbour sum: { $sum }
+site-energy = Hamiltonian Energy: { $energy } | Diff: { $diff }
+site-acceptance = Acceptance Criteria: { $criteria } | Will be flipped? { $flipped }
+drop-hint = Drop a snapshot or exported PNG to load it
+
+## Observables
+
+observables = Observables
+window-sweeps = Window (sweeps)
+magnetization = Magnetization
+energy-per-spin = Energy per spin
+histograms = Histograms
+bins = Bins
+count = Count
+sweep = Sweep
+paused = Paused
+compute-correlation = Compute C(r)
+every-sweeps = Every (sweeps)
+correlation-length = Correlation length ξ = { $length }
+distance = Distance r
+
+## Dashboard
+
+dashboard = Dashboard
+acceptance = Acceptance
+clusters = Clusters
+largest-cluster = Largest cluster
+mean-cluster = Mean cluster
+dashboard-window = χ over the latest { $window } sweeps, ξ when C(r) is computed
+dashboard-clusters = Largest cluster is the fraction of spins, mean cluster in spins
+
+## Performance
+
+flips-per-second = Flips/s
+sweeps-per-second = Sweeps/s
+frame = Frame
+steps = Steps
+upload = Upload
+
+## Temperature scan
+
+scan-from = k_B T / J from
+scan-to = to
+temperatures = Temperatures
+equilibration-sweeps = Equilibration sweeps
+sweeps-per-point = Sweeps per point
+cancel = Cancel
+run = Run
+export-csv = Export CSV
+scan-progress = { $done } / { $total } temperatures
+scan-description = { $sweeps } sweeps after { $equilibration } equilibration sweeps with { $algorithm }
+
+## Phase diagram
+
+phase-sweeps = Sweeps { $sweeps } / { $total }
+phase-hint = Columns are k_B T / J, rows are h / J. Click a cell to load it
+
+## Screen readers
+
+a11y-lattice = Lattice
+a11y-lattice-description = { $size } by { $size } spins, magnetization { $magnetization }
+a11y-reduced-temperature = { $reduced } reduced, { $kelvin } Kelvin
+a11y-kelvin = { $value } Kelvin
+a11y-field = { $value } times |J|
+a11y-spins = { $count } spins
+a11y-trend = { $count } values between { $low } and { $high }
+a11y-no-trend = No values yet
+
+## Equilibrium
+
+auto-pause = Pause at equilibrium
+auto-pause-hint = Pause once the mean and variance of the energy agree over the two latest windows
+equilibrated-toast = Equilibrated after { $sweeps } sweeps, paused
+
+## Structure factor
+
+show-structure-factor = S(k)
+structure-factor = Structure factor S(k)
+structure-factor-every = every (sweeps)
+structure-factor-peak = Central peak S(0) = { $value }
+structure-factor-hover = k = ({ $kx }, { $ky }) π, S(k) = { $value }
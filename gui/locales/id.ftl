@@ -0,0 +1,215 @@
+# Indonesian text of the GUI, missing messages fall back to English
+
+## App
+
+app-title = R-Ising Model
+app-description = Simulasi Model Ising yang dibangun dengan Rust dan egui.
+app-credits = Dibuat oleh Husni smoll brain
+light-mode = ☀ Mode terang
+dark-mode = 🌙 Mode gelap
+language = Bahasa
+new-tab = + Baru
+close-tab = Tutup
+tab-name = Nama
+default-tab-name = Simulasi { $number }
+
+## Restore prompt
+
+restore-heading = Pulihkan sesi terakhir?
+restore-simulations = Simulasi: { $names }
+restore-older = Disimpan oleh versi lama (tata letak { $saved }), dimigrasikan ke tata letak { $current }
+restore-newer = Disimpan oleh versi baru (tata letak { $saved }), sebagian pengaturan mungkin hilang
+restore = Pulihkan
+start-fresh = Mulai baru
+restore-failed-heading = Sesi terakhir tidak dapat dipulihkan
+restore-failed = Gagal membaca keadaan tata letak { $saved }. Galat { $error }
+
+## Run controls
+
+resume = Lanjutkan
+pause = Jeda
+reset = Atur ulang
+sweeps-done = Sapuan: { $sweeps }
+flips = Pembalikan: { $accepted } diterima / { $attempted } dicoba
+acceptance-rate = Tingkat penerimaan: { $rate }%
+cluster-acceptance-rate = Tingkat penerimaan klaster: { $rate }% (klaster dibalik hingga satu kisi spin per sapuan)
+running-time = Waktu berjalan: { $seconds } dtk ({ $speed } sapuan/dtk)
+reset-counters = Atur ulang penghitung
+
+## Parameters
+
+seed = Benih
+reseed = Ganti benih
+random-seed = Acak
+active-seed = Benih aktif: { $seed }
+presets = Prasetel
+preset-ordered = Jauh di fase teratur
+preset-critical = Dekat Tc
+preset-disordered = Tidak teratur
+algorithm = Algoritma
+algorithm-metropolis = Metropolis
+algorithm-heat-bath = Heat-bath
+algorithm-wolff = Wolff
+boundary = Batas
+boundary-mirror = Cermin
+boundary-open = Terbuka
+boundary-periodic = Periodik
+boundary-fixed = Tetap
+compare-replica = Bandingkan replika
+replica-temperature = Suhu replika (K)
+lattice-size = Ukuran Kisi
+temperature = Suhu (K)
+interactivity = Interaksi
+external-field = Medan luar h / |J|
+flip-field = Balik medan
+reduced-temperature = Suhu tereduksi k_B T / J: { $value }
+steps-per-frame = Langkah per bingkai
+
+## Tools
+
+temperature-scan = Pemindaian suhu
+phase-diagram = Diagram fase
+save = Simpan
+load = Muat
+pixels-per-spin = Piksel per spin
+export-png = Ekspor PNG
+export-data = Ekspor data
+export-data-hint = Sapuan, energi, magnetisasi, dan penerimaan dari sapuan yang direkam
+frame-every = Bingkai setiap
+sweeps = sapuan
+frame-rate = Laju bingkai
+scale = Skala
+record-gif = Rekam GIF
+stop-gif = Hentikan GIF ({ $frames } bingkai)
+
+## Timeline
+
+timeline = Linimasa
+checkpoint-every = Titik simpan setiap
+keep = Simpan
+checkpoints = titik simpan
+checkpoint-at = Titik simpan pada sapuan { $sweep }
+resume-from-here = Lanjutkan dari sini
+back-to-live = Kembali ke langsung
+live-at = Langsung pada sapuan { $sweep }
+showing-checkpoint = Menampilkan titik simpan pada sapuan { $sweep }
+
+## Painting and view
+
+brush-size = Ukuran kuas
+brush-hint = Seret kiri melukis ke atas, seret kanan melukis ke bawah
+touch-paints = Sentuhan melukis
+up = Atas
+down = Bawah
+undo = Urungkan
+redo = Ulangi
+view = Tampilan
+view-spins = Spin
+view-clusters = Klaster
+view-energy = Energi
+colormap = Peta warna
+colormap-classic = Klasik
+colormap-viridis = Viridis
+colormap-color-blind = Aman buta warna
+low = Rendah
+high = Tinggi
+largest-cluster-size = Klaster terbesar: { $spins } spin ({ $percent }%)
+legends = Legenda:
+spin-up = Spin atas (+)
+spin-down = Spin bawah (-)
+
+## Canvas
+
+show-controls = ☰ Kontrol
+show-observables = Observabel
+show-dashboard = Dasbor
+show-performance = Kinerja
+touch-hint = Seret untuk melukis, cubit untuk memperbesar, dua jari untuk menggeser
+hover-hint = Arahkan ke petak untuk melihat detailnya
+navigation-hint = | Gulir untuk memperbesar, seret tengah atau ctrl untuk menggeser
+reset-view = Atur ulang tampilan
+replica-temperatures = T kiri = { $left } K | T replika kanan = { $right } K
+site-up = x: { $x }, y: { $y } Spin atas (+)
+site-down = x: { $x }, y: { $y } Spin bawah (-)
+neighbours = Tetangga kiri: { $left } | kanan: { $right } | atas: { $above } | bawah: { $below }
+neighbour-sum = Jumlah tetangga: { $sum }
+site-energy = Energi Hamiltonian: { $energy } | Selisih: { $diff }
+site-acceptance = Kriteria Penerimaan: { $criteria } | Akan dibalik? { $flipped }
+drop-hint = Lepaskan snapshot atau PNG ekspor untuk memuatnya
+
+## Observables
+
+observables = Observabel
+window-sweeps = Jendela (sapuan)
+magnetization = Magnetisasi
+energy-per-spin = Energi per spin
+histograms = Histogram
+bins = Wadah
+count = Jumlah
+sweep = Sapuan
+paused = Dijeda
+compute-correlation = Hitung C(r)
+every-sweeps = Setiap (sapuan)
+correlation-length = Panjang korelasi ξ = { $length }
+distance = Jarak r
+
+## Dashboard
+
+dashboard = Dasbor
+acceptance = Penerimaan
+clusters = Klaster
+largest-cluster = Klaster terbesar
+mean-cluster = Rerata klaster
+dashboard-window = χ selama { $window } sapuan terakhir, ξ saat C(r) dihitung
+dashboard-clusters = Klaster terbesar dalam fraksi spin, rerata klaster dalam spin
+
+## Performance
+
+flips-per-second = Pembalikan/dtk
+sweeps-per-second = Sapuan/dtk
+frame = Bingkai
+steps = Langkah
+upload = Unggah
+
+## Temperature scan
+
+scan-from = k_B T / J dari
+scan-to = sampai
+temperatures = Jumlah suhu
+equilibration-sweeps = Sapuan ekuilibrasi
+sweeps-per-point = Sapuan per titik
+cancel = Batal
+run = Jalankan
+export-csv = Ekspor CSV
+scan-progress = { $done } / { $total } suhu
+scan-description = { $sweeps } sapuan setelah { $equilibration } sapuan ekuilibrasi dengan { $algorithm }
+
+## Phase diagram
+
+phase-sweeps = Sapuan { $sweeps } / { $total }
+phase-hint = Kolom adalah k_B T / J, baris adalah h / J. Klik sel untuk memuatnya
+
+## Screen readers
+
+a11y-lattice = Kisi
+a11y-lattice-description = { $size } kali { $size } spin, magnetisasi { $magnetization }
+a11y-reduced-temperature = { $reduced } tereduksi, { $kelvin } Kelvin
+a11y-kelvin = { $value } Kelvin
+a11y-field = { $value } kali |J|
+a11y-spins = { $count } spin
+a11y-trend = { $count } nilai antara { $low } dan { $high }
+a11y-no-trend = Belum ada nilai
+
+## Equilibrium
+
+auto-pause = Jeda saat setimbang
+auto-pause-hint = Jeda setelah rerata dan varians energi sama pada dua jendela terakhir
+equilibrated-toast = Setimbang setelah { $sweeps } sapuan, dijeda
+
+## Structure factor
+
+show-structure-factor = S(k)
+structure-factor = Faktor struktur S(k)
+structure-factor-every = setiap (sapuan)
+structure-factor-peak = Puncak pusat S(0) = { $value }
+structure-factor-hover = k = ({ $kx }, { $ky }) π, S(k) = { $value }
@@ -0,0 +1,24 @@
+use eframe::egui::{self, accesskit::Role};
+
+/// Name a widget and the value screen readers announce for it, e.g. "Temperature" and
+/// "2.3 reduced, 2269 Kelvin", replacing the bare number the widget announces on its own
+pub fn describe(response: &egui::Response, label: impl Into<String>, value: impl Into<String>) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_label(label.into());
+        node.set_value(value.into());
+    });
+}
+
+/// Announce a painted area, like the lattice canvas or a sparkline, as an image
+/// with a name and a longer description of what it shows
+pub fn describe_image(
+    response: &egui::Response,
+    label: impl Into<String>,
+    description: impl Into<String>,
+) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_role(Role::Image);
+        node.set_label(label.into());
+        node.set_description(description.into());
+    });
+}
@@ -1,19 +1,49 @@
-use eframe::egui::{self, Pos2, Rect};
-use internal::Lattice;
+use crate::i18n::{self, Language, t};
+use crate::permalink::{self, Permalink};
+use crate::simulation::Simulation;
+use crate::state::{self, STATE_VERSION, SavedSession};
+use eframe::egui;
+use std::time::Duration;
+
+/// Time of a frame the running simulations may spend stepping, split evenly between them
+/// Every tab steps on the UI thread, so the budget keeps many running tabs from freezing it
+const STEP_BUDGET: Duration = Duration::from_millis(12);
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct App {
-    pub lattice: Lattice,
-    pub is_paused: bool,
+    /// independent simulations, one per tab
+    pub simulations: Vec<Simulation>,
+    /// index of the shown simulation
+    pub active: usize,
+    /// number of simulations created so far, used to name new tabs
+    pub created: usize,
+    /// light or dark look of the app
+    pub theme: egui::Theme,
+    /// language of the UI text
+    pub language: Language,
+    /// layout version of the persisted state, see the state module
+    pub version: u32,
+    // Last session waiting for the user to restore it or start fresh
+    #[serde(skip)]
+    saved: Option<SavedSession>,
+    // Page embedded in another site, showing the lattice alone and keeping no state
+    #[serde(skip)]
+    embedded: bool,
 }
 
 impl Default for App {
     fn default() -> Self {
         Self {
-            lattice: Lattice::new(15, 100.0, 100.0),
-            is_paused: true,
+            simulations: vec![Simulation::default()],
+            active: 0,
+            created: 1,
+            theme: egui::Theme::Dark,
+            language: Language::default(),
+            version: STATE_VERSION,
+            saved: None,
+            embedded: false,
         }
     }
 }
@@ -21,192 +51,209 @@ impl Default for App {
 impl App {
     /// Called new before egui render the frist frame
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        // Set to always use dark mode
-        cc.egui_ctx.set_theme(egui::Theme::Dark);
-
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
-        // Load previous app state (if any).
+        // Load previous app state (if any), migrated to the current layout.
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
-        } else {
-            Default::default()
+        // It is only restored once the user accepts the prompt.
+        let mut app = Self {
+            saved: cc.storage.and_then(state::load),
+            ..Default::default()
+        };
+        // A shared link opens its configuration, the last session can still be restored
+        if let Some(permalink) = Permalink::from_page() {
+            app.simulations[0].apply_permalink(&permalink);
+        }
+        // Embedded pages share the storage of the site, so they leave the session alone
+        if permalink::is_embedded() {
+            app.saved = None;
+            app.embedded = true;
+        }
+
+        cc.egui_ctx.set_theme(app.theme);
+        i18n::set_language(app.language);
+        app
+    }
+
+    /// Prompt asking whether to restore the last session or start fresh
+    fn restore_prompt(&mut self, ctx: &egui::Context) {
+        let Some(saved) = &self.saved else {
+            return;
+        };
+        let mut restore = false;
+        let mut discard = false;
+        egui::Modal::new(egui::Id::new("restore_prompt")).show(ctx, |ui| match &saved.app {
+            Ok(app) => {
+                ui.heading(t!("restore-heading"));
+                let names: Vec<&str> = app
+                    .simulations
+                    .iter()
+                    .map(|simulation| simulation.name.as_str())
+                    .collect();
+                ui.label(t!("restore-simulations", names = names.join(", ")));
+                if saved.version < STATE_VERSION {
+                    ui.label(t!(
+                        "restore-older",
+                        saved = saved.version,
+                        current = STATE_VERSION
+                    ));
+                } else if saved.version > STATE_VERSION {
+                    ui.label(t!("restore-newer", saved = saved.version));
+                }
+                ui.horizontal(|ui| {
+                    restore = ui.button(t!("restore")).clicked();
+                    discard = ui.button(t!("start-fresh")).clicked();
+                });
+            }
+            Err(e) => {
+                ui.heading(t!("restore-failed-heading"));
+                ui.label(t!("restore-failed", saved = saved.version, error = e));
+                discard = ui.button(t!("start-fresh")).clicked();
+            }
+        });
+
+        if restore {
+            if let Some(SavedSession { app: Ok(app), .. }) = self.saved.take() {
+                println!("Restoring last session");
+                *self = *app;
+                ctx.set_theme(self.theme);
+                i18n::set_language(self.language);
+            }
+        } else if discard {
+            println!("Starting a fresh session");
+            self.saved = None;
         }
     }
+
+    /// Button switching between the light and dark theme
+    fn theme_toggle(&mut self, ui: &mut egui::Ui) {
+        let label = match self.theme {
+            egui::Theme::Dark => t!("light-mode"),
+            egui::Theme::Light => t!("dark-mode"),
+        };
+        if ui.button(label).clicked() {
+            self.theme = match self.theme {
+                egui::Theme::Dark => egui::Theme::Light,
+                egui::Theme::Light => egui::Theme::Dark,
+            };
+            println!("Switching theme to {:?}", self.theme);
+            ui.ctx().set_theme(self.theme);
+        }
+    }
+
+    /// Combo box switching the language of the UI text
+    fn language_selector(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::new("language", t!("language"))
+            .selected_text(self.language.name())
+            .show_ui(ui, |ui| {
+                for language in Language::ALL {
+                    let response =
+                        ui.selectable_value(&mut self.language, language, language.name());
+                    if response.changed() {
+                        println!("Switching language to {}", language.name());
+                        i18n::set_language(language);
+                    }
+                }
+            });
+    }
+
+    /// Tab per simulation with buttons adding a new one and closing the shown one
+    fn tab_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for (index, simulation) in self.simulations.iter().enumerate() {
+                if ui
+                    .selectable_label(index == self.active, &simulation.name)
+                    .clicked()
+                {
+                    println!("Switching to {}", simulation.name);
+                    self.active = index;
+                }
+            }
+
+            if ui.button(t!("new-tab")).clicked() {
+                self.created += 1;
+                let name = t!("default-tab-name", number = self.created);
+                println!("Creating {name}");
+                self.simulations.push(Simulation::named(name));
+                self.active = self.simulations.len() - 1;
+            }
+
+            // Keep at least one simulation around
+            if self.simulations.len() > 1 && ui.button(t!("close-tab")).clicked() {
+                let simulation = self.simulations.remove(self.active);
+                println!("Closing {}", simulation.name);
+                self.active = self.active.min(self.simulations.len() - 1);
+            }
+
+            ui.separator();
+            ui.label(t!("tab-name"));
+            ui.text_edit_singleline(&mut self.simulations[self.active].name);
+        });
+    }
 }
 
 impl eframe::App for App {
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // Keep the last session until the user answered the restore prompt
+        if self.saved.is_some() || self.embedded {
+            return;
+        }
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let side_panel_width = 150.0;
         let top_bottom_panel_height = 50.0;
+        // Persisted state may miss simulations or point past the last one
+        if self.simulations.is_empty() {
+            *self = Self::default();
+        }
+        self.active = self.active.min(self.simulations.len() - 1);
+
+        if self.embedded {
+            self.simulations[0].show_embedded(ctx);
+            self.simulations[0].advance(ctx, STEP_BUDGET);
+            return;
+        }
 
         egui::TopBottomPanel::top("top_panel")
             .resizable(true)
             .default_height(top_bottom_panel_height)
-            .height_range(25.0..=75.0)
+            .height_range(25.0..=100.0)
             .show(ctx, |ui| {
                 ui.vertical_centered(|ui| {
-                    ui.heading("R-Ising Model");
-                    ui.label("Ising Model simulation built with Rust and egui.");
-                });
-            });
-
-        egui::SidePanel::left("left_panel")
-            .default_width(side_panel_width)
-            .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    if self.is_paused {
-                        if ui.button("Resume").clicked() {
-                            println!("Resumed");
-                            self.is_paused = false;
-                        }
-                    } else if ui.button("Pause").clicked() {
-                        println!("Paused");
-                        self.is_paused = true;
-                    }
-
-                    if ui.button("Reset").clicked() {
-                        println!("Reset");
-                        self.lattice = self.lattice.reset_value();
-                    }
-                });
-                ui.label("");
-
-                ui.horizontal(|ui| {
-                    ui.label("Lattice Size");
-                    let response =
-                        ui.add(egui::DragValue::new(&mut self.lattice.size).range(5.0..=25.0));
-                    if response.changed() {
-                        println!("Updating Lattice size to {}", self.lattice.size);
-                        self.lattice.update_lattice();
-                    }
-                });
-
-                ui.vertical(|ui| {
-                    ui.label("Temperature (K)");
-                    let response = ui.add(egui::Slider::new(
-                        &mut self.lattice.temperature,
-                        0.0..=10_000.0,
-                    ));
-                    if response.changed() {
-                        println!("Updating temperature (K) to {}", self.lattice.temperature);
-                    }
+                    ui.heading(t!("app-title"));
+                    ui.label(t!("app-description"));
+                    ui.horizontal(|ui| {
+                        self.theme_toggle(ui);
+                        self.language_selector(ui);
+                    });
                 });
-
-                ui.vertical(|ui| {
-                    ui.label("Interactivity");
-                    let response = ui.add(egui::Slider::new(
-                        &mut self.lattice.interactivity,
-                        -10_000.0..=10_000.0,
-                    ));
-                    if response.changed() {
-                        println!(
-                            "Updating interactivity (K) to {}",
-                            self.lattice.interactivity
-                        );
-                    }
-                });
-
-                ui.vertical(|ui| {
-                    ui.label("");
-                    ui.label("Legends:");
-                    ui.label(egui::RichText::new("Spin up (+)").color(egui::Color32::DARK_RED));
-                    ui.label(egui::RichText::new("Spin down (-)").color(egui::Color32::LIGHT_BLUE));
-                });
-            });
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            egui::containers::Frame::canvas(ui.style()).show(ui, |ui| {
-                ui.label("Hover on a tile to see the detail");
-
-                // To create a 2D grid we need these data
-                // - Display size
-                // - Number of spins in a row
-                let ui_size = ui.available_size();
-                let (tile_size, offset) = if ui_size.x > ui_size.y {
-                    (
-                        ui_size.y / (1.5 * self.lattice.size as f32),
-                        (ui_size.x - ui_size.y) / 2.,
-                    )
-                } else {
-                    (
-                        ui_size.x / (1.5 * self.lattice.size as f32),
-                        (ui_size.y - ui_size.x) / 2.,
-                    )
-                };
-
-                // Render lattice
-                for x in 0..self.lattice.size {
-                    for y in 0..self.lattice.size {
-                        let (xp, yp) = if ui_size.x > ui_size.y {
-                            (
-                                x as f32 * tile_size + offset + 1.5 * side_panel_width,
-                                y as f32 * tile_size + top_bottom_panel_height,
-                            )
-                        } else {
-                            (
-                                x as f32 * tile_size + 1.5 * side_panel_width,
-                                y as f32 * tile_size + offset + top_bottom_panel_height,
-                            )
-                        };
-                        let tile = Rect::from_two_pos(
-                            Pos2::new(xp, yp),
-                            Pos2::new(xp + tile_size, yp + tile_size),
-                        );
-                        if ui.rect_contains_pointer(tile) {
-                            let h_energy = self.lattice.calculate_hamiltonian(x, y);
-                            let delta_h = self.lattice.calculate_delta_h(x, y);
-                            let acceptence_criteria =
-                                self.lattice.calculate_acceptence_criteria(delta_h);
-                            let is_flipped = delta_h < 0.0 || acceptence_criteria > 0.5;
-
-                            if self.lattice.value[y].value[x] == 1 {
-                                ui.label(
-                                    egui::RichText::new(format!("x: {x}, y: {y} Spin up (+)"))
-                                        .color(egui::Color32::DARK_RED),
-                                );
-                            } else {
-                                ui.label(
-                                    egui::RichText::new(format!("x: {x}, y: {y} Spin down (-)"))
-                                        .color(egui::Color32::LIGHT_BLUE),
-                                );
-                            }
-                            ui.label(format!("Hamiltonian Energy: {h_energy} | Diff: {delta_h}"));
-                            ui.label(format!("Acceptance Criteria: {acceptence_criteria} | Will be flipped? {is_flipped}"));
-                        }
-                        let fil_color = if self.lattice.value[y].value[x] == 1 {
-                            egui::Color32::DARK_RED
-                        } else {
-                            egui::Color32::LIGHT_BLUE
-                        };
-                        ui.painter().rect_filled(tile, 0.0, fil_color);
-                    }
-                }
-
-                // Only re-calculate and repaint if resumed
-                if !self.is_paused {
-                    let (x_rand, y_rand) = self.lattice.pick_random_point();
-                    self.lattice.metropolis_algo_calculation(x_rand, y_rand);
-
-                    ui.ctx().request_repaint();
-                }
+                self.tab_bar(ui);
             });
-        });
 
         egui::TopBottomPanel::bottom("bottom_panel")
             .resizable(true)
             .default_height(top_bottom_panel_height)
             .height_range(25.0..=75.0)
             .show(ctx, |ui| {
-                ui.label("Made by Husni smoll brain");
+                ui.label(t!("app-credits"));
             });
+
+        self.simulations[self.active].show(ctx);
+        self.restore_prompt(ctx);
+
+        // Hidden simulations keep running in the background, sharing the step budget
+        let running = self
+            .simulations
+            .iter()
+            .filter(|simulation| !simulation.is_paused)
+            .count();
+        let budget = STEP_BUDGET / running.max(1) as u32;
+        for simulation in &mut self.simulations {
+            simulation.advance(ctx, budget);
+        }
     }
 }
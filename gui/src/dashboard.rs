@@ -0,0 +1,181 @@
+use crate::a11y;
+use crate::i18n::t;
+use eframe::egui;
+use internal::{
+    Lattice,
+    correlation::correlation_length,
+    recorder::{Recorder, Sample},
+};
+use std::collections::VecDeque;
+
+/// Number of latest values drawn by every sparkline
+const SPARKLINE_LENGTH: usize = 100;
+
+/// Size of a sparkline in points
+const SPARKLINE_SIZE: egui::Vec2 = egui::vec2(100.0, 20.0);
+
+/// Trends of the observables the recorder does not keep, taken after every sweep
+#[derive(Default)]
+pub struct Dashboard {
+    /// windowed magnetic susceptibility
+    susceptibility: VecDeque<f64>,
+    /// fitted correlation length, taken whenever the correlation function is computed
+    correlation_length: VecDeque<f64>,
+    /// number of connected same-spin domains
+    clusters: VecDeque<f64>,
+    /// fraction of spins in the largest domain
+    largest_cluster: VecDeque<f64>,
+    /// mean number of spins per domain
+    mean_cluster: VecDeque<f64>,
+}
+
+impl Dashboard {
+    /// Take the windowed susceptibility and cluster statistics after a finished sweep
+    pub fn record(&mut self, lattice: &Lattice, recorder: &Recorder, window: usize) {
+        push(
+            &mut self.susceptibility,
+            recorder.susceptibility(lattice, window),
+        );
+        let sizes = lattice.cluster_sizes();
+        let spins = (lattice.size * lattice.size) as f64;
+        let largest = sizes.iter().copied().max().unwrap_or_default();
+        push(&mut self.clusters, sizes.len() as f64);
+        push(&mut self.largest_cluster, largest as f64 / spins);
+        push(&mut self.mean_cluster, spins / sizes.len().max(1) as f64);
+    }
+
+    /// Take the correlation length of a newly computed correlation function
+    pub fn record_correlation(&mut self, correlation: &[f64]) {
+        if let Some(length) = correlation_length(correlation) {
+            push(&mut self.correlation_length, length);
+        }
+    }
+
+    /// Forget every trend, e.g. when the lattice is replaced
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Grid of the current observables, each with a sparkline of its latest values
+    pub fn show(&self, ui: &mut egui::Ui, recorder: &Recorder, window: usize) {
+        ui.heading(t!("dashboard"));
+        let trend = |select: fn(&Sample) -> f64| -> Vec<f64> {
+            let skip = recorder.samples.len().saturating_sub(SPARKLINE_LENGTH);
+            recorder.samples.iter().skip(skip).map(select).collect()
+        };
+        let rows: [(String, Vec<f64>, egui::Color32, usize); 8] = [
+            (
+                "M".to_string(),
+                trend(|sample| sample.magnetization),
+                egui::Color32::LIGHT_GREEN,
+                4,
+            ),
+            (
+                "E".to_string(),
+                trend(|sample| sample.energy),
+                egui::Color32::LIGHT_RED,
+                4,
+            ),
+            (
+                "χ".to_string(),
+                self.susceptibility.iter().copied().collect(),
+                egui::Color32::LIGHT_BLUE,
+                4,
+            ),
+            (
+                t!("acceptance"),
+                trend(|sample| sample.acceptance),
+                egui::Color32::KHAKI,
+                3,
+            ),
+            (
+                "ξ".to_string(),
+                self.correlation_length.iter().copied().collect(),
+                egui::Color32::LIGHT_YELLOW,
+                3,
+            ),
+            (
+                t!("clusters"),
+                self.clusters.iter().copied().collect(),
+                egui::Color32::GRAY,
+                0,
+            ),
+            (
+                t!("largest-cluster"),
+                self.largest_cluster.iter().copied().collect(),
+                egui::Color32::GOLD,
+                3,
+            ),
+            (
+                t!("mean-cluster"),
+                self.mean_cluster.iter().copied().collect(),
+                egui::Color32::LIGHT_GRAY,
+                1,
+            ),
+        ];
+
+        egui::Grid::new("dashboard_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, values, color, decimals) in rows {
+                    ui.label(&name);
+                    match values.last() {
+                        Some(value) => ui.monospace(format!("{value:.decimals$}")),
+                        None => ui.monospace("-"),
+                    };
+                    sparkline(ui, &name, &values, color);
+                    ui.end_row();
+                }
+            });
+        ui.label(t!("dashboard-window", window = window));
+        ui.label(t!("dashboard-clusters"));
+    }
+}
+
+/// Append a value to a trend, dropping the oldest beyond the sparkline length
+fn push(trend: &mut VecDeque<f64>, value: f64) {
+    if trend.len() == SPARKLINE_LENGTH {
+        trend.pop_front();
+    }
+    trend.push_back(value);
+}
+
+/// Tiny line of the values scaled to their own range, without axes
+/// Screen readers announce the name with the number and range of the values
+fn sparkline(ui: &mut egui::Ui, name: &str, values: &[f64], color: egui::Color32) {
+    let (rect, response) = ui.allocate_exact_size(SPARKLINE_SIZE, egui::Sense::hover());
+    let (low, high) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+            (low.min(*value), high.max(*value))
+        });
+    let description = if values.is_empty() {
+        t!("a11y-no-trend")
+    } else {
+        t!(
+            "a11y-trend",
+            count = values.len(),
+            low = format!("{low:.3}"),
+            high = format!("{high:.3}")
+        )
+    };
+    a11y::describe_image(&response, name, description);
+    if values.len() < 2 {
+        return;
+    }
+    let range = if high > low { high - low } else { 1.0 };
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let x = index as f32 / (values.len() - 1) as f32;
+            let y = ((value - low) / range) as f32;
+            egui::pos2(
+                rect.left() + x * rect.width(),
+                rect.bottom() - y * rect.height(),
+            )
+        })
+        .collect();
+    ui.painter()
+        .add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+}
@@ -0,0 +1,89 @@
+use eframe::egui;
+use std::{
+    future::Future,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+/// Outcome of a file dialog finished in the background
+pub enum FileEvent {
+    /// a picked file with its name and content
+    Opened(String, Vec<u8>),
+    /// name of the written file
+    Saved(String),
+    /// why the file could not be written
+    Failed(String),
+}
+
+/// Run file dialogs without blocking the UI
+/// Natively the dialog runs on a thread, on the web the browser downloads and uploads files
+pub struct Files {
+    sender: Sender<FileEvent>,
+    receiver: Receiver<FileEvent>,
+}
+
+impl Default for Files {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+}
+
+impl Files {
+    /// Ask where to write bytes, suggesting file_name, and write them there
+    pub fn save(
+        &self,
+        ctx: &egui::Context,
+        filter: (&str, &[&str]),
+        file_name: &str,
+        bytes: Vec<u8>,
+    ) {
+        let dialog = rfd::AsyncFileDialog::new()
+            .add_filter(filter.0, filter.1)
+            .set_file_name(file_name);
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        spawn(async move {
+            let Some(file) = dialog.save_file().await else {
+                return;
+            };
+            let event = match file.write(&bytes).await {
+                Ok(()) => FileEvent::Saved(file.file_name()),
+                Err(e) => {
+                    FileEvent::Failed(format!("Failed to write {}. Error {e}", file.file_name()))
+                }
+            };
+            let _ = sender.send(event);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Ask for a file to read
+    pub fn open(&self, ctx: &egui::Context, filter: (&str, &[&str])) {
+        let dialog = rfd::AsyncFileDialog::new().add_filter(filter.0, filter.1);
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        spawn(async move {
+            let Some(file) = dialog.pick_file().await else {
+                return;
+            };
+            let bytes = file.read().await;
+            let _ = sender.send(FileEvent::Opened(file.file_name(), bytes));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Next finished file dialog, if any
+    pub fn poll(&self) -> Option<FileEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+    std::thread::spawn(move || pollster::block_on(future));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn(future: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(future);
+}
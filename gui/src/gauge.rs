@@ -0,0 +1,107 @@
+use crate::a11y;
+use crate::i18n::t;
+use crate::view::Colormap;
+use eframe::egui;
+
+/// Height of the gauge in points
+const GAUGE_HEIGHT: f32 = 24.0;
+
+/// Bar from -1 to +1 filled with the spin down to spin up gradient, marking the
+/// magnetization per spin, the order parameter, at a glance
+pub struct MagnetizationGauge {
+    magnetization: f64,
+    colormap: Colormap,
+}
+
+impl MagnetizationGauge {
+    /// Gauge of a magnetization per spin, clamped to -1..=1
+    pub fn new(magnetization: f64) -> Self {
+        Self {
+            magnetization: magnetization.clamp(-1.0, 1.0),
+            colormap: Colormap::default(),
+        }
+    }
+
+    /// Palette of the spin colors at both ends of the gauge
+    pub fn colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+}
+
+impl egui::Widget for MagnetizationGauge {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), GAUGE_HEIGHT),
+            egui::Sense::hover(),
+        );
+        let text = format!("M = {:+.3}", self.magnetization);
+        response.widget_info(|| {
+            egui::WidgetInfo::labeled(
+                egui::WidgetType::ProgressIndicator,
+                true,
+                t!("magnetization"),
+            )
+        });
+        a11y::describe(&response, t!("magnetization"), text.clone());
+        if !ui.is_rect_visible(rect) {
+            return response;
+        }
+
+        // Spin down on the left, a neutral gray at zero, and spin up on the right
+        let (up_color, down_color) = self.colormap.spin_colors(ui.visuals().dark_mode);
+        let middle = ui.visuals().widgets.inactive.bg_fill;
+        let mut mesh = egui::Mesh::default();
+        for (x, color) in [
+            (rect.left(), down_color),
+            (rect.center().x, middle),
+            (rect.right(), up_color),
+        ] {
+            mesh.colored_vertex(egui::pos2(x, rect.top()), color);
+            mesh.colored_vertex(egui::pos2(x, rect.bottom()), color);
+        }
+        for left in [0, 2] {
+            mesh.add_triangle(left, left + 1, left + 2);
+            mesh.add_triangle(left + 1, left + 2, left + 3);
+        }
+        let painter = ui.painter_at(rect);
+        painter.add(mesh);
+
+        let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+        painter.vline(rect.center().x, rect.y_range(), stroke);
+        let x = rect.center().x + self.magnetization as f32 * rect.width() / 2.0;
+        let marker = egui::Stroke::new(3.0, ui.visuals().strong_text_color());
+        painter.vline(x, rect.y_range(), marker);
+        let font = egui::TextStyle::Small.resolve(ui.style());
+        let color = ui.visuals().text_color();
+        let margin = egui::vec2(4.0, 0.0);
+        painter.text(
+            rect.left_center() + margin,
+            egui::Align2::LEFT_CENTER,
+            "-1",
+            font.clone(),
+            color,
+        );
+        painter.text(
+            rect.right_center() - margin,
+            egui::Align2::RIGHT_CENTER,
+            "+1",
+            font,
+            color,
+        );
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            text,
+            egui::TextStyle::Button.resolve(ui.style()),
+            ui.visuals().strong_text_color(),
+        );
+        ui.painter().rect_stroke(
+            rect,
+            2.0,
+            ui.visuals().widgets.noninteractive.bg_stroke,
+            egui::StrokeKind::Inside,
+        );
+        response
+    }
+}
@@ -0,0 +1,85 @@
+use internal::Lattice;
+
+/// Spin set by hand at x and y, with its value before and after the edit
+#[derive(Clone, Copy, Debug)]
+struct SpinEdit {
+    x: usize,
+    y: usize,
+    before: i32,
+    after: i32,
+}
+
+/// Undo and redo stacks of hand-painted strokes
+/// Monte Carlo flips are never recorded, undoing only restores the painted spins
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Vec<SpinEdit>>,
+    redo: Vec<Vec<SpinEdit>>,
+    // Edits of the stroke being painted
+    stroke: Vec<SpinEdit>,
+}
+
+impl History {
+    /// Set a spin as part of the running stroke, recording it when it changes
+    pub fn set_spin(&mut self, lattice: &mut Lattice, x: usize, y: usize, spin: i32) {
+        let before = lattice.value[y].value[x];
+        if before == spin {
+            return;
+        }
+        lattice.set_spin(x, y, spin);
+        self.stroke.push(SpinEdit {
+            x,
+            y,
+            before,
+            after: spin,
+        });
+    }
+
+    /// Close the running stroke so it is undone at once
+    pub fn finish_stroke(&mut self) {
+        if self.stroke.is_empty() {
+            return;
+        }
+        self.undo.push(std::mem::take(&mut self.stroke));
+        self.redo.clear();
+    }
+
+    /// Revert the latest stroke, return whether there was one
+    pub fn undo(&mut self, lattice: &mut Lattice) -> bool {
+        self.finish_stroke();
+        let Some(stroke) = self.undo.pop() else {
+            return false;
+        };
+        for edit in stroke.iter().rev() {
+            apply(lattice, edit.x, edit.y, edit.before);
+        }
+        self.redo.push(stroke);
+        true
+    }
+
+    /// Paint the latest undone stroke again, return whether there was one
+    pub fn redo(&mut self, lattice: &mut Lattice) -> bool {
+        let Some(stroke) = self.redo.pop() else {
+            return false;
+        };
+        for edit in &stroke {
+            apply(lattice, edit.x, edit.y, edit.after);
+        }
+        self.undo.push(stroke);
+        true
+    }
+
+    /// Forget every stroke, e.g. once the lattice is replaced
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.stroke.clear();
+    }
+}
+
+// Set a spin when it still falls inside the lattice, which may have shrunk since the edit
+fn apply(lattice: &mut Lattice, x: usize, y: usize, spin: i32) {
+    if x < lattice.size && y < lattice.size {
+        lattice.set_spin(x, y, spin);
+    }
+}
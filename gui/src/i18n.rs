@@ -0,0 +1,141 @@
+use fluent_bundle::{FluentArgs, FluentResource, concurrent::FluentBundle};
+use internal::{algorithm::Algorithm, boundary::Boundary};
+use std::sync::{OnceLock, RwLock};
+use unic_langid::{LanguageIdentifier, langid};
+
+/// Language the UI text is shown in, picked by the user
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::English);
+
+/// Translations of every language, in the order of Language::ALL
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// Languages the UI text is translated to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Indonesian,
+}
+
+impl Language {
+    /// Every language in display order
+    pub const ALL: [Language; 2] = [Language::English, Language::Indonesian];
+
+    /// Name of the language in itself
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Indonesian => "Bahasa Indonesia",
+        }
+    }
+
+    /// Unicode identifier of the language
+    fn id(self) -> LanguageIdentifier {
+        match self {
+            Language::English => langid!("en"),
+            Language::Indonesian => langid!("id"),
+        }
+    }
+
+    /// Fluent source of the translations
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.ftl"),
+            Language::Indonesian => include_str!("../locales/id.ftl"),
+        }
+    }
+}
+
+/// Show the UI text in language from the next frame on
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap_or_else(|e| e.into_inner()) = language;
+}
+
+/// Language the UI text is currently shown in
+pub fn language() -> Language {
+    *LANGUAGE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Parse the translations of every language once
+fn bundles() -> &'static [FluentBundle<FluentResource>] {
+    BUNDLES.get_or_init(|| {
+        Language::ALL
+            .iter()
+            .map(|language| {
+                let mut bundle = FluentBundle::new_concurrent(vec![language.id()]);
+                // Unicode isolation marks show up as boxes in the egui fonts
+                bundle.set_use_isolating(false);
+                let resource = FluentResource::try_new(language.source().to_string())
+                    .unwrap_or_else(|(resource, errors)| {
+                        eprintln!(
+                            "Failed to parse {} translations. Error {errors:?}",
+                            language.name()
+                        );
+                        resource
+                    });
+                // Duplicated messages keep their first translation
+                let _ = bundle.add_resource(resource);
+                bundle
+            })
+            .collect()
+    })
+}
+
+/// Text of the message id in the current language with its arguments filled in
+/// Fall back to English, then to the id itself, when the message is missing
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let bundles = bundles();
+    let current = Language::ALL
+        .iter()
+        .position(|language| *language == self::language())
+        .unwrap_or_default();
+    [current, 0]
+        .into_iter()
+        .find_map(|index| {
+            let bundle = &bundles[index];
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = Vec::new();
+            Some(
+                bundle
+                    .format_pattern(pattern, args, &mut errors)
+                    .into_owned(),
+            )
+        })
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Translated UI text of a message id, with optional named arguments
+/// ```ignore
+/// t!("sweeps-done", sweeps = progress.sweeps)
+/// ```
+macro_rules! t {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value.to_string());)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+
+pub(crate) use t;
+
+/// Name of the update algorithm in the UI language
+pub fn algorithm_label(algorithm: Algorithm) -> String {
+    match algorithm {
+        Algorithm::Metropolis => t!("algorithm-metropolis"),
+        Algorithm::HeatBath => t!("algorithm-heat-bath"),
+        Algorithm::Wolff => t!("algorithm-wolff"),
+    }
+}
+
+/// Name of the boundary condition in the UI language
+pub fn boundary_label(boundary: Boundary) -> String {
+    match boundary {
+        Boundary::Mirror => t!("boundary-mirror"),
+        Boundary::Open => t!("boundary-open"),
+        Boundary::Periodic => t!("boundary-periodic"),
+        Boundary::Fixed => t!("boundary-fixed"),
+    }
+}
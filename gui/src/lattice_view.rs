@@ -0,0 +1,379 @@
+use crate::a11y;
+use crate::history::History;
+use crate::i18n::t;
+use crate::view::{Colormap, ViewMode, render_lattice};
+use eframe::egui::{self, Pos2, Rect};
+use internal::{Lattice, boundary::Boundary};
+use web_time::Instant;
+
+/// Zoom, pan, and texture of a lattice view, kept between frames
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LatticeViewState {
+    /// magnification of the lattice, 1 shows the whole lattice
+    pub zoom: f32,
+    /// texture coordinate shown at the center of the view
+    pub center: Pos2,
+    // Texture the spins are uploaded to every frame, one pixel per spin
+    #[serde(skip)]
+    texture: Option<egui::TextureHandle>,
+}
+
+impl Default for LatticeViewState {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            center: Pos2::new(0.5, 0.5),
+            texture: None,
+        }
+    }
+}
+
+impl LatticeViewState {
+    /// Show the whole lattice again
+    pub fn reset(&mut self) {
+        self.zoom = 1.0;
+        self.center = Pos2::new(0.5, 0.5);
+    }
+
+    /// Follow the zoom and pan of another view
+    pub fn follow(&mut self, other: &LatticeViewState) {
+        self.zoom = other.zoom;
+        self.center = other.center;
+    }
+
+    /// Texture coordinates of the lattice shown in the view
+    fn uv(&self) -> Rect {
+        Rect::from_center_size(self.center, egui::Vec2::splat(1.0 / self.zoom))
+    }
+
+    /// Texture coordinate under a screen position of the view rect
+    fn to_uv(&self, pos: Pos2, rect: Rect) -> Pos2 {
+        let view = self.uv();
+        view.min + (pos - rect.min) / rect.size() * view.size()
+    }
+}
+
+/// What happened to a lattice view this frame
+pub struct LatticeViewResponse {
+    pub response: egui::Response,
+    /// spin under the pointer
+    pub hovered: Option<(usize, usize)>,
+    /// size of the largest cluster in the clusters view
+    pub largest_cluster: Option<usize>,
+    /// seconds spent coloring and uploading the texture
+    pub upload_time: f64,
+}
+
+/// Square egui widget drawing a lattice as a texture, with its boundary condition as a border
+/// Optionally zooms with the scroll wheel or a pinch, pans with a middle, ctrl, or two finger drag,
+/// and paints spins with a brush: left drag paints up, right drag paints down,
+/// and a one finger drag paints the touch spin
+pub struct LatticeView<'a> {
+    lattice: &'a mut Lattice,
+    state: &'a mut LatticeViewState,
+    mode: ViewMode,
+    colormap: Colormap,
+    side: f32,
+    navigable: bool,
+    brush: Option<(usize, &'a mut History)>,
+    touch_spin: i32,
+}
+
+impl<'a> LatticeView<'a> {
+    /// Create a view of the lattice, navigable and read-only, filling the available width
+    pub fn new(lattice: &'a mut Lattice, state: &'a mut LatticeViewState) -> Self {
+        Self {
+            lattice,
+            state,
+            mode: ViewMode::default(),
+            colormap: Colormap::default(),
+            side: f32::INFINITY,
+            navigable: true,
+            brush: None,
+            touch_spin: 1,
+        }
+    }
+
+    /// Color the sites with a view mode
+    pub fn mode(mut self, mode: ViewMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Palette of the sites and of the fixed boundary
+    pub fn colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    /// Side of the square view, capped by the available width
+    pub fn side(mut self, side: f32) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Whether scrolling and dragging zoom and pan the view
+    pub fn navigable(mut self, navigable: bool) -> Self {
+        self.navigable = navigable;
+        self
+    }
+
+    /// Paint square strokes of brush_size spins, recorded in the history
+    pub fn brush(mut self, brush_size: usize, history: &'a mut History) -> Self {
+        self.brush = Some((brush_size, history));
+        self
+    }
+
+    /// Spin painted by a one finger drag on touch screens, which have no right button
+    pub fn touch_spin(mut self, spin: i32) -> Self {
+        self.touch_spin = spin;
+        self
+    }
+
+    /// Draw the lattice and handle the pointer
+    pub fn show(mut self, ui: &mut egui::Ui) -> LatticeViewResponse {
+        let side = self.side.min(ui.available_width()).max(0.0);
+        let (rect, response) =
+            ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::click_and_drag());
+        let description = t!(
+            "a11y-lattice-description",
+            size = self.lattice.size,
+            magnetization = format!("{:+.2}", self.lattice.magnetization())
+        );
+        a11y::describe_image(&response, t!("a11y-lattice"), description);
+        if self.navigable {
+            self.zoom_and_pan(ui, &response, rect);
+        }
+
+        // Paint before uploading the texture so the stroke shows in the same frame
+        if let Some((brush_size, history)) = &mut self.brush {
+            if let Some((x, y)) = response
+                .interact_pointer_pos()
+                .and_then(|pos| spin_at(self.lattice, self.state, pos, rect))
+            {
+                let (primary, secondary, command, touching, multi_touch) = ui.input(|i| {
+                    (
+                        i.pointer.primary_down(),
+                        i.pointer.secondary_down(),
+                        i.modifiers.command,
+                        i.any_touches(),
+                        i.multi_touch().is_some(),
+                    )
+                });
+                // A ctrl or two finger drag pans instead of painting
+                let spin = match (primary, secondary) {
+                    _ if multi_touch => None,
+                    (true, _) if touching => Some(self.touch_spin),
+                    (true, _) if !command => Some(1),
+                    (_, true) => Some(-1),
+                    _ => None,
+                };
+                if let Some(spin) = spin {
+                    paint(self.lattice, history, *brush_size, x, y, spin);
+                }
+            }
+            // A stroke lasts until every button is released
+            if !ui.input(|i| i.pointer.any_down()) {
+                history.finish_stroke();
+            }
+        }
+
+        let upload_start = Instant::now();
+        let (image, largest_cluster) = render_lattice(
+            self.lattice,
+            self.mode,
+            self.colormap,
+            ui.visuals().dark_mode,
+        );
+        let texture = self.state.texture.get_or_insert_with(|| {
+            ui.ctx().load_texture(
+                "lattice",
+                egui::ColorImage::default(),
+                egui::TextureOptions::NEAREST,
+            )
+        });
+        texture.set(image, egui::TextureOptions::NEAREST);
+        let upload_time = upload_start.elapsed().as_secs_f64();
+        ui.painter()
+            .image(texture.id(), rect, self.state.uv(), egui::Color32::WHITE);
+        draw_boundary(ui, rect, self.lattice.boundary, self.colormap);
+
+        let hovered = response
+            .hover_pos()
+            .and_then(|pos| spin_at(self.lattice, self.state, pos, rect));
+        if let Some((x, y)) = hovered {
+            self.highlight_neighbours(ui, x, y, rect);
+        }
+        LatticeViewResponse {
+            response,
+            hovered,
+            largest_cluster,
+            upload_time,
+        }
+    }
+
+    /// Zoom with the scroll wheel around the pointer or with a pinch around the fingers,
+    /// and pan with a middle, ctrl, or two finger drag
+    fn zoom_and_pan(&mut self, ui: &egui::Ui, response: &egui::Response, rect: Rect) {
+        let state = &mut *self.state;
+        let max_zoom = self.lattice.size.max(1) as f32;
+        let (scroll, pinch, multi_touch) =
+            ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta(), i.multi_touch()));
+        let multi_touch = multi_touch.filter(|touch| rect.contains(touch.center_pos));
+        let focus = multi_touch
+            .map(|touch| touch.center_pos)
+            .or(response.hover_pos())
+            .filter(|pos| rect.contains(*pos));
+        if let Some(pos) = focus {
+            let factor = (scroll * 0.005).exp() * pinch;
+            if factor != 1.0 {
+                // Keep the spin under the pointer or between the fingers in place
+                let anchor = state.to_uv(pos, rect);
+                let zoom = (state.zoom * factor).clamp(1.0, max_zoom);
+                state.center = anchor + (state.center - anchor) * (state.zoom / zoom);
+                state.zoom = zoom;
+            }
+        }
+        let panning = ui.input(|i| {
+            i.pointer.middle_down() || (i.modifiers.command && i.pointer.primary_down())
+        });
+        if panning && response.dragged() {
+            state.center -= response.drag_delta() / rect.size() / state.zoom;
+        }
+        if let Some(touch) = multi_touch {
+            state.center -= touch.translation_delta / rect.size() / state.zoom;
+        }
+        // Keep the view inside the lattice
+        state.zoom = state.zoom.clamp(1.0, max_zoom);
+        let half = 0.5 / state.zoom;
+        state.center = Pos2::new(
+            state.center.x.clamp(half, 1.0 - half),
+            state.center.y.clamp(half, 1.0 - half),
+        );
+    }
+
+    /// Outline the hovered tile and its nearest neighbours inside the lattice
+    fn highlight_neighbours(&self, ui: &egui::Ui, x: usize, y: usize, rect: Rect) {
+        let stroke_color = ui.visuals().strong_text_color();
+        // Outlines of tiles partly outside a zoomed view stay inside the canvas
+        let painter = ui.painter_at(rect);
+        painter.rect_stroke(
+            self.tile_rect(x, y, rect),
+            0.0,
+            egui::Stroke::new(2.0, stroke_color),
+            egui::StrokeKind::Inside,
+        );
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            if let Some((nx, ny)) = self.lattice.neighbour_point(x, y, dx, dy) {
+                painter.rect_stroke(
+                    self.tile_rect(nx, ny, rect),
+                    0.0,
+                    egui::Stroke::new(1.0, stroke_color),
+                    egui::StrokeKind::Inside,
+                );
+            }
+        }
+    }
+
+    /// Screen rectangle of the tile at x and y inside the view rect
+    fn tile_rect(&self, x: usize, y: usize, rect: Rect) -> Rect {
+        let view = self.state.uv();
+        let tile = 1.0 / self.lattice.size as f32;
+        let to_screen = |uv: Pos2| rect.min + (uv - view.min) / view.size() * rect.size();
+        Rect::from_min_max(
+            to_screen(Pos2::new(x as f32 * tile, y as f32 * tile)),
+            to_screen(Pos2::new((x + 1) as f32 * tile, (y + 1) as f32 * tile)),
+        )
+    }
+}
+
+/// Spin under pos on the lattice drawn in rect
+fn spin_at(
+    lattice: &Lattice,
+    state: &LatticeViewState,
+    pos: Pos2,
+    rect: Rect,
+) -> Option<(usize, usize)> {
+    if !rect.contains(pos) || lattice.size == 0 {
+        return None;
+    }
+    let uv = state.to_uv(pos, rect);
+    let to_spin = |value: f32| ((value * lattice.size as f32) as usize).min(lattice.size - 1);
+    Some((to_spin(uv.x), to_spin(uv.y)))
+}
+
+/// Set every spin of the brush square centered on x and y
+fn paint(
+    lattice: &mut Lattice,
+    history: &mut History,
+    brush_size: usize,
+    x: usize,
+    y: usize,
+    spin: i32,
+) {
+    let half = brush_size / 2;
+    let last = lattice.size - 1;
+    for py in y.saturating_sub(half)..=(y + brush_size - 1 - half).min(last) {
+        for px in x.saturating_sub(half)..=(x + brush_size - 1 - half).min(last) {
+            history.set_spin(lattice, px, py, spin);
+        }
+    }
+}
+
+/// Border drawn around a lattice showing its boundary condition
+/// Periodic edges are dashed as they continue on the other side, fixed edges take the spin up color,
+/// mirror edges are doubled and open edges are a thin gray line
+fn draw_boundary(ui: &egui::Ui, rect: Rect, boundary: Boundary, colormap: Colormap) {
+    let painter = ui.painter();
+    let border = rect.expand(2.0);
+    let color = ui.visuals().strong_text_color();
+    match boundary {
+        Boundary::Periodic => {
+            let corners = [
+                border.left_top(),
+                border.right_top(),
+                border.right_bottom(),
+                border.left_bottom(),
+                border.left_top(),
+            ];
+            painter.extend(egui::Shape::dashed_line(
+                &corners,
+                egui::Stroke::new(1.5, color),
+                6.0,
+                4.0,
+            ));
+        }
+        Boundary::Fixed => {
+            let (up_color, _) = colormap.spin_colors(ui.visuals().dark_mode);
+            painter.rect_stroke(
+                border,
+                0.0,
+                egui::Stroke::new(3.0, up_color),
+                egui::StrokeKind::Outside,
+            );
+        }
+        Boundary::Open => {
+            painter.rect_stroke(
+                border,
+                0.0,
+                egui::Stroke::new(1.0, egui::Color32::GRAY),
+                egui::StrokeKind::Outside,
+            );
+        }
+        Boundary::Mirror => {
+            painter.rect_stroke(
+                border,
+                0.0,
+                egui::Stroke::new(1.0, color),
+                egui::StrokeKind::Outside,
+            );
+            painter.rect_stroke(
+                border.expand(3.0),
+                0.0,
+                egui::Stroke::new(1.0, color),
+                egui::StrokeKind::Outside,
+            );
+        }
+    }
+}
@@ -1,2 +1,24 @@
+mod a11y;
 mod app;
+mod dashboard;
+mod files;
+mod gauge;
+mod history;
+mod i18n;
+mod lattice_view;
+mod performance;
+mod permalink;
+mod phase_diagram;
+mod scan;
+mod simulation;
+mod state;
+mod timeline;
+mod view;
 pub use app::App;
+pub use gauge::MagnetizationGauge;
+pub use history::History;
+pub use i18n::Language;
+pub use lattice_view::{LatticeView, LatticeViewResponse, LatticeViewState};
+pub use permalink::Permalink;
+pub use simulation::Simulation;
+pub use view::{Colormap, ViewMode};
@@ -0,0 +1,97 @@
+use crate::i18n::t;
+use eframe::egui;
+
+/// Seconds over which sweeps and flips per second are counted
+const RATE_WINDOW: f64 = 1.0;
+
+/// Weight of the latest frame in the smoothed timings
+const SMOOTHING: f64 = 0.1;
+
+/// Throughput and timings of a simulation, shown in an overlay over the canvas
+#[derive(Default)]
+pub struct Performance {
+    /// sweeps per second over the latest rate window
+    sweeps_per_second: f64,
+    /// accepted spin flips per second over the latest rate window
+    flips_per_second: f64,
+    /// smoothed seconds between two frames
+    frame_time: f64,
+    /// smoothed seconds spent running the steps of a frame
+    step_time: f64,
+    /// smoothed seconds spent coloring and uploading the lattice texture of a frame
+    upload_time: f64,
+    /// time, sweeps, and accepted flips when the rate window started
+    window_start: Option<(f64, u64, u64)>,
+}
+
+impl Performance {
+    /// Count the sweeps and accepted flips done so far at time, in seconds
+    /// The rates are updated once per rate window
+    pub fn record_progress(&mut self, time: f64, sweeps: u64, flips: u64) {
+        let Some((start, start_sweeps, start_flips)) = self.window_start else {
+            self.window_start = Some((time, sweeps, flips));
+            return;
+        };
+        let elapsed = time - start;
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        // Counters reset by the user restart the window
+        self.sweeps_per_second = sweeps.saturating_sub(start_sweeps) as f64 / elapsed;
+        self.flips_per_second = flips.saturating_sub(start_flips) as f64 / elapsed;
+        self.window_start = Some((time, sweeps, flips));
+    }
+
+    /// Take the seconds since the previous frame
+    pub fn record_frame(&mut self, seconds: f64) {
+        smooth(&mut self.frame_time, seconds);
+    }
+
+    /// Take the seconds spent running the steps of this frame
+    pub fn record_step(&mut self, seconds: f64) {
+        smooth(&mut self.step_time, seconds);
+    }
+
+    /// Take the seconds spent coloring and uploading the lattice texture of this frame
+    pub fn record_upload(&mut self, seconds: f64) {
+        smooth(&mut self.upload_time, seconds);
+    }
+
+    /// Rates and timings, one per row
+    pub fn show(&self, ui: &mut egui::Ui) {
+        let fps = if self.frame_time > 0.0 {
+            1.0 / self.frame_time
+        } else {
+            0.0
+        };
+        egui::Grid::new("performance_grid").show(ui, |ui| {
+            let mut row = |name: String, value: String| {
+                ui.label(name);
+                ui.monospace(value);
+                ui.end_row();
+            };
+            row(
+                t!("flips-per-second"),
+                format!("{:>12.0}", self.flips_per_second),
+            );
+            row(
+                t!("sweeps-per-second"),
+                format!("{:>12.1}", self.sweeps_per_second),
+            );
+            row(
+                t!("frame"),
+                format!("{:>9.2} ms ({fps:.0} fps)", 1000.0 * self.frame_time),
+            );
+            row(t!("steps"), format!("{:>9.2} ms", 1000.0 * self.step_time));
+            row(
+                t!("upload"),
+                format!("{:>9.2} ms", 1000.0 * self.upload_time),
+            );
+        });
+    }
+}
+
+/// Move an exponential moving average towards the latest value
+fn smooth(average: &mut f64, value: f64) {
+    *average += SMOOTHING * (value - *average);
+}
@@ -0,0 +1,80 @@
+/// Name of the meta tag the web server puts the parameters of a shared link in
+#[cfg(target_arch = "wasm32")]
+const META_NAME: &str = "ising-permalink";
+
+/// Name of the meta tag the web server marks pages embedded in other sites with
+#[cfg(target_arch = "wasm32")]
+const EMBED_META_NAME: &str = "ising-embed";
+
+/// Simulation parameters of a shared link like `/?size=100&t=2.27&j=1&seed=42`,
+/// in the units of the presets: temperature and field reduced by |J|, J by the preset one
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Permalink {
+    /// lattice side
+    pub size: Option<usize>,
+    /// reduced temperature k_B T / |J|
+    pub t: Option<f64>,
+    /// interaction strength in units of the presets' J, negative for an antiferromagnet
+    pub j: Option<f64>,
+    /// reduced external field h / |J|
+    pub h: Option<f64>,
+    /// seed of the random spins and updates
+    pub seed: Option<u64>,
+}
+
+impl Permalink {
+    /// Parameters of a query string, skipping unknown, malformed, and non-finite ones
+    pub fn parse(query: &str) -> Self {
+        let mut permalink = Self::default();
+        for pair in query.trim_start_matches('?').split('&') {
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let number = || value.parse().ok().filter(|value: &f64| value.is_finite());
+            match name {
+                "size" => permalink.size = value.parse().ok(),
+                "t" => permalink.t = number(),
+                "j" => permalink.j = number(),
+                "h" => permalink.h = number(),
+                "seed" => permalink.seed = value.parse().ok(),
+                _ => {}
+            }
+        }
+        permalink
+    }
+
+    /// Parameters the web server put in the served page, None without any
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_page() -> Option<Self> {
+        Some(Self::parse(&meta_content(META_NAME)?))
+    }
+
+    /// Parameters the web server put in the served page, never any natively
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_page() -> Option<Self> {
+        None
+    }
+}
+
+/// Whether the web server served the page to be embedded in another site,
+/// showing the lattice alone
+#[cfg(target_arch = "wasm32")]
+pub fn is_embedded() -> bool {
+    meta_content(EMBED_META_NAME).is_some()
+}
+
+/// Whether the web server served the page to be embedded, never natively
+#[cfg(not(target_arch = "wasm32"))]
+pub fn is_embedded() -> bool {
+    false
+}
+
+/// Content of the meta tag named name in the served page
+#[cfg(target_arch = "wasm32")]
+fn meta_content(name: &str) -> Option<String> {
+    web_sys::window()?
+        .document()?
+        .query_selector(&format!("meta[name=\"{name}\"]"))
+        .ok()??
+        .get_attribute("content")
+}
@@ -0,0 +1,95 @@
+use crate::lattice_view::LatticeViewState;
+use internal::{Lattice, algorithm::Algorithm};
+
+/// Number of temperatures and fields sampled by the phase diagram
+pub const PHASE_GRID: usize = 6;
+
+/// Side of the small lattices evolved in every cell
+const PHASE_LATTICE_SIZE: usize = 32;
+
+/// Sweeps every cell runs before the phase diagram is done
+pub const PHASE_SWEEPS: usize = 300;
+
+/// Spin updates run per frame over all cells, so the GUI stays responsive
+const PHASE_SPINS_PER_FRAME: usize = 200_000;
+
+/// Lowest and highest reduced temperature k_B T / J of the columns
+const REDUCED_TEMPERATURES: (f64, f64) = (0.5, 4.0);
+
+/// Lowest and highest reduced field h / J of the rows
+const REDUCED_FIELDS: (f64, f64) = (-1.0, 1.0);
+
+/// Small lattice evolved at one point of the (T, h) grid
+pub struct PhaseCell {
+    /// reduced temperature k_B T / J of the cell
+    pub reduced_temperature: f64,
+    /// reduced field h / J of the cell
+    pub reduced_field: f64,
+    pub lattice: Lattice,
+    /// canvas of the thumbnail
+    pub view: LatticeViewState,
+}
+
+/// Grid of lattices across temperatures and fields, evolved a few frames at a time
+/// Columns go up in temperature from the left, rows go up in field from the bottom
+pub struct PhaseDiagram {
+    /// cells row by row, highest field first
+    pub cells: Vec<PhaseCell>,
+    /// sweeps run by every cell so far
+    pub sweeps: usize,
+}
+
+impl PhaseDiagram {
+    /// Create the grid from the interactivity, boundary, and seed of a lattice
+    /// Every cell starts from the same random spins
+    pub fn new(base: &Lattice, fallback_interactivity: f64) -> Self {
+        let interactivity = if base.interactivity == 0.0 {
+            fallback_interactivity
+        } else {
+            base.interactivity
+        };
+        let step = |(low, high): (f64, f64), index: usize| {
+            low + (high - low) * index as f64 / (PHASE_GRID - 1) as f64
+        };
+        let mut cells = Vec::with_capacity(PHASE_GRID * PHASE_GRID);
+        for row in 0..PHASE_GRID {
+            let reduced_field = step(REDUCED_FIELDS, PHASE_GRID - 1 - row);
+            for column in 0..PHASE_GRID {
+                let reduced_temperature = step(REDUCED_TEMPERATURES, column);
+                let mut lattice =
+                    Lattice::with_seed(PHASE_LATTICE_SIZE, interactivity, 0.0, base.seed);
+                lattice.boundary = base.boundary;
+                lattice.set_reduced_temperature(reduced_temperature);
+                lattice.field = reduced_field * interactivity.abs();
+                cells.push(PhaseCell {
+                    reduced_temperature,
+                    reduced_field,
+                    lattice,
+                    view: LatticeViewState::default(),
+                });
+            }
+        }
+        Self { cells, sweeps: 0 }
+    }
+
+    /// Whether every cell ran all of its sweeps
+    pub fn is_done(&self) -> bool {
+        self.sweeps >= PHASE_SWEEPS
+    }
+
+    /// Run the sweeps of one frame on every cell
+    /// Heat-bath sweeps sample the Boltzmann distribution at every temperature
+    pub fn advance(&mut self) {
+        let spins_per_round = self.cells.len() * PHASE_LATTICE_SIZE * PHASE_LATTICE_SIZE;
+        let rounds = (PHASE_SPINS_PER_FRAME / spins_per_round).max(1);
+        for _ in 0..rounds {
+            if self.is_done() {
+                return;
+            }
+            for cell in &mut self.cells {
+                cell.lattice.sweep_with(Algorithm::HeatBath, |_, _| {});
+            }
+            self.sweeps += 1;
+        }
+    }
+}
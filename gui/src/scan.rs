@@ -0,0 +1,286 @@
+use crate::i18n::{self, t};
+use eframe::egui;
+use egui_plot::{Line, Plot, Points};
+use internal::{
+    Lattice,
+    algorithm::Algorithm,
+    scan::{self, ScanPoint},
+};
+use std::sync::mpsc::{self, Receiver};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Temperature range and sweeps of a scan, in reduced units k_B T / J
+#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ScanSettings {
+    /// first reduced temperature
+    pub start: f64,
+    /// last reduced temperature
+    pub end: f64,
+    /// number of temperatures between start and end, both included
+    pub steps: usize,
+    /// sweeps run before measuring at every temperature
+    pub equilibration: usize,
+    /// sweeps averaged at every temperature
+    pub sweeps: usize,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            start: 1.5,
+            end: 3.5,
+            steps: 11,
+            equilibration: 200,
+            sweeps: 500,
+        }
+    }
+}
+
+/// Observable shown in a column of the results table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanColumn {
+    Temperature,
+    Magnetization,
+    Susceptibility,
+    HeatCapacity,
+}
+
+impl ScanColumn {
+    /// Every column in display order
+    pub const ALL: [ScanColumn; 4] = [
+        ScanColumn::Temperature,
+        ScanColumn::Magnetization,
+        ScanColumn::Susceptibility,
+        ScanColumn::HeatCapacity,
+    ];
+
+    /// Header of the column
+    pub fn name(self) -> &'static str {
+        match self {
+            ScanColumn::Temperature => "k_B T / J",
+            ScanColumn::Magnetization => "<|M|>",
+            ScanColumn::Susceptibility => "χ",
+            ScanColumn::HeatCapacity => "C",
+        }
+    }
+
+    /// Value of the column in a scan point
+    pub fn value(self, point: &ScanPoint) -> f64 {
+        match self {
+            ScanColumn::Temperature => point.reduced_temperature,
+            ScanColumn::Magnetization => point.magnetization,
+            ScanColumn::Susceptibility => point.susceptibility,
+            ScanColumn::HeatCapacity => point.heat_capacity,
+        }
+    }
+}
+
+/// Temperature scan measured in the background
+/// Natively every core runs a worker thread, on the web one temperature is measured per frame
+pub struct Scan {
+    /// measured points, in the order they finished
+    pub points: Vec<ScanPoint>,
+    /// number of temperatures of the scan
+    pub total: usize,
+    /// column the table is sorted by, and whether it is descending
+    pub sort: (ScanColumn, bool),
+    /// observable plotted against temperature
+    pub plotted: ScanColumn,
+    receiver: Receiver<ScanPoint>,
+    #[cfg(not(target_arch = "wasm32"))]
+    cancelled: Arc<AtomicBool>,
+    #[cfg(target_arch = "wasm32")]
+    jobs: Vec<Lattice>,
+    #[cfg(target_arch = "wasm32")]
+    sender: mpsc::Sender<ScanPoint>,
+    algorithm: Algorithm,
+    settings: ScanSettings,
+}
+
+impl Scan {
+    /// Start measuring every temperature of the settings on a copy of the lattice
+    pub fn start(
+        ctx: &egui::Context,
+        base: &Lattice,
+        algorithm: Algorithm,
+        settings: ScanSettings,
+    ) -> Self {
+        let jobs: Vec<Lattice> = scan::temperatures(settings.start, settings.end, settings.steps)
+            .into_iter()
+            .map(|reduced_temperature| {
+                let mut lattice = base.clone();
+                lattice.set_reduced_temperature(reduced_temperature);
+                lattice
+            })
+            .collect();
+        let (sender, receiver) = mpsc::channel();
+        let total = jobs.len();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let cancelled = {
+            let cancelled = Arc::new(AtomicBool::new(false));
+            let jobs = Arc::new(Mutex::new(jobs));
+            let workers = std::thread::available_parallelism()
+                .map_or(1, |n| n.get())
+                .min(total);
+            for _ in 0..workers {
+                let (jobs, sender, cancelled) = (jobs.clone(), sender.clone(), cancelled.clone());
+                let ctx = ctx.clone();
+                std::thread::spawn(move || {
+                    while !cancelled.load(Ordering::Relaxed) {
+                        let Some(mut lattice) = jobs.lock().ok().and_then(|mut jobs| jobs.pop())
+                        else {
+                            return;
+                        };
+                        let point = scan::measure(
+                            &mut lattice,
+                            algorithm,
+                            settings.equilibration,
+                            settings.sweeps,
+                        );
+                        if sender.send(point).is_err() {
+                            return;
+                        }
+                        ctx.request_repaint();
+                    }
+                });
+            }
+            cancelled
+        };
+        #[cfg(target_arch = "wasm32")]
+        let _ = ctx;
+
+        Self {
+            points: Vec::with_capacity(total),
+            total,
+            sort: (ScanColumn::Temperature, false),
+            plotted: ScanColumn::Magnetization,
+            receiver,
+            #[cfg(not(target_arch = "wasm32"))]
+            cancelled,
+            #[cfg(target_arch = "wasm32")]
+            jobs,
+            #[cfg(target_arch = "wasm32")]
+            sender,
+            algorithm,
+            settings,
+        }
+    }
+
+    /// Collect the points measured since the last frame
+    pub fn poll(&mut self) {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(mut lattice) = self.jobs.pop() {
+            let point = scan::measure(
+                &mut lattice,
+                self.algorithm,
+                self.settings.equilibration,
+                self.settings.sweeps,
+            );
+            let _ = self.sender.send(point);
+        }
+        self.points.extend(self.receiver.try_iter());
+    }
+
+    /// Whether every temperature was measured
+    pub fn is_done(&self) -> bool {
+        self.points.len() >= self.total
+    }
+
+    /// Stop measuring the remaining temperatures
+    pub fn cancel(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.cancelled.store(true, Ordering::Relaxed);
+        #[cfg(target_arch = "wasm32")]
+        self.jobs.clear();
+        self.total = self.points.len();
+    }
+
+    /// Settings and algorithm the scan runs with
+    pub fn describe(&self) -> String {
+        t!(
+            "scan-description",
+            sweeps = self.settings.sweeps,
+            equilibration = self.settings.equilibration,
+            algorithm = i18n::algorithm_label(self.algorithm)
+        )
+    }
+
+    /// Measured points as CSV, sorted by temperature
+    pub fn to_csv(&self) -> std::io::Result<Vec<u8>> {
+        let mut points = self.points.clone();
+        points.sort_by(|a, b| a.reduced_temperature.total_cmp(&b.reduced_temperature));
+        let mut out = Vec::new();
+        scan::write_csv(&points, &mut out)?;
+        Ok(out)
+    }
+
+    /// Plot of the chosen observable against temperature
+    pub fn plot(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for column in &ScanColumn::ALL[1..] {
+                ui.radio_value(&mut self.plotted, *column, column.name());
+            }
+        });
+        let mut points: Vec<[f64; 2]> = self
+            .points
+            .iter()
+            .map(|point| [point.reduced_temperature, self.plotted.value(point)])
+            .collect();
+        points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        let name = self.plotted.name();
+        Plot::new("scan_plot")
+            .height(160.0)
+            .x_axis_label("k_B T / J")
+            .y_axis_label(name)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(name, points.clone()));
+                plot_ui.points(Points::new(name, points).radius(3.0));
+            });
+    }
+
+    /// Results table, clicking a header sorts by its column and clicking it again reverses
+    pub fn table(&mut self, ui: &mut egui::Ui) {
+        let (column, descending) = self.sort;
+        self.points.sort_by(|a, b| {
+            let order = column.value(a).total_cmp(&column.value(b));
+            if descending { order.reverse() } else { order }
+        });
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("scan_table").striped(true).show(ui, |ui| {
+                    for header in ScanColumn::ALL {
+                        let arrow = match (header == column, descending) {
+                            (true, false) => " ⏶",
+                            (true, true) => " ⏷",
+                            (false, _) => "",
+                        };
+                        if ui.button(format!("{}{arrow}", header.name())).clicked() {
+                            self.sort = (header, header == column && !descending);
+                        }
+                    }
+                    ui.end_row();
+                    for point in &self.points {
+                        for column in ScanColumn::ALL {
+                            ui.label(format!("{:.4}", column.value(point)));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for Scan {
+    // Workers of a closed scan stop after their current temperature
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
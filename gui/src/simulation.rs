@@ -0,0 +1,1770 @@
+use crate::a11y;
+use crate::dashboard::Dashboard;
+use crate::files::{FileEvent, Files};
+use crate::gauge::MagnetizationGauge;
+use crate::history::History;
+use crate::i18n::{self, t};
+use crate::lattice_view::{LatticeView, LatticeViewState};
+use crate::performance::Performance;
+use crate::permalink::Permalink;
+use crate::phase_diagram::{PHASE_GRID, PHASE_SWEEPS, PhaseDiagram};
+use crate::scan::{Scan, ScanSettings};
+use crate::timeline::Timeline;
+use crate::view::{Colormap, ViewMode, render_structure_factor};
+use eframe::egui;
+use egui_plot::{Bar, BarChart, Line, Plot, VLine};
+use internal::{
+    KB, Lattice,
+    algorithm::Algorithm,
+    animation::Animation,
+    boundary::Boundary,
+    correlation::correlation_length,
+    export,
+    recorder::{Recorder, Sample},
+    snapshot::{SNAPSHOT_EXTENSION, Snapshot},
+};
+use std::time::Duration;
+use web_time::Instant;
+
+/// Memory the frames of a GIF recording may take before the recording stops
+const GIF_MAX_BYTES: usize = 128 * 1024 * 1024;
+
+/// Viewport width below which the side panels collapse behind toggle buttons
+const NARROW_WIDTH: f32 = 700.0;
+
+/// Number of latest sweeps kept for the observables charts
+const OBSERVABLES_CAPACITY: usize = 2000;
+
+/// Interaction strength set by presets, 1000 k_B so reduced temperatures read as thousands of Kelvin
+const PRESET_INTERACTIVITY: f64 = 1000.0 * KB;
+
+/// One-click physical regimes: message id of the name, reduced temperature k_B T / J, and sweeps run per frame
+const PRESETS: [(&str, f64, f64); 3] = [
+    ("preset-ordered", 1.0, 1.0),
+    // Critical slowing down makes domains evolve slowly, so run faster
+    ("preset-critical", 2.269, 2.0),
+    ("preset-disordered", 5.0, 0.2),
+];
+
+/// Seconds a toast stays over the canvas once shown
+const TOAST_SECONDS: f64 = 4.0;
+
+/// Short notice shown over the bottom of the canvas
+struct Toast {
+    /// text of the notice
+    text: String,
+    /// time the notice was first shown, None until the tab is shown
+    shown_at: Option<f64>,
+}
+
+/// Simulation progress shown in the left panel, reset independently of the lattice
+#[derive(Default)]
+struct Progress {
+    /// completed sweeps
+    sweeps: u64,
+    /// attempted spin flips
+    attempted: u64,
+    /// accepted spin flips
+    accepted: u64,
+    /// seconds spent running the simulation
+    running: f64,
+}
+
+/// One independent simulation shown in a tab, with its own lattice and parameters
+/// We derive Deserialize/Serialize so we can persist app state on shutdown.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct Simulation {
+    /// name shown on the tab
+    pub name: String,
+    pub lattice: Lattice,
+    pub is_paused: bool,
+    /// number of latest sweeps shown in the observables charts
+    pub plot_window: usize,
+    /// side of the square of spins set by painting on the lattice
+    pub brush_size: usize,
+    /// spin painted by a one finger drag on touch screens
+    pub touch_spin: i32,
+    /// whether the dashboard panel is open
+    pub show_dashboard: bool,
+    /// whether the performance overlay is drawn over the canvas
+    pub show_performance: bool,
+    /// whether the structure factor canvas is drawn next to the lattice
+    pub show_structure_factor: bool,
+    /// number of sweeps between two structure factor updates
+    pub structure_factor_every: usize,
+    /// pixels per spin side of exported PNG images
+    pub export_scale: usize,
+    /// number of sweeps between two frames of a GIF recording
+    pub gif_every: usize,
+    /// frames shown per second in recorded GIFs
+    pub gif_frame_rate: f64,
+    /// pixels per spin side of recorded GIFs
+    pub gif_scale: usize,
+    /// number of site update steps run every frame
+    pub steps_per_frame: usize,
+    /// zoom and pan of the lattice canvas
+    pub lattice_view: LatticeViewState,
+    /// how the lattice canvas colors its sites
+    pub view: ViewMode,
+    /// palette of the lattice canvas in every view
+    pub colormap: Colormap,
+    /// temperatures and sweeps of the temperature scan
+    pub scan_settings: ScanSettings,
+    /// Monte Carlo update running the simulation
+    pub algorithm: Algorithm,
+    /// number of sweeps between two correlation function updates, 0 to only compute on demand
+    pub correlation_every: usize,
+    /// number of sweeps between two timeline checkpoints, 0 to keep none
+    pub checkpoint_every: usize,
+    /// number of latest checkpoints kept by the timeline
+    pub checkpoint_depth: usize,
+    /// whether the simulation pauses once the energy settles
+    pub auto_pause: bool,
+    /// number of sweeps in each of the two windows compared to detect equilibrium
+    pub equilibration_window: usize,
+    /// number of latest sweeps sampled by the histograms
+    pub histogram_window: usize,
+    /// number of bins of the histograms
+    pub histogram_bins: usize,
+    /// latest spin-spin correlation function C(r)
+    #[serde(skip)]
+    pub correlation: Vec<f64>,
+    #[serde(skip)]
+    pub animation: Option<Animation>,
+    #[serde(skip)]
+    pub recorder: Recorder,
+    // Metropolis steps and flips of the running sweep
+    #[serde(skip)]
+    steps: usize,
+    #[serde(skip)]
+    flipped: usize,
+    #[serde(skip)]
+    progress: Progress,
+    #[serde(skip)]
+    history: History,
+    // Trends of the dashboard observables, only taken while the dashboard is open
+    #[serde(skip)]
+    dashboard: Dashboard,
+    #[serde(skip)]
+    performance: Performance,
+    // Checkpoints the scrubber goes back to, kept in memory only
+    #[serde(skip)]
+    timeline: Timeline,
+    // Temperature, interactivity, and field the energy last settled at, restarting the
+    // detection when they change
+    #[serde(skip)]
+    equilibrated: Option<[f64; 3]>,
+    // Temperature, interactivity, and field with the recorded sweep before they were set,
+    // the detection only testing the samples taken since
+    #[serde(skip)]
+    parameters_since: Option<([f64; 3], u64)>,
+    #[serde(skip)]
+    toast: Option<Toast>,
+    // Latest structure factor S(k) and its canvas texture
+    #[serde(skip)]
+    structure_factor: Vec<Vec<f64>>,
+    #[serde(skip)]
+    structure_factor_texture: Option<egui::TextureHandle>,
+    // Size of the largest cluster found when rendering the clusters view
+    #[serde(skip)]
+    largest_cluster: Option<usize>,
+    // Phase diagram explorer shown in its own window while open
+    #[serde(skip)]
+    phase_diagram: Option<PhaseDiagram>,
+    // Temperature scan window, with the scan once started
+    #[serde(skip)]
+    show_scan: bool,
+    #[serde(skip)]
+    scan: Option<Scan>,
+    // Whether the left and right panels are open on a narrow viewport
+    #[serde(skip)]
+    show_controls: bool,
+    #[serde(skip)]
+    show_observables: bool,
+    // Seed typed in the left panel
+    #[serde(skip)]
+    seed_input: String,
+    // Last failed action shown in the left panel
+    #[serde(skip)]
+    error: Option<String>,
+    #[serde(skip)]
+    files: Files,
+    // Lattice shown next to the main one, sharing its seed and sweeps but not its temperature
+    #[serde(skip)]
+    replica: Option<Lattice>,
+    // Canvas of the replica, following the zoom and pan of the lattice canvas
+    #[serde(skip)]
+    replica_view: LatticeViewState,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self {
+            name: String::from("Simulation 1"),
+            lattice: Lattice::new(15, 100.0, 100.0),
+            is_paused: true,
+            plot_window: 200,
+            brush_size: 1,
+            touch_spin: 1,
+            show_dashboard: false,
+            show_performance: false,
+            show_structure_factor: false,
+            structure_factor_every: 10,
+            export_scale: 8,
+            gif_every: 1,
+            gif_frame_rate: 10.0,
+            gif_scale: 4,
+            steps_per_frame: 1,
+            lattice_view: LatticeViewState::default(),
+            view: ViewMode::default(),
+            colormap: Colormap::default(),
+            scan_settings: ScanSettings::default(),
+            algorithm: Algorithm::default(),
+            correlation_every: 0,
+            checkpoint_every: 10,
+            checkpoint_depth: 100,
+            auto_pause: false,
+            equilibration_window: 50,
+            histogram_window: 500,
+            histogram_bins: 30,
+            correlation: Vec::new(),
+            animation: None,
+            recorder: Recorder::new(OBSERVABLES_CAPACITY),
+            steps: 0,
+            flipped: 0,
+            progress: Progress::default(),
+            history: History::default(),
+            dashboard: Dashboard::default(),
+            performance: Performance::default(),
+            timeline: Timeline::default(),
+            equilibrated: None,
+            parameters_since: None,
+            toast: None,
+            structure_factor: Vec::new(),
+            structure_factor_texture: None,
+            largest_cluster: None,
+            phase_diagram: None,
+            show_scan: false,
+            scan: None,
+            show_controls: false,
+            show_observables: false,
+            seed_input: String::new(),
+            error: None,
+            files: Files::default(),
+            replica: None,
+            replica_view: LatticeViewState::default(),
+        }
+    }
+}
+
+impl Simulation {
+    /// Create a new default simulation shown under name
+    pub fn named(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// Run one Metropolis step, recording the observables once a sweep worth of steps is done
+    /// Heat-bath and Wolff updates run a whole sweep once a sweep worth of steps is counted
+    /// Return whether the step finished a sweep
+    fn step(&mut self) -> bool {
+        if self.algorithm == Algorithm::Metropolis {
+            let (x_rand, y_rand) = self.lattice.pick_random_point();
+            if self.lattice.metropolis_algo_calculation(x_rand, y_rand) {
+                self.flipped += 1;
+            }
+            if let Some(replica) = &mut self.replica {
+                let (x_rand, y_rand) = replica.pick_random_point();
+                replica.metropolis_algo_calculation(x_rand, y_rand);
+            }
+        }
+        self.steps += 1;
+        if self.steps < self.lattice.size * self.lattice.size {
+            return false;
+        }
+        if self.algorithm != Algorithm::Metropolis {
+            self.flipped += self.lattice.sweep_with(self.algorithm, |_, _| {});
+            if let Some(replica) = &mut self.replica {
+                replica.sweep_with(self.algorithm, |_, _| {});
+            }
+        }
+        self.recorder.record(&self.lattice, self.flipped);
+        self.detect_equilibrium();
+        if self.show_dashboard {
+            self.dashboard
+                .record(&self.lattice, &self.recorder, self.plot_window);
+        }
+        self.progress.sweeps += 1;
+        self.progress.attempted += self.steps as u64;
+        // Cluster updates may flip spins more than once, counted up to the attempted flips
+        self.progress.accepted += self.flipped.min(self.steps) as u64;
+        if self.correlation_every > 0
+            && self
+                .recorder
+                .sweeps
+                .is_multiple_of(self.correlation_every as u64)
+        {
+            self.update_correlation();
+        }
+        if self.show_structure_factor
+            && self
+                .recorder
+                .sweeps
+                .is_multiple_of(self.structure_factor_every.max(1) as u64)
+        {
+            self.structure_factor = self.lattice.structure_factor();
+        }
+        if self.checkpoint_every > 0
+            && self
+                .recorder
+                .sweeps
+                .is_multiple_of(self.checkpoint_every as u64)
+        {
+            self.timeline
+                .push(self.recorder.sweeps, &self.lattice, self.checkpoint_depth);
+        }
+        self.steps = 0;
+        self.flipped = 0;
+        true
+    }
+
+    /// Sweep counter, flip counts, acceptance rate, and running time with a reset button
+    fn progress_controls(&mut self, ui: &mut egui::Ui) {
+        let progress = &self.progress;
+        let acceptance = if progress.attempted == 0 {
+            0.0
+        } else {
+            progress.accepted as f64 / progress.attempted as f64
+        };
+        let speed = if progress.running > 0.0 {
+            progress.sweeps as f64 / progress.running
+        } else {
+            0.0
+        };
+        ui.label(t!("sweeps-done", sweeps = progress.sweeps));
+        ui.label(t!(
+            "flips",
+            accepted = progress.accepted,
+            attempted = progress.attempted
+        ));
+        let rate = format!("{:.2}", 100.0 * acceptance);
+        if self.algorithm == Algorithm::Wolff {
+            ui.label(t!("cluster-acceptance-rate", rate = rate));
+        } else {
+            ui.label(t!("acceptance-rate", rate = rate));
+        }
+        ui.label(t!(
+            "running-time",
+            seconds = format!("{:.1}", progress.running),
+            speed = format!("{speed:.1}")
+        ));
+        if ui.button(t!("reset-counters")).clicked() {
+            println!("Reset counters");
+            self.progress = Progress::default();
+        }
+    }
+
+    /// Pause with a toast once the energy settles, when auto-pause is on
+    /// The detection restarts when the parameters change or the recording restarts,
+    /// only testing the samples taken since
+    fn detect_equilibrium(&mut self) {
+        let parameters = [
+            self.lattice.temperature,
+            self.lattice.interactivity,
+            self.lattice.field,
+        ];
+        let sweeps = self.recorder.sweeps;
+        // The latest sample was taken with the parameters found changed after it
+        let since = match self.parameters_since {
+            Some((since_parameters, since))
+                if since_parameters == parameters && since <= sweeps =>
+            {
+                since
+            }
+            _ => sweeps.saturating_sub(1),
+        };
+        self.parameters_since = Some((parameters, since));
+        let settling = sweeps - since < 2 * self.equilibration_window as u64;
+        if settling
+            || self
+                .equilibrated
+                .is_some_and(|settled| settled != parameters)
+        {
+            self.equilibrated = None;
+        }
+        if !self.auto_pause
+            || settling
+            || self.equilibrated.is_some()
+            || !self.recorder.is_equilibrated(self.equilibration_window)
+        {
+            return;
+        }
+        println!("Equilibrated after {} sweeps, paused", self.recorder.sweeps);
+        self.equilibrated = Some(parameters);
+        self.is_paused = true;
+        self.toast = Some(Toast {
+            text: t!("equilibrated-toast", sweeps = self.recorder.sweeps),
+            shown_at: None,
+        });
+    }
+
+    /// Checkbox and window of the equilibrium detection pausing the simulation
+    fn auto_pause_controls(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.auto_pause, t!("auto-pause"))
+            .on_hover_text(t!("auto-pause-hint"));
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("window-sweeps"));
+            ui.add(egui::DragValue::new(&mut self.equilibration_window).range(5..=1000))
+                .labelled_by(label.id);
+        });
+    }
+
+    /// Toast over the bottom of the canvas, dropped a few seconds after it is first shown
+    fn toast_overlay(&mut self, ui: &egui::Ui, canvas: egui::Rect) {
+        let Some(toast) = &mut self.toast else {
+            return;
+        };
+        let time = ui.input(|i| i.time);
+        let shown_at = *toast.shown_at.get_or_insert(time);
+        if time - shown_at > TOAST_SECONDS {
+            self.toast = None;
+            return;
+        }
+        egui::Area::new(egui::Id::new("toast"))
+            .pivot(egui::Align2::CENTER_BOTTOM)
+            .fixed_pos(canvas.center_bottom() + egui::vec2(0.0, -16.0))
+            .interactable(false)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(&toast.text));
+            });
+        ui.ctx().request_repaint();
+    }
+
+    /// Compute the correlation function up to half of the lattice size
+    fn update_correlation(&mut self) {
+        self.correlation = self.lattice.correlation_function(self.lattice.size / 2);
+        self.dashboard.record_correlation(&self.correlation);
+    }
+
+    /// Start a new lattice from the parameters of a shared link, see Permalink
+    /// Missing parameters keep their value, except J and h that default to the presets'
+    pub fn apply_permalink(&mut self, permalink: &Permalink) {
+        println!("Starting from permalink {permalink:?}");
+        let mut lattice = Lattice::with_seed(
+            permalink.size.unwrap_or(self.lattice.size).clamp(5, 1000),
+            permalink.j.unwrap_or(1.0) * PRESET_INTERACTIVITY,
+            self.lattice.temperature,
+            permalink.seed.unwrap_or(self.lattice.seed),
+        );
+        if let Some(reduced_temperature) = permalink.t {
+            lattice.set_reduced_temperature(reduced_temperature);
+        }
+        lattice.field = permalink.h.unwrap_or_default() * lattice.interactivity.abs();
+        lattice.boundary = self.lattice.boundary;
+        self.load_lattice(lattice);
+    }
+
+    /// Replace the lattice, e.g. by a phase diagram cell, restarting the observables and edits
+    fn load_lattice(&mut self, lattice: Lattice) {
+        self.lattice = lattice;
+        self.reset_replica();
+        self.lattice_view.reset();
+        self.history.clear();
+        self.seed_input = self.lattice.seed.to_string();
+        self.error = None;
+        self.recorder.clear();
+        self.dashboard.clear();
+        self.timeline.clear();
+        self.steps = 0;
+        self.flipped = 0;
+    }
+
+    /// Checkpoint settings and the scrubber going back to a buffered checkpoint
+    fn timeline_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(t!("timeline"));
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("checkpoint-every"));
+            ui.add(egui::DragValue::new(&mut self.checkpoint_every).range(0..=10_000))
+                .labelled_by(label.id);
+            ui.label(t!("sweeps"));
+        });
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("keep"));
+            ui.add(egui::DragValue::new(&mut self.checkpoint_depth).range(1..=1000))
+                .labelled_by(label.id);
+            ui.label(t!("checkpoints"));
+        });
+        let count = self.timeline.checkpoints.len();
+        if count == 0 {
+            return;
+        }
+
+        // The position past the last checkpoint is the live lattice
+        let mut position = self.timeline.selected.unwrap_or(count);
+        let response = ui.add(egui::Slider::new(&mut position, 0..=count).show_value(false));
+        let value = match self.timeline.checkpoints.get(position) {
+            Some(checkpoint) => t!("checkpoint-at", sweep = checkpoint.sweep),
+            None => t!("live-at", sweep = self.recorder.sweeps),
+        };
+        a11y::describe(&response, t!("timeline"), value);
+        if response.changed() {
+            if position == count {
+                println!("Back to the live lattice");
+                self.timeline.selected = None;
+            } else {
+                println!(
+                    "Showing checkpoint at sweep {}",
+                    self.timeline.checkpoints[position].sweep
+                );
+                self.timeline.selected = Some(position);
+                self.is_paused = true;
+            }
+        }
+        match self.timeline.selected().map(|checkpoint| checkpoint.sweep) {
+            Some(sweep) => {
+                ui.label(t!("checkpoint-at", sweep = sweep));
+                ui.horizontal(|ui| {
+                    if ui.button(t!("resume-from-here")).clicked() {
+                        self.resume_from_checkpoint();
+                    }
+                    if ui.button(t!("back-to-live")).clicked() {
+                        println!("Back to the live lattice");
+                        self.timeline.selected = None;
+                    }
+                });
+            }
+            None => {
+                ui.label(t!("live-at", sweep = self.recorder.sweeps));
+            }
+        }
+    }
+
+    /// Continue the simulation from the shown checkpoint, dropping the sweeps after it
+    fn resume_from_checkpoint(&mut self) {
+        let Some(checkpoint) = self.timeline.selected() else {
+            return;
+        };
+        let sweep = checkpoint.sweep;
+        println!("Resuming from sweep {sweep}");
+        self.lattice = checkpoint.lattice.clone();
+        self.timeline.rewind(sweep);
+        self.recorder.rewind(sweep);
+        self.dashboard.clear();
+        self.history.clear();
+        self.reset_replica();
+        self.steps = 0;
+        self.flipped = 0;
+    }
+
+    /// Window with a grid of lattices across temperatures and fields
+    /// Clicking a cell loads its lattice into the main view
+    fn phase_diagram_window(&mut self, ctx: &egui::Context) {
+        let Some(diagram) = &mut self.phase_diagram else {
+            return;
+        };
+        diagram.advance();
+        if !diagram.is_done() {
+            ctx.request_repaint();
+        }
+
+        let mut open = true;
+        let mut clicked = None;
+        let colormap = self.colormap;
+        egui::Window::new(t!("phase-diagram"))
+            .id(egui::Id::new("phase_diagram_window"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(t!(
+                    "phase-sweeps",
+                    sweeps = diagram.sweeps,
+                    total = PHASE_SWEEPS
+                ));
+                ui.label(t!("phase-hint"));
+                egui::Grid::new("phase_diagram_grid").show(ui, |ui| {
+                    for (index, cell) in diagram.cells.iter_mut().enumerate() {
+                        if index % PHASE_GRID == 0 {
+                            ui.label(format!("{:+.2}", cell.reduced_field));
+                        }
+                        let response = LatticeView::new(&mut cell.lattice, &mut cell.view)
+                            .side(64.0)
+                            .colormap(colormap)
+                            .navigable(false)
+                            .show(ui)
+                            .response
+                            .on_hover_text(format!(
+                                "k_B T / J = {:.2}, h / J = {:+.2}",
+                                cell.reduced_temperature, cell.reduced_field
+                            ));
+                        if response.clicked() {
+                            clicked = Some(cell.lattice.clone());
+                        }
+                        if index % PHASE_GRID == PHASE_GRID - 1 {
+                            ui.end_row();
+                        }
+                    }
+                    ui.label("");
+                    for cell in diagram.cells.iter().take(PHASE_GRID) {
+                        ui.label(format!("{:.2}", cell.reduced_temperature));
+                    }
+                    ui.end_row();
+                });
+            });
+
+        if let Some(lattice) = clicked {
+            println!(
+                "Loading phase diagram cell at temperature {} and field {}",
+                lattice.temperature, lattice.field
+            );
+            self.load_lattice(lattice);
+        }
+        if !open {
+            self.phase_diagram = None;
+        }
+    }
+
+    /// Window defining, running, and showing the results of a temperature scan
+    fn scan_window(&mut self, ctx: &egui::Context) {
+        if let Some(scan) = &mut self.scan {
+            scan.poll();
+        }
+        let mut open = self.show_scan;
+        egui::Window::new(t!("temperature-scan"))
+            .id(egui::Id::new("scan_window"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let settings = &mut self.scan_settings;
+                egui::Grid::new("scan_settings").show(ui, |ui| {
+                    let label = ui.label(t!("scan-from"));
+                    ui.add(
+                        egui::DragValue::new(&mut settings.start)
+                            .speed(0.01)
+                            .range(0.01..=10.0),
+                    )
+                    .labelled_by(label.id);
+                    let label = ui.label(t!("scan-to"));
+                    ui.add(
+                        egui::DragValue::new(&mut settings.end)
+                            .speed(0.01)
+                            .range(0.01..=10.0),
+                    )
+                    .labelled_by(label.id);
+                    ui.end_row();
+                    let label = ui.label(t!("temperatures"));
+                    ui.add(egui::DragValue::new(&mut settings.steps).range(1..=200))
+                        .labelled_by(label.id);
+                    ui.end_row();
+                    let label = ui.label(t!("equilibration-sweeps"));
+                    ui.add(egui::DragValue::new(&mut settings.equilibration).range(0..=100_000))
+                        .labelled_by(label.id);
+                    let label = ui.label(t!("sweeps-per-point"));
+                    ui.add(egui::DragValue::new(&mut settings.sweeps).range(1..=100_000))
+                        .labelled_by(label.id);
+                    ui.end_row();
+                });
+
+                let running = self.scan.as_ref().is_some_and(|scan| !scan.is_done());
+                ui.horizontal(|ui| {
+                    if running {
+                        if ui.button(t!("cancel")).clicked() {
+                            println!("Cancelling temperature scan");
+                            if let Some(scan) = &mut self.scan {
+                                scan.cancel();
+                            }
+                        }
+                    } else if ui.button(t!("run")).clicked() {
+                        println!("Running temperature scan");
+                        let mut base = self.lattice.clone();
+                        if base.interactivity == 0.0 {
+                            base.interactivity = PRESET_INTERACTIVITY;
+                        }
+                        self.scan =
+                            Some(Scan::start(ctx, &base, self.algorithm, self.scan_settings));
+                    }
+                    if let Some(scan) = &self.scan
+                        && !scan.points.is_empty()
+                        && ui.button(t!("export-csv")).clicked()
+                    {
+                        match scan.to_csv() {
+                            Ok(bytes) => self.files.save(ctx, ("CSV", &["csv"]), "scan.csv", bytes),
+                            Err(e) => self.error = Some(format!("Failed to export CSV. Error {e}")),
+                        }
+                    }
+                });
+
+                let Some(scan) = &mut self.scan else {
+                    return;
+                };
+                ui.label(scan.describe());
+                let progress = scan.points.len() as f32 / scan.total.max(1) as f32;
+                ui.add(egui::ProgressBar::new(progress).text(t!(
+                    "scan-progress",
+                    done = scan.points.len(),
+                    total = scan.total
+                )));
+                scan.plot(ui);
+                scan.table(ui);
+            });
+        self.show_scan = open;
+    }
+
+    /// Radio buttons switching the boundary condition while the simulation runs
+    fn boundary_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(t!("boundary"));
+        ui.horizontal_wrapped(|ui| {
+            for boundary in Boundary::ALL {
+                if ui
+                    .radio_value(
+                        &mut self.lattice.boundary,
+                        boundary,
+                        i18n::boundary_label(boundary),
+                    )
+                    .changed()
+                {
+                    println!("Switching boundary to {}", boundary.name());
+                }
+            }
+        });
+    }
+
+    /// Combo box switching the update algorithm while the simulation runs
+    fn algorithm_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(t!("algorithm"));
+            egui::ComboBox::from_id_salt("algorithm")
+                .selected_text(i18n::algorithm_label(self.algorithm))
+                .show_ui(ui, |ui| {
+                    for algorithm in Algorithm::ALL {
+                        if ui
+                            .selectable_value(
+                                &mut self.algorithm,
+                                algorithm,
+                                i18n::algorithm_label(algorithm),
+                            )
+                            .changed()
+                        {
+                            println!("Switching algorithm to {}", algorithm.name());
+                        }
+                    }
+                });
+        });
+    }
+
+    /// Buttons setting the temperature, interactivity, and speed of a physical regime
+    fn preset_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(t!("presets"));
+        for (name, reduced_temperature, sweeps_per_frame) in PRESETS {
+            if ui.button(i18n::message(name, None)).clicked() {
+                println!("Applying preset {name}");
+                self.lattice.interactivity = PRESET_INTERACTIVITY;
+                self.lattice.set_reduced_temperature(reduced_temperature);
+                let steps = sweeps_per_frame * (self.lattice.size * self.lattice.size) as f64;
+                self.steps_per_frame = (steps as usize).max(1);
+            }
+        }
+    }
+
+    /// Slider of the external field in units of |J| with a button reversing its sign
+    fn field_controls(&mut self, ui: &mut egui::Ui) {
+        let interactivity = self.lattice.interactivity.abs();
+        ui.label(t!("external-field"));
+        ui.horizontal(|ui| {
+            let mut reduced_field = if interactivity > 0.0 {
+                self.lattice.field / interactivity
+            } else {
+                0.0
+            };
+            let response = ui.add_enabled(
+                interactivity > 0.0,
+                egui::Slider::new(&mut reduced_field, -4.0..=4.0),
+            );
+            a11y::describe(
+                &response,
+                t!("external-field"),
+                t!("a11y-field", value = format!("{reduced_field:.2}")),
+            );
+            if response.changed() {
+                self.lattice.field = reduced_field * interactivity;
+                println!("Updating external field to {}", self.lattice.field);
+            }
+            if ui.button(t!("flip-field")).clicked() {
+                self.lattice.field = -self.lattice.field;
+                println!("Flipped external field to {}", self.lattice.field);
+            }
+        });
+    }
+
+    /// Capture a GIF frame every gif_every sweeps, stopping once the memory bound is reached
+    fn capture_frame(&mut self, ctx: &egui::Context) {
+        let Some(animation) = &mut self.animation else {
+            return;
+        };
+        if !self
+            .recorder
+            .sweeps
+            .is_multiple_of(self.gif_every.max(1) as u64)
+        {
+            return;
+        }
+        if !animation.push(&self.lattice) {
+            println!("Stopped GIF recording after {} frames", animation.len());
+            self.stop_recording(ctx);
+        }
+    }
+
+    /// Encode the recorded frames and ask where to write the GIF
+    fn stop_recording(&mut self, ctx: &egui::Context) {
+        let Some(animation) = self.animation.take() else {
+            return;
+        };
+        if animation.is_empty() {
+            return;
+        }
+        match animation.to_gif() {
+            Ok(bytes) => self
+                .files
+                .save(ctx, ("GIF image", &["gif"]), "lattice.gif", bytes),
+            Err(e) => self.error = Some(format!("Failed to encode GIF. Error {e}")),
+        }
+    }
+
+    /// GIF options and the button starting or stopping a recording
+    fn recording_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("frame-every"));
+            ui.add(egui::DragValue::new(&mut self.gif_every).range(1..=1000))
+                .labelled_by(label.id);
+            ui.label(t!("sweeps"));
+        });
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("frame-rate"));
+            ui.add(egui::DragValue::new(&mut self.gif_frame_rate).range(1.0..=50.0))
+                .labelled_by(label.id);
+            let label = ui.label(t!("scale"));
+            ui.add(egui::DragValue::new(&mut self.gif_scale).range(1..=16))
+                .labelled_by(label.id);
+        });
+        match &self.animation {
+            Some(animation) => {
+                let frames = animation.len();
+                if ui.button(t!("stop-gif", frames = frames)).clicked() {
+                    self.stop_recording(ui.ctx());
+                }
+            }
+            None => {
+                if ui.button(t!("record-gif")).clicked() {
+                    self.animation = Some(Animation::new(
+                        self.lattice.size,
+                        self.gif_scale,
+                        self.gif_frame_rate,
+                        GIF_MAX_BYTES,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Replace the lattice by a new one started from seed, keeping its parameters
+    fn restart(&mut self, seed: u64) {
+        let (boundary, field) = (self.lattice.boundary, self.lattice.field);
+        self.lattice = Lattice::with_seed(
+            self.lattice.size,
+            self.lattice.interactivity,
+            self.lattice.temperature,
+            seed,
+        );
+        self.lattice.boundary = boundary;
+        self.lattice.field = field;
+        self.reset_replica();
+        self.history.clear();
+        self.seed_input = seed.to_string();
+        self.error = None;
+        self.recorder.clear();
+        self.dashboard.clear();
+        self.timeline.clear();
+        self.steps = 0;
+        self.flipped = 0;
+    }
+
+    /// Restart the replica as a copy of the lattice, random number generator included
+    /// The replica keeps its own temperature
+    fn reset_replica(&mut self) {
+        if let Some(replica) = &mut self.replica {
+            let temperature = replica.temperature;
+            *replica = self.lattice.clone();
+            replica.temperature = temperature;
+        }
+    }
+
+    /// Toggle of the side-by-side replica with its own temperature slider
+    fn replica_controls(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.replica.is_some();
+        if ui.checkbox(&mut enabled, t!("compare-replica")).changed() {
+            if enabled {
+                println!("Starting replica from seed {}", self.lattice.seed);
+                // Both lattices restart from the same seed so only the temperature differs
+                self.replica = Some(self.lattice.clone());
+                self.restart(self.lattice.seed);
+            } else {
+                println!("Stopping replica");
+                self.replica = None;
+            }
+        }
+        if let Some(replica) = &mut self.replica {
+            ui.label(t!("replica-temperature"));
+            let response = ui.add(egui::Slider::new(&mut replica.temperature, 0.0..=10_000.0));
+            a11y::describe(
+                &response,
+                t!("replica-temperature"),
+                temperature_value(replica),
+            );
+            if response.changed() {
+                println!(
+                    "Updating replica temperature (K) to {}",
+                    replica.temperature
+                );
+            }
+        }
+    }
+
+    /// Seed field with buttons restarting the lattice from the typed or a random seed
+    fn seed_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("seed"));
+            ui.add(egui::TextEdit::singleline(&mut self.seed_input).desired_width(120.0))
+                .labelled_by(label.id);
+        });
+        ui.horizontal(|ui| {
+            if ui.button(t!("reseed")).clicked() {
+                match self.seed_input.trim().parse() {
+                    Ok(seed) => {
+                        println!("Reseeding lattice with {seed}");
+                        self.restart(seed);
+                    }
+                    Err(e) => {
+                        self.error = Some(format!("Invalid seed '{}'. Error {e}", self.seed_input))
+                    }
+                }
+            }
+            if ui.button(t!("random-seed")).clicked() {
+                let seed = rand::random();
+                println!("Reseeding lattice with {seed}");
+                self.restart(seed);
+            }
+        });
+        ui.label(t!("active-seed", seed = self.lattice.seed));
+    }
+
+    /// Buttons writing the lattice into a snapshot file and reading it back
+    fn snapshot_controls(&mut self, ui: &mut egui::Ui) {
+        let filter: (&str, &[&str]) = ("Ising snapshot", &[SNAPSHOT_EXTENSION]);
+        // Exported images embed a snapshot too
+        let open_filter: (&str, &[&str]) = ("Ising snapshot", &[SNAPSHOT_EXTENSION, "png"]);
+        ui.horizontal(|ui| {
+            if ui.button(t!("save")).clicked() {
+                match Snapshot::new(&self.lattice).to_bytes() {
+                    Ok(bytes) => self.files.save(
+                        ui.ctx(),
+                        filter,
+                        &format!("lattice.{SNAPSHOT_EXTENSION}"),
+                        bytes,
+                    ),
+                    Err(e) => self.error = Some(format!("Failed to save snapshot. Error {e}")),
+                }
+            }
+            if ui.button(t!("load")).clicked() {
+                self.files.open(ui.ctx(), open_filter);
+            }
+        });
+    }
+
+    /// Scale picker and buttons writing the lattice as a PNG image and the observables as CSV
+    fn export_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("pixels-per-spin"));
+            ui.add(egui::DragValue::new(&mut self.export_scale).range(1..=64))
+                .labelled_by(label.id);
+        });
+        if ui.button(t!("export-png")).clicked() {
+            match export::to_png(&self.lattice, self.export_scale) {
+                Ok(bytes) => {
+                    self.files
+                        .save(ui.ctx(), ("PNG image", &["png"]), "lattice.png", bytes)
+                }
+                Err(e) => self.error = Some(format!("Failed to export PNG. Error {e}")),
+            }
+        }
+        let response = ui
+            .button(t!("export-data"))
+            .on_hover_text(t!("export-data-hint"));
+        if response.clicked() {
+            let mut bytes = Vec::new();
+            match self.recorder.write_csv(&mut bytes) {
+                Ok(()) => self
+                    .files
+                    .save(ui.ctx(), ("CSV", &["csv"]), "observables.csv", bytes),
+                Err(e) => self.error = Some(format!("Failed to export data. Error {e}")),
+            }
+        }
+    }
+
+    /// Apply the file dialogs finished since the last frame
+    fn handle_files(&mut self) {
+        while let Some(event) = self.files.poll() {
+            match event {
+                FileEvent::Opened(name, bytes) => self.open_snapshot(&name, &bytes),
+                FileEvent::Saved(name) => println!("Saved {name}"),
+                FileEvent::Failed(error) => self.error = Some(error),
+            }
+        }
+    }
+
+    /// Load a snapshot file, or the snapshot embedded in an exported PNG image
+    fn open_snapshot(&mut self, name: &str, bytes: &[u8]) {
+        match Snapshot::from_file_bytes(bytes) {
+            Ok(snapshot) => {
+                println!("Loaded snapshot {name}");
+                self.load_lattice(snapshot.into_lattice());
+            }
+            Err(e) => self.error = Some(format!("Failed to load snapshot {name}. Error {e}")),
+        }
+    }
+
+    /// Load the snapshots and exported images dropped onto the window
+    /// Natively dropped files come with their path, on the web with their content
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        for file in ctx.input(|i| i.raw.dropped_files.clone()) {
+            let name = match &file.path {
+                Some(path) => path.display().to_string(),
+                None => file.name.clone(),
+            };
+            let bytes = match (&file.bytes, &file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => std::fs::read(path),
+                (None, None) => continue,
+            };
+            match bytes {
+                Ok(bytes) => self.open_snapshot(&name, &bytes),
+                Err(e) => self.error = Some(format!("Failed to read {name}. Error {e}")),
+            }
+        }
+    }
+
+    /// Hint drawn over the window while files are dragged over it
+    fn drop_hint(&self, ctx: &egui::Context) {
+        if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+            return;
+        }
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("drop_hint"),
+        ));
+        let rect = ctx.content_rect();
+        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            t!("drop-hint"),
+            egui::TextStyle::Heading.resolve(&ctx.style()),
+            egui::Color32::WHITE,
+        );
+    }
+
+    /// Undo and redo buttons of the painted strokes
+    fn edit_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(t!("undo")).on_hover_text("Ctrl+Z").clicked() {
+                self.undo();
+            }
+            if ui.button(t!("redo")).on_hover_text("Ctrl+Y").clicked() {
+                self.redo();
+            }
+        });
+    }
+
+    /// Undo with Ctrl+Z and redo with Ctrl+Y or Ctrl+Shift+Z
+    /// Left to a focused text field, undoing its own edits
+    fn handle_edit_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (undo, redo) = ctx.input_mut(|i| {
+            let redo = i.consume_key(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::Z,
+            ) || i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y);
+            (i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z), redo)
+        });
+        if undo {
+            self.undo();
+        }
+        if redo {
+            self.redo();
+        }
+    }
+
+    /// Revert the latest painted stroke
+    fn undo(&mut self) {
+        if self.history.undo(&mut self.lattice) {
+            println!("Undo painted stroke");
+        }
+    }
+
+    /// Paint the latest undone stroke again
+    fn redo(&mut self) {
+        if self.history.redo(&mut self.lattice) {
+            println!("Redo painted stroke");
+        }
+    }
+
+    /// Radio buttons switching how the lattice is colored
+    fn view_controls(&mut self, ui: &mut egui::Ui) {
+        ui.label(t!("view"));
+        ui.horizontal(|ui| {
+            for view in ViewMode::ALL {
+                if ui.radio_value(&mut self.view, view, view.label()).changed() {
+                    println!("Switching view to {}", view.name());
+                }
+            }
+        });
+        egui::ComboBox::new("colormap", t!("colormap"))
+            .selected_text(self.colormap.label())
+            .show_ui(ui, |ui| {
+                for colormap in Colormap::ALL {
+                    let response =
+                        ui.selectable_value(&mut self.colormap, colormap, colormap.label());
+                    if response.changed() {
+                        println!("Switching colormap to {}", colormap.name());
+                    }
+                }
+            });
+        if self.view == ViewMode::Energy {
+            // Gradient legend from the lowest to the highest local energy
+            ui.horizontal(|ui| {
+                ui.label(t!("low"));
+                for step in 0..=10 {
+                    ui.colored_label(self.colormap.energy_color(f64::from(step) / 10.0), "■");
+                }
+                ui.label(t!("high"));
+            });
+        }
+        if let (ViewMode::Clusters, Some(largest)) = (self.view, self.largest_cluster) {
+            let spins = self.lattice.size * self.lattice.size;
+            ui.label(t!(
+                "largest-cluster-size",
+                spins = largest,
+                percent = format!("{:.1}", 100.0 * largest as f64 / spins as f64)
+            ));
+        }
+    }
+
+    /// Show magnetization and energy per spin versus sweep for the latest plot_window sweeps
+    /// Both charts share their x axis and cursor, and mark the sweep the simulation paused at
+    fn observables_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading(t!("observables"));
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("window-sweeps"));
+            ui.add(egui::DragValue::new(&mut self.plot_window).range(10..=OBSERVABLES_CAPACITY))
+                .labelled_by(label.id);
+        });
+
+        let skip = self.recorder.samples.len().saturating_sub(self.plot_window);
+        let samples: Vec<&Sample> = self.recorder.samples.iter().skip(skip).collect();
+        let paused_at = self
+            .is_paused
+            .then(|| self.recorder.last().map(|sample| sample.sweep as f64))
+            .flatten();
+        let chart_height = (ui.available_height() / 3.0 - 30.0).max(80.0);
+
+        let magnetization: Vec<[f64; 2]> = samples
+            .iter()
+            .map(|sample| [sample.sweep as f64, sample.magnetization])
+            .collect();
+        let energy: Vec<[f64; 2]> = samples
+            .iter()
+            .map(|sample| [sample.sweep as f64, sample.energy])
+            .collect();
+        observable_plot(
+            ui,
+            &t!("magnetization"),
+            magnetization,
+            egui::Color32::LIGHT_GREEN,
+            chart_height,
+            paused_at,
+        );
+        observable_plot(
+            ui,
+            &t!("energy-per-spin"),
+            energy,
+            egui::Color32::LIGHT_RED,
+            chart_height,
+            paused_at,
+        );
+        self.correlation_panel(ui, chart_height);
+        egui::CollapsingHeader::new(t!("histograms"))
+            .id_salt("histograms")
+            .show(ui, |ui| self.histograms(ui));
+    }
+
+    /// Histograms of the sampled magnetization and energy per spin
+    fn histograms(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("window-sweeps"));
+            ui.add(
+                egui::DragValue::new(&mut self.histogram_window).range(10..=OBSERVABLES_CAPACITY),
+            )
+            .labelled_by(label.id);
+            let label = ui.label(t!("bins"));
+            ui.add(egui::DragValue::new(&mut self.histogram_bins).range(2..=200))
+                .labelled_by(label.id);
+        });
+        histogram_plot(
+            ui,
+            &t!("magnetization"),
+            self.recorder
+                .histogram(self.histogram_window, self.histogram_bins, |sample| {
+                    sample.magnetization
+                }),
+            egui::Color32::LIGHT_GREEN,
+        );
+        histogram_plot(
+            ui,
+            &t!("energy-per-spin"),
+            self.recorder
+                .histogram(self.histogram_window, self.histogram_bins, |sample| {
+                    sample.energy
+                }),
+            egui::Color32::LIGHT_RED,
+        );
+    }
+
+    /// Plot of the correlation function C(r) with its fitted correlation length
+    fn correlation_panel(&mut self, ui: &mut egui::Ui, height: f32) {
+        ui.horizontal(|ui| {
+            if ui.button(t!("compute-correlation")).clicked() {
+                println!("Computing correlation function");
+                self.update_correlation();
+            }
+            let label = ui.label(t!("every-sweeps"));
+            ui.add(egui::DragValue::new(&mut self.correlation_every).range(0..=10_000))
+                .labelled_by(label.id);
+        });
+        match correlation_length(&self.correlation) {
+            Some(length) => ui.label(t!("correlation-length", length = format!("{length:.3}"))),
+            None => ui.label(t!("correlation-length", length = "-")),
+        };
+
+        let points: Vec<[f64; 2]> = self
+            .correlation
+            .iter()
+            .enumerate()
+            .map(|(r, c)| [r as f64, *c])
+            .collect();
+        Plot::new("Correlation")
+            .height(height)
+            .x_axis_label(t!("distance"))
+            .y_axis_label("C(r)")
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new("C(r)", points).color(egui::Color32::LIGHT_YELLOW));
+            });
+    }
+
+    /// Button pausing or resuming the simulation
+    fn run_toggle(&mut self, ui: &mut egui::Ui) {
+        if self.is_paused {
+            if ui.button(t!("resume")).clicked() {
+                println!("Resumed");
+                self.is_paused = false;
+            }
+        } else if ui.button(t!("pause")).clicked() {
+            println!("Paused");
+            self.is_paused = true;
+        }
+    }
+
+    /// Run controls, parameters, tools, and legends of the left panel
+    fn control_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            self.run_toggle(ui);
+
+            if ui.button(t!("reset")).clicked() {
+                println!("Reset");
+                self.restart(rand::random());
+            }
+        });
+        ui.label("");
+
+        self.progress_controls(ui);
+        ui.label("");
+
+        self.seed_controls(ui);
+        ui.label("");
+
+        self.preset_controls(ui);
+        ui.label("");
+
+        self.algorithm_controls(ui);
+        self.boundary_controls(ui);
+        self.replica_controls(ui);
+        if ui.button(t!("temperature-scan")).clicked() {
+            self.show_scan = !self.show_scan;
+        }
+        if ui.button(t!("phase-diagram")).clicked() {
+            println!("Exploring the phase diagram");
+            self.phase_diagram = Some(PhaseDiagram::new(&self.lattice, PRESET_INTERACTIVITY));
+        }
+
+        self.snapshot_controls(ui);
+        self.export_controls(ui);
+        self.recording_controls(ui);
+        self.timeline_controls(ui);
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        ui.label("");
+
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("lattice-size"));
+            let response = ui
+                .add(egui::DragValue::new(&mut self.lattice.size).range(5.0..=1000.0))
+                .labelled_by(label.id);
+            if response.changed() {
+                println!("Updating Lattice size to {}", self.lattice.size);
+                self.lattice.update_lattice();
+                self.history.clear();
+                self.reset_replica();
+            }
+        });
+
+        ui.vertical(|ui| {
+            ui.label(t!("temperature"));
+            let response = ui.add(egui::Slider::new(
+                &mut self.lattice.temperature,
+                0.0..=10_000.0,
+            ));
+            a11y::describe(
+                &response,
+                t!("temperature"),
+                temperature_value(&self.lattice),
+            );
+            if response.changed() {
+                println!("Updating temperature (K) to {}", self.lattice.temperature);
+            }
+        });
+
+        ui.vertical(|ui| {
+            ui.label(t!("interactivity"));
+            let response = ui.add(egui::Slider::new(
+                &mut self.lattice.interactivity,
+                -10_000.0..=10_000.0,
+            ));
+            let value = format!("{:.0}", self.lattice.interactivity);
+            a11y::describe(
+                &response,
+                t!("interactivity"),
+                t!("a11y-kelvin", value = value),
+            );
+            if response.changed() {
+                println!(
+                    "Updating interactivity (K) to {}",
+                    self.lattice.interactivity
+                );
+            }
+        });
+
+        self.field_controls(ui);
+
+        ui.label(t!(
+            "reduced-temperature",
+            value = format!("{:.3}", self.lattice.reduced_temperature())
+        ));
+
+        ui.horizontal(|ui| {
+            let label = ui.label(t!("steps-per-frame"));
+            ui.add(egui::DragValue::new(&mut self.steps_per_frame).range(1..=1_000_000))
+                .labelled_by(label.id);
+        });
+        self.auto_pause_controls(ui);
+
+        ui.vertical(|ui| {
+            ui.label("");
+            ui.label(t!("brush-size"));
+            let response = ui.add(egui::Slider::new(&mut self.brush_size, 1..=50));
+            let value = t!("a11y-spins", count = self.brush_size);
+            a11y::describe(&response, t!("brush-size"), value);
+            ui.label(t!("brush-hint"));
+            ui.horizontal(|ui| {
+                ui.label(t!("touch-paints"));
+                ui.radio_value(&mut self.touch_spin, 1, t!("up"));
+                ui.radio_value(&mut self.touch_spin, -1, t!("down"));
+            });
+            self.edit_controls(ui);
+            ui.label("");
+            self.view_controls(ui);
+            ui.label("");
+            ui.label(t!("legends"));
+            let (up_color, down_color) = self.colormap.spin_colors(ui.visuals().dark_mode);
+            ui.label(egui::RichText::new(t!("spin-up")).color(up_color));
+            ui.label(egui::RichText::new(t!("spin-down")).color(down_color));
+        });
+    }
+
+    /// Run the steps of one frame when resumed, also called while the tab is hidden
+    /// Stop early once the steps took budget, leaving the rest of the frame to the other tabs
+    pub fn advance(&mut self, ctx: &egui::Context, budget: Duration) {
+        // Only re-calculate and repaint if resumed
+        if self.is_paused {
+            return;
+        }
+        self.progress.running += f64::from(ctx.input(|i| i.stable_dt));
+        let start = Instant::now();
+        for _ in 0..self.steps_per_frame {
+            if self.step() {
+                self.capture_frame(ctx);
+            }
+            // Auto-pause stops right at the sweep the energy settled at
+            if self.is_paused || start.elapsed() >= budget {
+                break;
+            }
+        }
+        self.performance.record_step(start.elapsed().as_secs_f64());
+        ctx.request_repaint();
+    }
+
+    /// Rates and timings drawn over the top right corner of the canvas
+    fn performance_overlay(&mut self, ui: &egui::Ui, canvas: egui::Rect) {
+        let (time, frame_time) = ui.input(|i| (i.time, f64::from(i.unstable_dt)));
+        self.performance.record_frame(frame_time);
+        self.performance
+            .record_progress(time, self.progress.sweeps, self.progress.accepted);
+        egui::Area::new(egui::Id::new("performance_overlay"))
+            .pivot(egui::Align2::RIGHT_TOP)
+            .fixed_pos(canvas.right_top() + egui::vec2(-8.0, 8.0))
+            .interactable(false)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| self.performance.show(ui));
+            });
+    }
+
+    /// Structure factor S(k) on a log scale, k = 0 at the center, with S(k) of the hovered k
+    fn structure_factor_canvas(&mut self, ui: &mut egui::Ui, side: f32) {
+        let size = self.structure_factor.len();
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+        let image = render_structure_factor(&self.structure_factor, self.colormap);
+        let texture = self.structure_factor_texture.get_or_insert_with(|| {
+            ui.ctx().load_texture(
+                "structure_factor",
+                egui::ColorImage::default(),
+                egui::TextureOptions::NEAREST,
+            )
+        });
+        texture.set(image, egui::TextureOptions::NEAREST);
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        ui.painter()
+            .image(texture.id(), rect, uv, egui::Color32::WHITE);
+        let peak = self
+            .structure_factor
+            .get(size / 2)
+            .and_then(|row| row.get(size / 2))
+            .copied()
+            .unwrap_or_default();
+        a11y::describe_image(
+            &response,
+            t!("structure-factor"),
+            t!("structure-factor-peak", value = format!("{peak:.1}")),
+        );
+
+        let Some(pos) = response.hover_pos() else {
+            return;
+        };
+        let cell = (pos - rect.min) / rect.size() * size as f32;
+        let (kx, ky) = (cell.x as usize, cell.y as usize);
+        if let Some(value) = self.structure_factor.get(ky).and_then(|row| row.get(kx)) {
+            // Wave vectors in units of pi, from -1 to 1 across the canvas
+            let wave_number = |k: usize| 2.0 * (k as f64 - (size / 2) as f64) / size as f64;
+            response.on_hover_text(t!(
+                "structure-factor-hover",
+                kx = format!("{:+.2}", wave_number(kx)),
+                ky = format!("{:+.2}", wave_number(ky)),
+                value = format!("{value:.2}")
+            ));
+        }
+    }
+
+    /// Show the lattice alone with a button pausing or resuming it, for embedded pages
+    /// The view neither zooms nor pans, leaving scrolling to the embedding page
+    pub fn show_embedded(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.run_toggle(ui);
+            let ui_size = ui.available_size();
+            LatticeView::new(&mut self.lattice, &mut self.lattice_view)
+                .mode(self.view)
+                .colormap(self.colormap)
+                .side(ui_size.x.min(ui_size.y))
+                .navigable(false)
+                .show(ui);
+        });
+    }
+
+    /// Show the controls, observables, and lattice of the simulation
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let side_panel_width = 150.0;
+        self.handle_files();
+        self.handle_dropped_files(ctx);
+        self.drop_hint(ctx);
+        self.handle_edit_keys(ctx);
+        // Running again shows the live lattice
+        if !self.is_paused {
+            self.timeline.selected = None;
+        }
+        self.phase_diagram_window(ctx);
+        self.scan_window(ctx);
+
+        // Small viewports like phones keep the canvas whole and open the panels on demand
+        let narrow = ctx.content_rect().width() < NARROW_WIDTH;
+        egui::SidePanel::left("left_panel")
+            .default_width(side_panel_width)
+            .show_animated(ctx, !narrow || self.show_controls, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.control_panel(ui));
+            });
+
+        egui::SidePanel::right("dashboard_panel")
+            .default_width(side_panel_width)
+            .show_animated(ctx, self.show_dashboard, |ui| {
+                self.dashboard.show(ui, &self.recorder, self.plot_window)
+            });
+
+        egui::SidePanel::right("observables_panel")
+            .default_width(2.0 * side_panel_width)
+            .show_animated(ctx, !narrow || self.show_observables, |ui| {
+                self.observables_panel(ui)
+            });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::containers::Frame::canvas(ui.style()).show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    if narrow {
+                        ui.toggle_value(&mut self.show_controls, t!("show-controls"));
+                        ui.toggle_value(&mut self.show_observables, t!("show-observables"));
+                    }
+                    ui.toggle_value(&mut self.show_dashboard, t!("show-dashboard"));
+                    ui.toggle_value(&mut self.show_performance, t!("show-performance"));
+                    if ui
+                        .toggle_value(&mut self.show_structure_factor, t!("show-structure-factor"))
+                        .changed()
+                    {
+                        self.structure_factor = self.lattice.structure_factor();
+                    }
+                    if self.show_structure_factor {
+                        let label = ui.label(t!("structure-factor-every"));
+                        ui.add(
+                            egui::DragValue::new(&mut self.structure_factor_every).range(1..=1000),
+                        )
+                        .labelled_by(label.id);
+                    }
+                    if ui.input(|i| i.any_touches()) {
+                        ui.label(t!("touch-hint"));
+                    } else {
+                        ui.label(t!("hover-hint"));
+                        ui.label(t!("navigation-hint"));
+                    }
+                    if ui.button(t!("reset-view")).clicked() {
+                        println!("Reset view");
+                        self.lattice_view.reset();
+                    }
+                });
+
+                let magnetization = match self.timeline.selected() {
+                    Some(checkpoint) => checkpoint.lattice.magnetization(),
+                    None => self.lattice.magnetization(),
+                };
+                ui.add(MagnetizationGauge::new(magnetization).colormap(self.colormap));
+
+                // Draw the lattice as one square, leaving room for the hover details
+                // and for the replica and the structure factor on the right
+                let ui_size = ui.available_size();
+                let gap = ui.spacing().item_spacing.x;
+                let columns = [true, self.replica.is_some(), self.show_structure_factor]
+                    .into_iter()
+                    .filter(|shown| *shown)
+                    .count() as f32;
+                let side = ((ui_size.x - (columns - 1.0) * gap) / columns)
+                    .min(ui_size.y - (4.0 + columns) * ui.spacing().interact_size.y);
+                let mut hovered = None;
+                ui.horizontal(|ui| {
+                    // A checkpoint is only looked at, painting goes to the live lattice
+                    let view = match self.timeline.selected() {
+                        Some(checkpoint) => {
+                            LatticeView::new(&mut checkpoint.lattice, &mut self.lattice_view)
+                        }
+                        None => LatticeView::new(&mut self.lattice, &mut self.lattice_view)
+                            .brush(self.brush_size, &mut self.history)
+                            .touch_spin(self.touch_spin),
+                    };
+                    let canvas = view
+                        .mode(self.view)
+                        .colormap(self.colormap)
+                        .side(side)
+                        .show(ui);
+                    hovered = canvas.hovered.filter(|_| self.timeline.selected.is_none());
+                    self.largest_cluster = canvas.largest_cluster;
+                    self.performance.record_upload(canvas.upload_time);
+                    if self.show_performance {
+                        self.performance_overlay(ui, canvas.response.rect);
+                    }
+                    self.toast_overlay(ui, canvas.response.rect);
+                    if let Some(replica) = &mut self.replica {
+                        // The replica follows every parameter but the temperature
+                        replica.interactivity = self.lattice.interactivity;
+                        replica.field = self.lattice.field;
+                        replica.boundary = self.lattice.boundary;
+                        self.replica_view.follow(&self.lattice_view);
+                        LatticeView::new(replica, &mut self.replica_view)
+                            .mode(self.view)
+                            .colormap(self.colormap)
+                            .side(side)
+                            .navigable(false)
+                            .show(ui);
+                    }
+                    if self.show_structure_factor {
+                        self.structure_factor_canvas(ui, side);
+                    }
+                });
+                if let Some(checkpoint) = self.timeline.selected() {
+                    ui.label(t!("showing-checkpoint", sweep = checkpoint.sweep));
+                }
+                if let Some(replica) = &self.replica {
+                    ui.label(t!(
+                        "replica-temperatures",
+                        left = format!("{:.1}", self.lattice.temperature),
+                        right = format!("{:.1}", replica.temperature)
+                    ));
+                }
+
+                if let Some((x, y)) = hovered {
+                    let h_energy = self.lattice.calculate_hamiltonian(x, y);
+                    let delta_h = self.lattice.calculate_delta_h(x, y);
+                    let acceptence_criteria = self.lattice.calculate_acceptence_criteria(delta_h);
+                    let is_flipped = delta_h < 0.0 || acceptence_criteria > 0.5;
+                    let (up_color, down_color) = self.colormap.spin_colors(ui.visuals().dark_mode);
+
+                    if self.lattice.value[y].value[x] == 1 {
+                        ui.label(egui::RichText::new(t!("site-up", x = x, y = y)).color(up_color));
+                    } else {
+                        ui.label(
+                            egui::RichText::new(t!("site-down", x = x, y = y)).color(down_color),
+                        );
+                    }
+                    // Rows grow downwards on screen, so the y - 1 neighbour sits above
+                    let (left, right, down, up) = self.lattice.find_neighbours(x, y);
+                    let sum = left + right + down + up;
+                    ui.label(t!(
+                        "neighbours",
+                        left = format!("{left:+}"),
+                        right = format!("{right:+}"),
+                        above = format!("{down:+}"),
+                        below = format!("{up:+}")
+                    ));
+                    ui.label(t!("neighbour-sum", sum = format!("{sum:+}")));
+                    ui.label(t!("site-energy", energy = h_energy, diff = delta_h));
+                    ui.label(t!(
+                        "site-acceptance",
+                        criteria = acceptence_criteria,
+                        flipped = is_flipped
+                    ));
+                }
+            });
+        });
+    }
+}
+
+/// Spoken value of a temperature slider, e.g. "2.3 reduced, 2269 Kelvin"
+fn temperature_value(lattice: &Lattice) -> String {
+    t!(
+        "a11y-reduced-temperature",
+        reduced = format!("{:.1}", lattice.reduced_temperature()),
+        kelvin = format!("{:.0}", lattice.temperature)
+    )
+}
+
+/// Line chart of an observable versus sweep, linked to the other observables charts
+/// A vertical line marks paused_at when the simulation is paused
+fn observable_plot(
+    ui: &mut egui::Ui,
+    label: &str,
+    points: Vec<[f64; 2]>,
+    color: egui::Color32,
+    height: f32,
+    paused_at: Option<f64>,
+) {
+    Plot::new(label)
+        .height(height)
+        .x_axis_label(t!("sweep"))
+        .y_axis_label(label)
+        .link_axis("observables_axis", [true, false])
+        .link_cursor("observables_cursor", [true, false])
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(label, points).color(color));
+            if let Some(sweep) = paused_at {
+                plot_ui.vline(VLine::new(t!("paused"), sweep).color(egui::Color32::GRAY));
+            }
+        });
+}
+
+/// Bar chart of a histogram given as bin centers and counts
+fn histogram_plot(
+    ui: &mut egui::Ui,
+    label: &str,
+    histogram: Vec<(f64, usize)>,
+    color: egui::Color32,
+) {
+    // Bars fill their bin, a lone bin gets a fixed width
+    let width = match histogram.as_slice() {
+        [first, second, ..] => second.0 - first.0,
+        _ => 0.01,
+    };
+    let bars: Vec<Bar> = histogram
+        .into_iter()
+        .map(|(center, count)| Bar::new(center, count as f64).width(width))
+        .collect();
+    Plot::new(format!("{label} histogram"))
+        .height(120.0)
+        .x_axis_label(label)
+        .y_axis_label(t!("count"))
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(label, bars).color(color));
+        });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(simulation: &mut Simulation, sweeps: usize) {
+        for _ in 0..sweeps {
+            simulation.recorder.record(&simulation.lattice, 0);
+            simulation.detect_equilibrium();
+        }
+    }
+
+    #[test]
+    fn test_parameter_changes_restart_the_equilibrium_detection() {
+        let mut simulation = Simulation {
+            auto_pause: true,
+            equilibration_window: 5,
+            ..Default::default()
+        };
+        record(&mut simulation, 10);
+        assert!(simulation.is_paused);
+
+        simulation.is_paused = false;
+        simulation.lattice.temperature += 1.0;
+        record(&mut simulation, 9);
+        assert!(!simulation.is_paused);
+
+        record(&mut simulation, 1);
+        assert!(simulation.is_paused);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::app::App;
+use crate::simulation::Simulation;
+use eframe::egui::{self, Pos2};
+
+/// Layout version of the persisted app state, bumped with a migration whenever the layout changes
+/// * 0: a single simulation at the top level, before tabs
+/// * 1: a simulation per tab, each with its own canvas zoom and center
+/// * 2: the canvas zoom and center grouped in a lattice view, and this version saved
+pub const STATE_VERSION: u32 = 2;
+
+/// Persisted app state found at startup, waiting for the user to restore it or start fresh
+pub struct SavedSession {
+    /// layout version the state was saved with
+    pub version: u32,
+    /// the state migrated to the current layout, or why it could not be read
+    pub app: Result<Box<App>, String>,
+}
+
+/// Version and tabs of a persisted state, whatever its layout
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct Probe {
+    version: u32,
+    simulations: Vec<serde::de::IgnoredAny>,
+}
+
+/// Theme of the single simulation app of version 0
+#[derive(serde::Deserialize)]
+#[serde(default)]
+struct AppV0 {
+    theme: egui::Theme,
+}
+
+impl Default for AppV0 {
+    fn default() -> Self {
+        Self {
+            theme: egui::Theme::Dark,
+        }
+    }
+}
+
+/// Tabs of version 1, only read for their canvas
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct TabsV1 {
+    simulations: Vec<CanvasV1>,
+}
+
+/// Canvas zoom and center kept on the simulation up to version 1
+#[derive(serde::Deserialize)]
+#[serde(default)]
+struct CanvasV1 {
+    zoom: f32,
+    view_center: Pos2,
+}
+
+impl Default for CanvasV1 {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            view_center: Pos2::new(0.5, 0.5),
+        }
+    }
+}
+
+impl CanvasV1 {
+    /// Move the zoom and center into the lattice view of the simulation
+    fn apply(&self, simulation: &mut Simulation) {
+        simulation.lattice_view.zoom = self.zoom;
+        simulation.lattice_view.center = self.view_center;
+    }
+}
+
+/// Read the persisted app state and migrate it to the current layout
+/// Return None when nothing was saved yet
+pub fn load(storage: &dyn eframe::Storage) -> Option<SavedSession> {
+    let text = storage.get_string(eframe::APP_KEY)?;
+    let probe: Probe = ron::from_str(&text).unwrap_or_default();
+    // Versions before 2 did not save their version, tell them apart by their tabs
+    let version = match probe {
+        Probe {
+            version: 0,
+            simulations,
+        } if simulations.is_empty() => 0,
+        Probe { version: 0, .. } => 1,
+        Probe { version, .. } => version,
+    };
+    let app = migrate(&text, version)
+        .map(Box::new)
+        .map_err(|e| e.to_string());
+    Some(SavedSession { version, app })
+}
+
+/// Deserialize a state saved with an older layout version
+fn migrate(text: &str, version: u32) -> Result<App, ron::error::SpannedError> {
+    let mut app = match version {
+        0 => from_v0(text)?,
+        1 => from_v1(text)?,
+        _ => ron::from_str(text)?,
+    };
+    app.version = STATE_VERSION;
+    Ok(app)
+}
+
+/// Put the single simulation of version 0 in the first tab
+fn from_v0(text: &str) -> Result<App, ron::error::SpannedError> {
+    let AppV0 { theme } = ron::from_str(text)?;
+    let canvas: CanvasV1 = ron::from_str(text)?;
+    let mut simulation: Simulation = ron::from_str(text)?;
+    canvas.apply(&mut simulation);
+    let mut app = App::default();
+    app.simulations = vec![simulation];
+    app.theme = theme;
+    Ok(app)
+}
+
+/// Group the canvas zoom and center of every tab of version 1
+fn from_v1(text: &str) -> Result<App, ron::error::SpannedError> {
+    let mut app: App = ron::from_str(text)?;
+    let tabs: TabsV1 = ron::from_str(text)?;
+    for (simulation, canvas) in app.simulations.iter_mut().zip(&tabs.simulations) {
+        canvas.apply(simulation);
+    }
+    Ok(app)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct Storage(HashMap<String, String>);
+
+    impl eframe::Storage for Storage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn saved(text: &str) -> SavedSession {
+        let mut storage = Storage::default();
+        eframe::Storage::set_string(&mut storage, eframe::APP_KEY, text.to_string());
+        load(&storage).unwrap()
+    }
+
+    #[test]
+    fn test_version_0_moves_its_simulation_to_the_first_tab() {
+        let saved =
+            saved("(theme: Light, steps_per_frame: 3, zoom: 2.0, view_center: (x: 0.25, y: 0.75))");
+
+        let app = saved.app.unwrap();
+        assert_eq!(saved.version, 0);
+        assert_eq!(app.version, STATE_VERSION);
+        assert_eq!(app.theme, egui::Theme::Light);
+        assert_eq!(app.simulations.len(), 1);
+        assert_eq!(app.simulations[0].steps_per_frame, 3);
+        assert_eq!(app.simulations[0].lattice_view.zoom, 2.0);
+        assert_eq!(
+            app.simulations[0].lattice_view.center,
+            Pos2::new(0.25, 0.75)
+        );
+    }
+
+    #[test]
+    fn test_version_0_keeps_its_lattice_saved_before_the_seed() {
+        let saved = saved(
+            "(lattice: (value: [(value: [1, -1]), (value: [-1, -1])], size: 2, \
+             interactivity: 100.0, temperature: 100.0), is_paused: true)",
+        );
+
+        let app = saved.app.unwrap();
+        let lattice = &app.simulations[0].lattice;
+        assert_eq!(saved.version, 0);
+        assert_eq!(lattice.size, 2);
+        assert_eq!(lattice.value[0].value, vec![1, -1]);
+        assert_eq!(lattice.value[1].value, vec![-1, -1]);
+        assert_eq!((lattice.interactivity, lattice.temperature), (100.0, 100.0));
+        assert!(app.simulations[0].is_paused);
+    }
+
+    #[test]
+    fn test_version_1_groups_the_canvas_of_every_tab() {
+        let saved = saved(
+            "(simulations: [(zoom: 2.0, view_center: (x: 0.25, y: 0.75)), (steps_per_frame: 3)], \
+             active: 1, created: 2)",
+        );
+
+        let app = saved.app.unwrap();
+        assert_eq!(saved.version, 1);
+        assert_eq!(app.version, STATE_VERSION);
+        assert_eq!((app.active, app.simulations.len()), (1, 2));
+        assert_eq!(app.simulations[0].lattice_view.zoom, 2.0);
+        assert_eq!(
+            app.simulations[0].lattice_view.center,
+            Pos2::new(0.25, 0.75)
+        );
+        assert_eq!(app.simulations[1].lattice_view.zoom, 1.0);
+        assert_eq!(app.simulations[1].steps_per_frame, 3);
+    }
+
+    #[test]
+    fn test_corrupt_states_fail_to_load() {
+        let mistyped = saved("(simulations: [(steps_per_frame: \"many\")], version: 2)");
+        let truncated = saved("(version: 2, simulations: [(steps_per_frame: 3");
+
+        assert_eq!(mistyped.version, 2);
+        assert!(mistyped.app.is_err());
+        assert!(truncated.app.is_err());
+    }
+
+    #[test]
+    fn test_newer_versions_load_what_they_share() {
+        let saved = saved("(version: 3, theme: Light, simulations: [(steps_per_frame: 3)])");
+
+        let app = saved.app.unwrap();
+        assert_eq!(saved.version, 3);
+        assert_eq!(app.version, STATE_VERSION);
+        assert_eq!(app.theme, egui::Theme::Light);
+        assert_eq!(app.simulations[0].steps_per_frame, 3);
+    }
+}
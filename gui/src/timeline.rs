@@ -0,0 +1,49 @@
+use internal::Lattice;
+use std::collections::VecDeque;
+
+/// Lattice saved after a sweep
+pub struct Checkpoint {
+    /// sweep the lattice was saved after
+    pub sweep: u64,
+    pub lattice: Lattice,
+}
+
+/// Ring buffer of the latest checkpoints, and the one shown instead of the live lattice
+#[derive(Default)]
+pub struct Timeline {
+    /// checkpoints, oldest first
+    pub checkpoints: VecDeque<Checkpoint>,
+    /// index of the shown checkpoint, None shows the live lattice
+    pub selected: Option<usize>,
+}
+
+impl Timeline {
+    /// Save a copy of the lattice, dropping the oldest checkpoints beyond depth
+    pub fn push(&mut self, sweep: u64, lattice: &Lattice, depth: usize) {
+        while self.checkpoints.len() >= depth.max(1) {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back(Checkpoint {
+            sweep,
+            lattice: lattice.clone(),
+        });
+    }
+
+    /// Checkpoint shown instead of the live lattice
+    pub fn selected(&mut self) -> Option<&mut Checkpoint> {
+        self.checkpoints.get_mut(self.selected?)
+    }
+
+    /// Drop the checkpoints after sweep, e.g. when resuming from an older one
+    pub fn rewind(&mut self, sweep: u64) {
+        self.checkpoints
+            .retain(|checkpoint| checkpoint.sweep <= sweep);
+        self.selected = None;
+    }
+
+    /// Drop every checkpoint and go back to the live lattice
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+        self.selected = None;
+    }
+}
@@ -0,0 +1,265 @@
+use crate::i18n::t;
+use eframe::egui::{Color32, ColorImage};
+use internal::Lattice;
+
+/// Colors of spin up and spin down sites in dark mode
+const SPIN_UP_COLOR: Color32 = Color32::DARK_RED;
+const SPIN_DOWN_COLOR: Color32 = Color32::LIGHT_BLUE;
+
+/// Colors of spin up and spin down sites in light mode, darker to stay readable on white
+const LIGHT_SPIN_UP_COLOR: Color32 = Color32::from_rgb(200, 30, 30);
+const LIGHT_SPIN_DOWN_COLOR: Color32 = Color32::from_rgb(30, 80, 180);
+
+/// Colors of the clusters other than the largest one, cycled by cluster label
+const CLUSTER_COLORS: [Color32; 8] = [
+    Color32::from_rgb(70, 110, 160),
+    Color32::from_rgb(160, 80, 80),
+    Color32::from_rgb(80, 140, 90),
+    Color32::from_rgb(130, 90, 150),
+    Color32::from_rgb(60, 140, 140),
+    Color32::from_rgb(150, 120, 70),
+    Color32::from_rgb(110, 110, 110),
+    Color32::from_rgb(150, 90, 130),
+];
+
+/// Stops of the continuous energy colormap, from the lowest to the highest energy
+const ENERGY_STOPS: [Color32; 4] = [
+    Color32::from_rgb(0, 0, 4),
+    Color32::from_rgb(120, 28, 109),
+    Color32::from_rgb(237, 105, 37),
+    Color32::from_rgb(252, 255, 164),
+];
+
+/// Stops of viridis, perceptually uniform from dark purple to yellow
+const VIRIDIS_STOPS: [Color32; 5] = [
+    Color32::from_rgb(68, 1, 84),
+    Color32::from_rgb(59, 82, 139),
+    Color32::from_rgb(33, 145, 140),
+    Color32::from_rgb(94, 201, 98),
+    Color32::from_rgb(253, 231, 37),
+];
+
+/// Viridis colors of the clusters other than the largest one, which takes the yellow end
+const VIRIDIS_CLUSTER_COLORS: [Color32; 8] = [
+    Color32::from_rgb(68, 1, 84),
+    Color32::from_rgb(72, 36, 117),
+    Color32::from_rgb(65, 68, 135),
+    Color32::from_rgb(53, 95, 141),
+    Color32::from_rgb(42, 120, 142),
+    Color32::from_rgb(33, 145, 140),
+    Color32::from_rgb(34, 168, 132),
+    Color32::from_rgb(68, 191, 112),
+];
+
+/// Stops of cividis, readable with every kind of color vision deficiency
+const CIVIDIS_STOPS: [Color32; 5] = [
+    Color32::from_rgb(0, 34, 78),
+    Color32::from_rgb(64, 77, 107),
+    Color32::from_rgb(124, 123, 120),
+    Color32::from_rgb(188, 175, 111),
+    Color32::from_rgb(253, 231, 55),
+];
+
+/// Okabe-Ito colors of the clusters other than the largest one, which takes the yellow
+const OKABE_ITO_CLUSTER_COLORS: [Color32; 6] = [
+    Color32::from_rgb(230, 159, 0),
+    Color32::from_rgb(86, 180, 233),
+    Color32::from_rgb(0, 158, 115),
+    Color32::from_rgb(0, 114, 178),
+    Color32::from_rgb(213, 94, 0),
+    Color32::from_rgb(204, 121, 167),
+];
+
+/// How the lattice canvas colors its sites
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ViewMode {
+    /// spin up and spin down colors
+    #[default]
+    Spins,
+    /// a color per connected cluster of aligned spins, the largest one highlighted
+    Clusters,
+    /// heatmap of the local bond energy, bright on domain walls and frustrated sites
+    Energy,
+}
+
+impl ViewMode {
+    /// Every view mode in display order
+    pub const ALL: [ViewMode; 3] = [ViewMode::Spins, ViewMode::Clusters, ViewMode::Energy];
+
+    /// Human readable name of the view mode
+    pub fn name(self) -> &'static str {
+        match self {
+            ViewMode::Spins => "Spins",
+            ViewMode::Clusters => "Clusters",
+            ViewMode::Energy => "Energy",
+        }
+    }
+
+    /// Name of the view mode in the UI language
+    pub fn label(self) -> String {
+        match self {
+            ViewMode::Spins => t!("view-spins"),
+            ViewMode::Clusters => t!("view-clusters"),
+            ViewMode::Energy => t!("view-energy"),
+        }
+    }
+}
+
+/// Palette of the spins, energy heatmap, and clusters views
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Colormap {
+    /// red and blue spins, inferno energies
+    #[default]
+    Classic,
+    /// viridis everywhere
+    Viridis,
+    /// orange and blue spins, cividis energies, and Okabe-Ito clusters
+    ColorBlind,
+}
+
+impl Colormap {
+    /// Every colormap in display order
+    pub const ALL: [Colormap; 3] = [Colormap::Classic, Colormap::Viridis, Colormap::ColorBlind];
+
+    /// Human readable name of the colormap
+    pub fn name(self) -> &'static str {
+        match self {
+            Colormap::Classic => "Classic",
+            Colormap::Viridis => "Viridis",
+            Colormap::ColorBlind => "Color-blind safe",
+        }
+    }
+
+    /// Name of the colormap in the UI language
+    pub fn label(self) -> String {
+        match self {
+            Colormap::Classic => t!("colormap-classic"),
+            Colormap::Viridis => t!("colormap-viridis"),
+            Colormap::ColorBlind => t!("colormap-color-blind"),
+        }
+    }
+
+    /// Colors of spin up and spin down sites for the dark or light theme
+    pub fn spin_colors(self, dark_mode: bool) -> (Color32, Color32) {
+        match self {
+            Colormap::Classic if dark_mode => (SPIN_UP_COLOR, SPIN_DOWN_COLOR),
+            Colormap::Classic => (LIGHT_SPIN_UP_COLOR, LIGHT_SPIN_DOWN_COLOR),
+            Colormap::Viridis => (VIRIDIS_STOPS[4], VIRIDIS_STOPS[0]),
+            Colormap::ColorBlind => (OKABE_ITO_CLUSTER_COLORS[0], OKABE_ITO_CLUSTER_COLORS[3]),
+        }
+    }
+
+    /// Color of the energy heatmap at t, from 0 for the lowest to 1 for the highest energy
+    pub fn energy_color(self, t: f64) -> Color32 {
+        let stops: &[Color32] = match self {
+            Colormap::Classic => &ENERGY_STOPS,
+            Colormap::Viridis => &VIRIDIS_STOPS,
+            Colormap::ColorBlind => &CIVIDIS_STOPS,
+        };
+        interpolate(stops, t)
+    }
+
+    /// Colors cycled by the clusters, and the color of the largest cluster
+    fn cluster_colors(self) -> (&'static [Color32], Color32) {
+        match self {
+            Colormap::Classic => (&CLUSTER_COLORS, Color32::GOLD),
+            Colormap::Viridis => (&VIRIDIS_CLUSTER_COLORS, VIRIDIS_STOPS[4]),
+            Colormap::ColorBlind => (&OKABE_ITO_CLUSTER_COLORS, Color32::from_rgb(240, 228, 66)),
+        }
+    }
+}
+
+/// Image of the lattice, one pixel per spin, colored by the view mode and colormap
+/// Also return the size of the largest cluster in the clusters view
+pub fn render_lattice(
+    lattice: &Lattice,
+    view: ViewMode,
+    colormap: Colormap,
+    dark_mode: bool,
+) -> (ColorImage, Option<usize>) {
+    let size = [lattice.size, lattice.size];
+    match view {
+        ViewMode::Spins => {
+            let (up_color, down_color) = colormap.spin_colors(dark_mode);
+            let pixels = lattice
+                .value
+                .iter()
+                .flat_map(|spins| spins.value.iter())
+                .map(|spin| if *spin == 1 { up_color } else { down_color })
+                .collect();
+            (ColorImage::new(size, pixels), None)
+        }
+        ViewMode::Clusters => {
+            let labels = lattice.cluster_labels();
+            let mut sizes = Vec::new();
+            for label in labels.iter().flatten() {
+                if *label >= sizes.len() {
+                    sizes.resize(label + 1, 0);
+                }
+                sizes[*label] += 1;
+            }
+            let (largest, largest_size) = sizes
+                .iter()
+                .copied()
+                .enumerate()
+                .max_by_key(|(_, size)| *size)
+                .unwrap_or_default();
+            let (colors, largest_color) = colormap.cluster_colors();
+            let pixels = labels
+                .iter()
+                .flatten()
+                .map(|label| {
+                    if *label == largest {
+                        largest_color
+                    } else {
+                        colors[label % colors.len()]
+                    }
+                })
+                .collect();
+            (ColorImage::new(size, pixels), Some(largest_size))
+        }
+        ViewMode::Energy => {
+            // Local energies lie within -(4|J| + |h|) and 4|J| + |h|
+            let bound = 4.0 * lattice.interactivity.abs() + lattice.field.abs();
+            let pixels = lattice
+                .local_energy_map()
+                .iter()
+                .flatten()
+                .map(|energy| {
+                    if bound == 0.0 {
+                        return colormap.energy_color(0.5);
+                    }
+                    colormap.energy_color((energy + bound) / (2.0 * bound))
+                })
+                .collect();
+            (ColorImage::new(size, pixels), None)
+        }
+    }
+}
+
+/// Color at t of the stops spread evenly from 0 to 1, linearly interpolated
+fn interpolate(stops: &[Color32], t: f64) -> Color32 {
+    let scaled = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let index = (scaled as usize).min(stops.len() - 2);
+    let (low, high) = (stops[index], stops[index + 1]);
+    let fraction = (scaled - index as f64) as f32;
+    let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * fraction) as u8;
+    Color32::from_rgb(
+        mix(low.r(), high.r()),
+        mix(low.g(), high.g()),
+        mix(low.b(), high.b()),
+    )
+}
+
+/// Image of the structure factor, one pixel per wave vector, colored by log(1 + S(k))
+/// relative to the highest value N, reached at k = 0 by a fully ordered lattice
+pub fn render_structure_factor(structure_factor: &[Vec<f64>], colormap: Colormap) -> ColorImage {
+    let size = structure_factor.len();
+    let top = ((size * size) as f64).ln_1p();
+    let pixels = structure_factor
+        .iter()
+        .flatten()
+        .map(|value| colormap.energy_color(value.ln_1p() / top))
+        .collect();
+    ColorImage::new([size, size], pixels)
+}
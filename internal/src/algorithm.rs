@@ -0,0 +1,197 @@
+use crate::{Lattice, KB};
+use core::f64;
+use rand::Rng;
+
+/// Monte Carlo update used to run a sweep
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Algorithm {
+    /// single spin flips accepted with the Metropolis criteria
+    #[default]
+    Metropolis,
+    /// single spins drawn from their local equilibrium distribution
+    HeatBath,
+    /// clusters of aligned spins flipped at once
+    Wolff,
+}
+
+impl Algorithm {
+    /// Every algorithm in cycling order
+    pub const ALL: [Algorithm; 3] = [Algorithm::Metropolis, Algorithm::HeatBath, Algorithm::Wolff];
+
+    /// Human readable name of the algorithm
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Metropolis => "Metropolis",
+            Algorithm::HeatBath => "Heat-bath",
+            Algorithm::Wolff => "Wolff",
+        }
+    }
+
+    /// Algorithm following this one in cycling order
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|a| *a == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl Lattice {
+    /// Heat-bath sweep, size * size updates on random points
+    /// A spin is set up with probability 1 / (1 + e^(-2 * Beta * (J * sum_of_all_neighbors + h)))
+    pub(crate) fn heat_bath_sweep(&mut self, on_flip: &mut impl FnMut(usize, usize)) -> usize {
+        let minus_two_beta = -2.0 / (KB * self.temperature);
+        let mut flipped = 0;
+        for _ in 0..self.size * self.size {
+            let (x, y) = self.pick_random_point();
+            let (left, right, down, up) = self.find_neighbours(x, y);
+            let field = self.interactivity * f64::from(left + right + down + up) + self.field;
+            let mut probability_up = 1.0 / (1.0 + (minus_two_beta * field).exp());
+            if probability_up.is_nan() {
+                probability_up = 0.5;
+            }
+            let spin = if self.rng.0.random::<f64>() < probability_up {
+                1
+            } else {
+                -1
+            };
+            if self.value[y].value[x] != spin {
+                self.value[y].value[x] = spin;
+                on_flip(x, y);
+                flipped += 1;
+            }
+        }
+        flipped
+    }
+
+    /// Wolff sweep, grow and flip clusters from random seeds until size * size spins flipped
+    /// A neighbour aligned with the cluster joins it with probability 1 - e^(-2 * Beta * J)
+    /// Under an external field the cluster flip is accepted with probability e^(-2 * Beta * h * spin * cluster_size)
+    pub(crate) fn wolff_sweep(&mut self, on_flip: &mut impl FnMut(usize, usize)) -> usize {
+        let minus_two_beta_j = -2.0 * self.interactivity / (KB * self.temperature);
+        let mut add_probability = 1.0 - minus_two_beta_j.exp();
+        if add_probability.is_nan() {
+            add_probability = 0.0;
+        }
+        let mut flipped = 0;
+        // Bound the attempts so a field rejecting every cluster still ends the sweep
+        for _ in 0..self.size * self.size {
+            if flipped >= self.size * self.size {
+                break;
+            }
+            flipped += self.flip_wolff_cluster(add_probability, on_flip);
+        }
+        flipped
+    }
+
+    /// Grow a cluster from a random point and flip it, return the number of flipped spins
+    fn flip_wolff_cluster(
+        &mut self,
+        add_probability: f64,
+        on_flip: &mut impl FnMut(usize, usize),
+    ) -> usize {
+        let (x, y) = self.pick_random_point();
+        let spin = self.value[y].value[x];
+        // Flip spins as soon as they join so they are never added twice
+        self.flip(x, y);
+        let mut cluster = vec![(x, y)];
+        let mut stack = vec![(x, y)];
+
+        while let Some((x, y)) = stack.pop() {
+            let neighbours: Vec<(usize, usize)> = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .into_iter()
+                .filter_map(|(dx, dy)| self.neighbour_point(x, y, dx, dy))
+                .collect();
+            for (nx, ny) in neighbours {
+                if self.value[ny].value[nx] != spin {
+                    continue;
+                }
+                if self.rng.0.random::<f64>() < add_probability {
+                    self.flip(nx, ny);
+                    cluster.push((nx, ny));
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        if self.field != 0.0 {
+            let field_delta_h = 2.0 * self.field * f64::from(spin) * cluster.len() as f64;
+            let acceptance = (-field_delta_h / (KB * self.temperature)).exp();
+            if self.rng.0.random::<f64>() >= acceptance {
+                for (x, y) in cluster {
+                    self.flip(x, y);
+                }
+                return 0;
+            }
+        }
+        for (x, y) in &cluster {
+            on_flip(*x, *y);
+        }
+        cluster.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn aligned_lattice(size: usize) -> Lattice {
+        let mut lattice = Lattice::with_seed(size, 1.0, 1.0, 42);
+        for spins in &mut lattice.value {
+            spins.value.fill(1);
+        }
+        lattice
+    }
+
+    #[test]
+    fn test_next_cycles_through_all() {
+        let mut algorithm = Algorithm::default();
+        for expected in Algorithm::ALL.iter().cycle().skip(1).take(3) {
+            algorithm = algorithm.next();
+            assert_eq!(algorithm, *expected);
+        }
+    }
+
+    #[test]
+    fn test_heat_bath_keeps_cold_aligned_lattice() {
+        let mut lattice = aligned_lattice(6);
+
+        let flipped = lattice.sweep_with(Algorithm::HeatBath, |_, _| {});
+
+        assert_eq!(flipped, 0);
+        assert_eq!(lattice.magnetization(), 1.0);
+    }
+
+    #[test]
+    fn test_wolff_flips_cold_aligned_lattice_at_once() {
+        let mut lattice = aligned_lattice(6);
+        let mut flips = Vec::new();
+
+        let flipped = lattice.sweep_with(Algorithm::Wolff, |x, y| flips.push((x, y)));
+
+        assert_eq!(flipped, 36);
+        assert_eq!(flips.len(), 36);
+        assert_eq!(lattice.magnetization(), -1.0);
+    }
+
+    #[test]
+    fn test_heat_bath_follows_strong_field() {
+        let mut lattice = aligned_lattice(6);
+        lattice.field = -5.0;
+
+        for _ in 0..20 {
+            lattice.sweep_with(Algorithm::HeatBath, |_, _| {});
+        }
+
+        assert_eq!(lattice.magnetization(), -1.0);
+    }
+
+    #[test]
+    fn test_wolff_rejects_cluster_against_strong_field() {
+        let mut lattice = aligned_lattice(6);
+        lattice.field = 5.0;
+
+        let flipped = lattice.sweep_with(Algorithm::Wolff, |_, _| {});
+
+        assert_eq!(flipped, 0);
+        assert_eq!(lattice.magnetization(), 1.0);
+    }
+}
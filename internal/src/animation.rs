@@ -0,0 +1,144 @@
+use crate::{
+    export::{SPIN_DOWN_COLOR, SPIN_UP_COLOR},
+    Lattice,
+};
+use std::io::{self, Write};
+
+/// Frames of a lattice evolution, encoded as an animated GIF once captured
+/// Frames are kept as one byte per spin and stop being captured past max_bytes
+#[derive(Clone, Debug)]
+pub struct Animation {
+    /// number of spins per side of every frame
+    pub size: usize,
+    /// pixels per spin side in the encoded GIF
+    pub scale: usize,
+    /// frames shown per second in the encoded GIF
+    pub frame_rate: f64,
+    /// upper bound on the memory taken by the captured frames
+    pub max_bytes: usize,
+    // Captured frames, 1 for up spins and 0 for down spins in row order
+    frames: Vec<Vec<u8>>,
+}
+
+impl Animation {
+    /// Create a new empty Animation of size x size lattices
+    pub fn new(size: usize, scale: usize, frame_rate: f64, max_bytes: usize) -> Self {
+        Self {
+            size,
+            scale: scale.max(1),
+            frame_rate,
+            max_bytes,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Capture the lattice as the next frame
+    /// Return false without capturing when the memory bound is reached or the size changed
+    pub fn push(&mut self, lattice: &Lattice) -> bool {
+        let frame_bytes = self.size * self.size;
+        if lattice.size != self.size || (self.frames.len() + 1) * frame_bytes > self.max_bytes {
+            return false;
+        }
+        self.frames.push(
+            lattice
+                .value
+                .iter()
+                .flat_map(|spins| spins.value.iter().map(|spin| u8::from(*spin == 1)))
+                .collect(),
+        );
+        true
+    }
+
+    /// Number of captured frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Encode the captured frames as GIF bytes, e.g. to download them
+    pub fn to_gif(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_gif(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Write the captured frames as an endlessly looping GIF into writer
+    pub fn write_gif(&self, writer: impl Write) -> io::Result<()> {
+        let side = self.size * self.scale;
+        let side = u16::try_from(side).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Animation side of {side} pixels is too large"),
+            )
+        })?;
+        // GIF delays are in hundredths of a second
+        let delay = (100.0 / self.frame_rate.max(0.01))
+            .round()
+            .clamp(1.0, 65535.0) as u16;
+        let palette = [SPIN_DOWN_COLOR, SPIN_UP_COLOR].concat();
+
+        let mut encoder =
+            gif::Encoder::new(writer, side, side, &palette).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+        for spins in &self.frames {
+            let mut pixels = Vec::with_capacity(usize::from(side) * usize::from(side));
+            for row in spins.chunks(self.size) {
+                let scaled: Vec<u8> = row
+                    .iter()
+                    .flat_map(|spin| std::iter::repeat_n(*spin, self.scale))
+                    .collect();
+                for _ in 0..self.scale {
+                    pixels.extend_from_slice(&scaled);
+                }
+            }
+            let mut frame = gif::Frame::from_indexed_pixels(side, side, pixels, None);
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+        encoder.into_inner().map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push_stops_at_memory_bound() {
+        let lattice = Lattice::with_seed(4, 1.0, 1.0, 42);
+        let mut animation = Animation::new(4, 1, 10.0, 40);
+
+        assert!(animation.push(&lattice));
+        assert!(animation.push(&lattice));
+        assert!(!animation.push(&lattice));
+        assert!(!animation.push(&Lattice::with_seed(2, 1.0, 1.0, 42)));
+        assert_eq!(animation.len(), 2);
+    }
+
+    #[test]
+    fn test_gif_frames() {
+        let lattice = Lattice::with_seed(3, 1.0, 1.0, 42);
+        let mut animation = Animation::new(3, 2, 10.0, 1024);
+        animation.push(&lattice);
+        animation.push(&lattice);
+
+        let bytes = animation.to_gif().unwrap();
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(bytes.as_slice())
+            .unwrap();
+
+        assert_eq!((decoder.width(), decoder.height()), (6, 6));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 10);
+            frames += 1;
+        }
+        assert_eq!(frames, 2);
+    }
+}
@@ -0,0 +1,73 @@
+/// How neighbours outside of the lattice edges are treated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Boundary {
+    /// a neighbour outside of the lattice takes the value of the edge spin itself
+    #[default]
+    Mirror,
+    /// edge spins have no neighbour outside of the lattice
+    Open,
+    /// the lattice wraps around into a torus
+    Periodic,
+    /// neighbours outside of the lattice are fixed up spins
+    Fixed,
+}
+
+impl Boundary {
+    /// Every boundary condition in cycling order
+    pub const ALL: [Boundary; 4] = [
+        Boundary::Mirror,
+        Boundary::Open,
+        Boundary::Periodic,
+        Boundary::Fixed,
+    ];
+
+    /// Human readable name of the boundary condition
+    pub fn name(self) -> &'static str {
+        match self {
+            Boundary::Mirror => "Mirror",
+            Boundary::Open => "Open",
+            Boundary::Periodic => "Periodic",
+            Boundary::Fixed => "Fixed",
+        }
+    }
+
+    /// Boundary condition following this one in cycling order
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|b| *b == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Lattice;
+
+    fn corner_neighbours(boundary: Boundary) -> (i32, i32, i32, i32) {
+        let mut lattice = Lattice::with_seed(3, 1.0, 1.0, 42);
+        lattice.value[0].value = vec![-1, 1, -1];
+        lattice.value[1].value = vec![1, -1, -1];
+        lattice.value[2].value = vec![-1, -1, 1];
+        lattice.boundary = boundary;
+        lattice.find_neighbours(0, 0)
+    }
+
+    #[test]
+    fn test_corner_neighbours() {
+        assert_eq!(corner_neighbours(Boundary::Mirror), (-1, 1, -1, 1));
+        assert_eq!(corner_neighbours(Boundary::Open), (0, 1, 0, 1));
+        assert_eq!(corner_neighbours(Boundary::Fixed), (1, 1, 1, 1));
+        assert_eq!(corner_neighbours(Boundary::Periodic), (-1, 1, -1, 1));
+    }
+
+    #[test]
+    fn test_periodic_wraps_around() {
+        let mut lattice = Lattice::with_seed(3, 1.0, 1.0, 42);
+        lattice.boundary = Boundary::Periodic;
+
+        assert_eq!(lattice.neighbour_point(0, 0, -1, 0), Some((2, 0)));
+        assert_eq!(lattice.neighbour_point(2, 2, 0, 1), Some((2, 0)));
+        lattice.boundary = Boundary::Open;
+        assert_eq!(lattice.neighbour_point(0, 0, -1, 0), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::Lattice;
+
+impl Lattice {
+    /// Hoshen-Kopelman labels of the connected same-spin domains, indexed by y then x
+    /// Labels are numbered from 0 in scan order, neighbours follow the boundary condition
+    pub fn cluster_labels(&self) -> Vec<Vec<usize>> {
+        let index = |x: usize, y: usize| y * self.size + x;
+        let mut parents: Vec<usize> = (0..self.size * self.size).collect();
+
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let spin = self.value[y].value[x];
+                for (dx, dy) in [(1, 0), (0, 1)] {
+                    let Some((nx, ny)) = self.neighbour_point(x, y, dx, dy) else {
+                        continue;
+                    };
+                    if self.value[ny].value[nx] == spin {
+                        union(&mut parents, index(x, y), index(nx, ny));
+                    }
+                }
+            }
+        }
+
+        // Renumber the roots in scan order so labels are compact
+        let mut labels = vec![usize::MAX; parents.len()];
+        let mut count = 0;
+        (0..self.size)
+            .map(|y| {
+                (0..self.size)
+                    .map(|x| {
+                        let root = find(&mut parents, index(x, y));
+                        if labels[root] == usize::MAX {
+                            labels[root] = count;
+                            count += 1;
+                        }
+                        labels[root]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Number of spins of every connected same-spin domain, indexed by cluster label
+    pub fn cluster_sizes(&self) -> Vec<usize> {
+        let mut sizes = Vec::new();
+        for label in self.cluster_labels().into_iter().flatten() {
+            if label >= sizes.len() {
+                sizes.resize(label + 1, 0);
+            }
+            sizes[label] += 1;
+        }
+        sizes
+    }
+}
+
+fn find(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    // Keep the earliest site in scan order as the root
+    if a < b {
+        parents[b] = a;
+    } else {
+        parents[a] = b;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::boundary::Boundary;
+
+    fn striped_lattice() -> Lattice {
+        let mut lattice = Lattice::with_seed(4, 1.0, 1.0, 42);
+        for (y, spins) in lattice.value.iter_mut().enumerate() {
+            spins.value.fill(if y % 2 == 0 { 1 } else { -1 });
+        }
+        lattice
+    }
+
+    #[test]
+    fn test_stripes_are_separate_clusters() {
+        let labels = striped_lattice().cluster_labels();
+
+        assert_eq!(labels[0], vec![0; 4]);
+        assert_eq!(labels[1], vec![1; 4]);
+        assert_eq!(labels[3], vec![3; 4]);
+    }
+
+    #[test]
+    fn test_cluster_sizes_count_spins_per_stripe() {
+        assert_eq!(striped_lattice().cluster_sizes(), vec![4; 4]);
+    }
+
+    #[test]
+    fn test_periodic_boundary_joins_edges() {
+        let mut lattice = Lattice::with_seed(4, 1.0, 1.0, 42);
+        lattice.boundary = Boundary::Periodic;
+        for (y, spins) in lattice.value.iter_mut().enumerate() {
+            spins.value.fill(if y == 0 || y == 3 { 1 } else { -1 });
+        }
+
+        let labels = lattice.cluster_labels();
+
+        assert_eq!(labels[3], labels[0]);
+        assert_eq!(labels[1], vec![1; 4]);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::Lattice;
+
+impl Lattice {
+    /// Connected spin-spin correlation C(r) = <s_i * s_i+r> - <s>^2 for r in 0..=max_distance
+    /// Pairs are taken along rows and columns, skipping pairs outside of non periodic edges
+    pub fn correlation_function(&self, max_distance: usize) -> Vec<f64> {
+        let magnetization = self.magnetization();
+        (0..=max_distance)
+            .map(|r| {
+                let distance = r as isize;
+                let mut total = 0;
+                let mut pairs = 0;
+                for y in 0..self.size {
+                    for x in 0..self.size {
+                        let spin = self.value[y].value[x];
+                        for (dx, dy) in [(distance, 0), (0, distance)] {
+                            if let Some((nx, ny)) = self.neighbour_point(x, y, dx, dy) {
+                                total += spin * self.value[ny].value[nx];
+                                pairs += 1;
+                            }
+                        }
+                    }
+                }
+                if pairs == 0 {
+                    return 0.0;
+                }
+                f64::from(total) / f64::from(pairs) - magnetization * magnetization
+            })
+            .collect()
+    }
+}
+
+/// Correlation length xi of C(r) ~ e^(-r / xi), fitted by least squares on ln C(r)
+/// Only the leading positive values from r = 1 are fitted
+/// None when fewer than two values are usable or C(r) does not decay
+pub fn correlation_length(correlation: &[f64]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = correlation
+        .iter()
+        .enumerate()
+        .skip(1)
+        .take_while(|(_, c)| **c > 0.0)
+        .map(|(r, c)| (r as f64, c.ln()))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let count = points.len() as f64;
+    let mean_r = points.iter().map(|(r, _)| r).sum::<f64>() / count;
+    let mean_ln = points.iter().map(|(_, ln)| ln).sum::<f64>() / count;
+    let covariance: f64 = points
+        .iter()
+        .map(|(r, ln)| (r - mean_r) * (ln - mean_ln))
+        .sum();
+    let variance: f64 = points.iter().map(|(r, _)| (r - mean_r).powi(2)).sum();
+    let slope = covariance / variance;
+    (slope < 0.0).then(|| -1.0 / slope)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::boundary::Boundary;
+
+    #[test]
+    fn test_checkerboard_alternates() {
+        let mut lattice = Lattice::with_seed(6, 1.0, 1.0, 42);
+        lattice.boundary = Boundary::Periodic;
+        for y in 0..6 {
+            for x in 0..6 {
+                lattice.set_spin(x, y, if (x + y) % 2 == 0 { 1 } else { -1 });
+            }
+        }
+
+        let correlation = lattice.correlation_function(2);
+
+        assert_eq!(correlation, vec![1.0, -1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_correlation_length_of_exponential() {
+        let correlation: Vec<f64> = (0..8).map(|r| (-(r as f64) / 3.0).exp()).collect();
+
+        let length = correlation_length(&correlation).unwrap();
+
+        assert!((length - 3.0).abs() < 1e-9);
+        assert_eq!(correlation_length(&[1.0, 0.0, 0.0]), None);
+    }
+}
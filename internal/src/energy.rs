@@ -0,0 +1,36 @@
+use crate::Lattice;
+
+impl Lattice {
+    /// Bond energy of every spin with its nearest neighbours, indexed by y then x
+    /// Aligned regions sit at -4J while spins on domain walls stand out with higher values
+    pub fn local_energy_map(&self) -> Vec<Vec<f64>> {
+        (0..self.size)
+            .map(|y| {
+                (0..self.size)
+                    .map(|x| self.calculate_hamiltonian(x, y))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flipped_spin_stands_out() {
+        let mut lattice = Lattice::with_seed(5, 1.0, 1.0, 42);
+        for spins in &mut lattice.value {
+            spins.value.fill(1);
+        }
+        lattice.flip(2, 2);
+
+        let map = lattice.local_energy_map();
+
+        assert_eq!(map[2][2], 4.0);
+        assert_eq!(map[2][1], -2.0);
+        assert_eq!(map[1][2], -2.0);
+        assert_eq!(map[0][0], -4.0);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    snapshot::{Snapshot, SNAPSHOT_PNG_KEYWORD},
+    Lattice,
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// RGB color of up spins in exported images
+pub const SPIN_UP_COLOR: [u8; 3] = [0, 128, 0];
+
+/// RGB color of down spins in exported images
+pub const SPIN_DOWN_COLOR: [u8; 3] = [0, 0, 0];
+
+/// Render the lattice into a plain-text grid
+/// Up spins are `+`, down spins are `-`, one row of the lattice per line
+pub fn to_text(lattice: &Lattice) -> String {
+    lattice
+        .value
+        .iter()
+        .map(|spins| {
+            let mut line: String = spins
+                .value
+                .iter()
+                .map(|spin| if *spin == 1 { '+' } else { '-' })
+                .collect();
+            line.push('\n');
+            line
+        })
+        .collect()
+}
+
+/// Write the lattice as a plain-text grid into a file
+pub fn save_text(lattice: &Lattice, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(to_text(lattice).as_bytes())?;
+    writer.flush()
+}
+
+/// Write the lattice as a PNG image into a file
+/// Every spin is drawn as a scale by scale square of pixels
+pub fn save_png(lattice: &Lattice, path: impl AsRef<Path>, scale: usize) -> io::Result<()> {
+    write_png(lattice, BufWriter::new(File::create(path)?), scale)
+}
+
+/// Encode the lattice as PNG image bytes, e.g. to download them
+/// Every spin is drawn as a scale by scale square of pixels
+pub fn to_png(lattice: &Lattice, scale: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    write_png(lattice, &mut bytes, scale)?;
+    Ok(bytes)
+}
+
+/// Write the lattice as a PNG image into writer
+/// Every spin is drawn as a scale by scale square of pixels,
+/// and a snapshot of the lattice is embedded in a compressed text chunk to load the image back
+pub fn write_png(lattice: &Lattice, writer: impl Write, scale: usize) -> io::Result<()> {
+    let scale = scale.max(1);
+    let side = lattice.size * scale;
+    let dimension = u32::try_from(side).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Image side of {side} pixels is too large"),
+        )
+    })?;
+
+    let mut pixels = Vec::with_capacity(side * side * 3);
+    for spins in &lattice.value {
+        let row: Vec<u8> = spins
+            .value
+            .iter()
+            .flat_map(|spin| {
+                let color = if *spin == 1 {
+                    SPIN_UP_COLOR
+                } else {
+                    SPIN_DOWN_COLOR
+                };
+                color.repeat(scale)
+            })
+            .collect();
+        for _ in 0..scale {
+            pixels.extend_from_slice(&row);
+        }
+    }
+
+    let mut encoder = png::Encoder::new(writer, dimension, dimension);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let snapshot =
+        String::from_utf8(Snapshot::new(lattice).to_bytes()?).map_err(io::Error::other)?;
+    encoder
+        .add_ztxt_chunk(SNAPSHOT_PNG_KEYWORD.to_string(), snapshot)
+        .map_err(io::Error::other)?;
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(&pixels).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        let mut lattice = Lattice::with_seed(2, 1.0, 1.0, 42);
+        lattice.value[0].value = vec![1, -1];
+        lattice.value[1].value = vec![-1, -1];
+
+        assert_eq!(to_text(&lattice), "+-\n--\n");
+    }
+
+    #[test]
+    fn test_save_png_dimensions() {
+        let lattice = Lattice::with_seed(3, 1.0, 1.0, 42);
+        let path = std::env::temp_dir().join("r-ising-model-test-export.png");
+
+        save_png(&lattice, &path, 4).unwrap();
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        let (width, height, color_type) = (info.width, info.height, info.color_type);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((width, height), (12, 12));
+        assert_eq!(color_type, png::ColorType::Rgb);
+    }
+
+    #[test]
+    fn test_to_png_dimensions() {
+        let lattice = Lattice::with_seed(5, 1.0, 1.0, 42);
+
+        let bytes = to_png(&lattice, 2).unwrap();
+        let reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+
+        assert_eq!((reader.info().width, reader.info().height), (10, 10));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{Lattice, Spins};
+use rand::Rng;
+
+/// Starting configuration of the lattice spins
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum InitialState {
+    /// every spin up or down with the same probability
+    #[default]
+    Random,
+    /// every spin up
+    AllUp,
+    /// neighbouring spins alternate between up and down
+    Checkerboard,
+    /// every spin up with the given probability
+    Biased(f64),
+}
+
+impl InitialState {
+    /// Presets offered when resetting the lattice
+    pub const PRESETS: [InitialState; 4] = [
+        InitialState::Random,
+        InitialState::AllUp,
+        InitialState::Checkerboard,
+        InitialState::Biased(0.75),
+    ];
+
+    /// Human readable name of the initial state
+    pub fn name(self) -> String {
+        match self {
+            InitialState::Random => "Random".to_string(),
+            InitialState::AllUp => "All up".to_string(),
+            InitialState::Checkerboard => "Checkerboard".to_string(),
+            InitialState::Biased(up) => format!("Biased ({:.0}% up)", up * 100.0),
+        }
+    }
+}
+
+impl Lattice {
+    /// Replace every spin with the initial state
+    /// Random and biased states draw from the lattice random number generator
+    pub fn reset_spins(&mut self, state: InitialState) {
+        let size = self.size;
+        self.value = match state {
+            InitialState::Random => (0..size).map(|_| Spins::new(size, &mut self.rng)).collect(),
+            InitialState::AllUp => vec![
+                Spins {
+                    value: vec![1; size]
+                };
+                size
+            ],
+            InitialState::Checkerboard => (0..size)
+                .map(|y| Spins {
+                    value: (0..size)
+                        .map(|x| if (x + y) % 2 == 0 { 1 } else { -1 })
+                        .collect(),
+                })
+                .collect(),
+            InitialState::Biased(up) => {
+                let up = up.clamp(0.0, 1.0);
+                (0..size)
+                    .map(|_| Spins {
+                        value: (0..size)
+                            .map(|_| if self.rng.0.random_bool(up) { 1 } else { -1 })
+                            .collect(),
+                    })
+                    .collect()
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ordered_states() {
+        let mut lattice = Lattice::with_seed(4, 1.0, 1.0, 42);
+
+        lattice.reset_spins(InitialState::AllUp);
+        assert_eq!(lattice.magnetization(), 1.0);
+
+        lattice.reset_spins(InitialState::Checkerboard);
+        assert_eq!(lattice.magnetization(), 0.0);
+        assert_eq!(lattice.value[0].value, vec![1, -1, 1, -1]);
+        assert_eq!(lattice.value[1].value, vec![-1, 1, -1, 1]);
+    }
+
+    #[test]
+    fn test_fully_biased_state() {
+        let mut lattice = Lattice::with_seed(4, 1.0, 1.0, 42);
+
+        lattice.reset_spins(InitialState::Biased(1.0));
+        assert_eq!(lattice.magnetization(), 1.0);
+
+        lattice.reset_spins(InitialState::Biased(0.0));
+        assert_eq!(lattice.magnetization(), -1.0);
+    }
+}
@@ -1,5 +1,24 @@
+use algorithm::Algorithm;
+use boundary::Boundary;
 use core::f64;
-const KB: f64 = 1.380649e-23; // Boltzmann Constant in J K^-1
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+pub mod algorithm;
+pub mod animation;
+pub mod boundary;
+pub mod cluster;
+pub mod correlation;
+pub mod energy;
+pub mod export;
+pub mod initial_state;
+pub mod recorder;
+pub mod scan;
+pub mod session;
+pub mod snapshot;
+pub mod structure_factor;
+
+/// Boltzmann Constant in J K^-1
+pub const KB: f64 = 1.380649e-23;
 
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Spins {
@@ -8,17 +27,33 @@ pub struct Spins {
 
 impl Spins {
     // Create a new random spin vector with value of -1 or 1
-    fn new(size: usize) -> Self {
+    fn new(size: usize, rng: &mut LatticeRng) -> Self {
         Self {
             value: (0..size)
                 // Generate random spins
-                .map(|_| rand::random_bool(0.5))
+                .map(|_| rng.0.random_bool(0.5))
                 .map(|up| if !up { -1 } else { 1 })
                 .collect(),
         }
     }
 }
 
+/// Random number generator of a Lattice, seeded from the lattice seed
+#[derive(Clone, Debug)]
+struct LatticeRng(StdRng);
+
+impl LatticeRng {
+    fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for LatticeRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Lattice {
     /// the 2d lattice
@@ -29,14 +64,34 @@ pub struct Lattice {
     pub interactivity: f64,
     /// sim temperature
     pub temperature: f64,
+    /// external magnetic field h
+    #[serde(default)]
+    pub field: f64,
+    /// seed of the random number generator, random for lattices saved before it existed
+    #[serde(default = "rand::random")]
+    pub seed: u64,
+    /// treatment of neighbours outside of the lattice edges
+    #[serde(default)]
+    pub boundary: Boundary,
+    /// random number generator used for spins and point picking
+    #[serde(skip)]
+    rng: LatticeRng,
 }
 
 impl Lattice {
     /// Create a new Lattice with provided size, interactivity, and temperature
+    /// The lattice is seeded randomly
     pub fn new(size: usize, interactivity: f64, temperature: f64) -> Self {
+        Lattice::with_seed(size, interactivity, temperature, rand::random())
+    }
+
+    /// Create a new Lattice with provided size, interactivity, temperature, and seed
+    /// The same seed always produce the same simulation
+    pub fn with_seed(size: usize, interactivity: f64, temperature: f64, seed: u64) -> Self {
+        let mut rng = LatticeRng::new(seed);
         let mut value: Vec<Spins> = Vec::new();
         for _ in 0..size {
-            let spins = Spins::new(size);
+            let spins = Spins::new(size, &mut rng);
             value.push(spins)
         }
         Self {
@@ -44,9 +99,18 @@ impl Lattice {
             size,
             interactivity,
             temperature,
+            field: 0.0,
+            seed,
+            boundary: Boundary::default(),
+            rng,
         }
     }
 
+    /// Restart the random number generator from the lattice seed
+    pub(crate) fn reseed_rng(&mut self) {
+        self.rng = LatticeRng::new(self.seed);
+    }
+
     /// Update Lattice when a new size configured
     pub fn update_lattice(&mut self) -> Self {
         // if diff == 0 return early
@@ -58,12 +122,12 @@ impl Lattice {
             let diff = self.size - self.value.len();
             // Add new values to existing spins vector
             for spins in &mut self.value {
-                let mut new_spins = Spins::new(diff);
+                let mut new_spins = Spins::new(diff, &mut self.rng);
                 spins.value.append(&mut new_spins.value);
             }
             // Add new spins vector to lattice value
             for _spins_id in 0..diff {
-                let new_spins_vector = Spins::new(self.size);
+                let new_spins_vector = Spins::new(self.size, &mut self.rng);
                 self.value.push(new_spins_vector);
             }
         // else if diff < 0
@@ -85,7 +149,10 @@ impl Lattice {
     }
 
     pub fn reset_value(&self) -> Self {
-        Lattice::new(self.size, self.interactivity, self.temperature)
+        let mut lattice = Lattice::new(self.size, self.interactivity, self.temperature);
+        lattice.field = self.field;
+        lattice.boundary = self.boundary;
+        lattice
     }
 
     /// Set Lattice Size
@@ -99,48 +166,74 @@ impl Lattice {
     }
 
     /// pick randomg x and y point to be sampled
-    pub fn pick_random_point(&self) -> (usize, usize) {
+    pub fn pick_random_point(&mut self) -> (usize, usize) {
         (
-            rand::random_range(0..self.size),
-            rand::random_range(0..self.size),
+            self.rng.0.random_range(0..self.size),
+            self.rng.0.random_range(0..self.size),
         )
     }
 
+    /// Flip the spin at x and y
+    pub fn flip(&mut self, x: usize, y: usize) {
+        self.value[y].value[x] = -self.value[y].value[x];
+    }
+
+    /// Set the spin at x and y to -1 or 1
+    pub fn set_spin(&mut self, x: usize, y: usize, spin: i32) {
+        self.value[y].value[x] = spin;
+    }
+
     /// Hamiltonian Formula
-    /// H = -J * sum_over_nearest_neighbors(spin_i, spin_j)
-    /// H = -J * current_spin * sum_of_all_neighbors
+    /// H = -J * sum_over_nearest_neighbors(spin_i, spin_j) - h * spin_i
+    /// H = -current_spin * (J * sum_of_all_neighbors + h)
     pub fn calculate_hamiltonian(&self, x_rand: usize, y_rand: usize) -> f64 {
         let current_spin = f64::from(self.value[y_rand].value[x_rand]);
         let (left, right, down, up) = self.find_neighbours(x_rand, y_rand);
 
-        -self.interactivity * current_spin * f64::from(left + right + down + up)
+        -current_spin * (self.interactivity * f64::from(left + right + down + up) + self.field)
     }
 
     /// Gather nearest neighbours
+    /// Neighbours outside of the lattice follow the boundary condition
     pub fn find_neighbours(&self, x_rand: usize, y_rand: usize) -> (i32, i32, i32, i32) {
         let current_spin = self.value[y_rand].value[x_rand];
-        let is_not_most_left = x_rand != 0;
-        let is_not_most_right = x_rand != self.size - 1;
-        let is_not_bottom = y_rand != 0;
-        let is_not_top = y_rand != self.size - 1;
-
-        let (mut left, mut right, mut down, mut up) =
-            (current_spin, current_spin, current_spin, current_spin);
-
-        if is_not_most_left {
-            left = self.value[y_rand].value[x_rand - 1]
-        };
-        if is_not_most_right {
-            right = self.value[y_rand].value[x_rand + 1]
-        };
-        if is_not_bottom {
-            down = self.value[y_rand - 1].value[x_rand]
+        let outside = match self.boundary {
+            Boundary::Mirror => current_spin,
+            Boundary::Open => 0,
+            Boundary::Fixed => 1,
+            // Periodic neighbours are always inside the lattice
+            Boundary::Periodic => 0,
         };
-        if is_not_top {
-            up = self.value[y_rand + 1].value[x_rand]
+        let neighbour = |dx: isize, dy: isize| {
+            self.neighbour_point(x_rand, y_rand, dx, dy)
+                .map_or(outside, |(x, y)| self.value[y].value[x])
         };
 
-        (left, right, down, up)
+        (
+            neighbour(-1, 0),
+            neighbour(1, 0),
+            neighbour(0, -1),
+            neighbour(0, 1),
+        )
+    }
+
+    /// Point next to x and y moved by dx and dy
+    /// None when it falls outside of the lattice, unless the boundary is periodic
+    pub fn neighbour_point(
+        &self,
+        x: usize,
+        y: usize,
+        dx: isize,
+        dy: isize,
+    ) -> Option<(usize, usize)> {
+        if self.boundary == Boundary::Periodic {
+            let size = self.size as isize;
+            let wrap = |value: usize, delta: isize| (value as isize + delta).rem_euclid(size);
+            return Some((wrap(x, dx) as usize, wrap(y, dy) as usize));
+        }
+        let x = x.checked_add_signed(dx).filter(|x| *x < self.size)?;
+        let y = y.checked_add_signed(dy).filter(|y| *y < self.size)?;
+        Some((x, y))
     }
 
     /// Metropolis Algorith Calculation
@@ -149,7 +242,8 @@ impl Lattice {
     /// If Acceptence Criteria > 0.5; take the new flip. It's mean the atom try to escape
     /// a local minima.
     /// Else keep the old spin
-    pub fn metropolis_algo_calculation(&mut self, x_rand: usize, y_rand: usize) {
+    /// Return whether the spin was flipped
+    pub fn metropolis_algo_calculation(&mut self, x_rand: usize, y_rand: usize) -> bool {
         let delta_h = self.calculate_delta_h(x_rand, y_rand);
         let acceptence_criteria = self.calculate_acceptence_criteria(delta_h);
 
@@ -159,6 +253,75 @@ impl Lattice {
         if is_flipped {
             self.value[y_rand].value[x_rand] = -self.value[y_rand].value[x_rand];
         }
+        is_flipped
+    }
+
+    /// Run one Monte Carlo sweep, size * size Metropolis steps on random points
+    /// Return the number of flipped spins
+    pub fn sweep(&mut self) -> usize {
+        self.sweep_with(Algorithm::Metropolis, |_, _| {})
+    }
+
+    /// Run one Monte Carlo sweep with an algorithm,
+    /// calling on_flip with x and y of every flipped spin
+    /// Return the number of flipped spins
+    pub fn sweep_with(
+        &mut self,
+        algorithm: Algorithm,
+        mut on_flip: impl FnMut(usize, usize),
+    ) -> usize {
+        match algorithm {
+            Algorithm::Metropolis => self.metropolis_sweep(&mut on_flip),
+            Algorithm::HeatBath => self.heat_bath_sweep(&mut on_flip),
+            Algorithm::Wolff => self.wolff_sweep(&mut on_flip),
+        }
+    }
+
+    /// Metropolis sweep, size * size Metropolis steps on random points
+    fn metropolis_sweep(&mut self, on_flip: &mut impl FnMut(usize, usize)) -> usize {
+        let mut flipped = 0;
+        for _ in 0..self.size * self.size {
+            let (x_rand, y_rand) = self.pick_random_point();
+            if self.metropolis_algo_calculation(x_rand, y_rand) {
+                on_flip(x_rand, y_rand);
+                flipped += 1;
+            }
+        }
+        flipped
+    }
+
+    /// Magnetization per spin
+    /// M = sum_of_all_spins / N, ranging from -1 to 1
+    pub fn magnetization(&self) -> f64 {
+        let total: i32 = self
+            .value
+            .iter()
+            .map(|spins| spins.value.iter().sum::<i32>())
+            .sum();
+        f64::from(total) / (self.size * self.size) as f64
+    }
+
+    /// Energy per spin
+    /// E = sum_over_all_spins(H) / 2N - h * M / 2, halved since every bond is counted twice
+    /// while the field term is counted once
+    pub fn energy_per_spin(&self) -> f64 {
+        let mut total = 0.0;
+        for y in 0..self.size {
+            for x in 0..self.size {
+                total += self.calculate_hamiltonian(x, y);
+            }
+        }
+        total / (2 * self.size * self.size) as f64 - self.field * self.magnetization() / 2.0
+    }
+
+    /// Reduced temperature T* = k_B * T / |J|, infinite without interaction
+    pub fn reduced_temperature(&self) -> f64 {
+        KB * self.temperature / self.interactivity.abs()
+    }
+
+    /// Set the temperature in Kelvin from a reduced temperature T* = k_B * T / |J|
+    pub fn set_reduced_temperature(&mut self, reduced_temperature: f64) {
+        self.temperature = reduced_temperature * self.interactivity.abs() / KB;
     }
 
     /// Calculate Hamiltonian energy difference of a point
@@ -0,0 +1,411 @@
+use crate::{Lattice, KB};
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+/// Lattice observables taken after a sweep
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Sample {
+    /// sweep number when the sample was taken
+    pub sweep: u64,
+    /// magnetization per spin
+    pub magnetization: f64,
+    /// energy per spin
+    pub energy: f64,
+    /// ratio of flipped spins to attempted flips in the sweep, from 0 to 1
+    /// A Wolff sweep flips clusters until size * size spins flipped, so it counts as 1
+    /// unless an external field rejected clusters
+    pub acceptance: f64,
+}
+
+/// Record lattice observables per sweep
+/// Only the latest `capacity` samples are kept
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Recorder {
+    /// maximum number of kept samples
+    pub capacity: usize,
+    /// recorded samples, oldest first
+    pub samples: VecDeque<Sample>,
+    /// number of recorded sweeps
+    pub sweeps: u64,
+    /// number of attempted flips
+    pub attempted: u64,
+    /// number of accepted flips, at most the attempted ones of every sweep
+    pub accepted: u64,
+}
+
+impl Recorder {
+    /// Create a new empty Recorder keeping up to capacity samples
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+            sweeps: 0,
+            attempted: 0,
+            accepted: 0,
+        }
+    }
+
+    /// Take a sample of the lattice after a finished sweep
+    /// flipped is the number of spins flipped during the sweep, counted up to size * size
+    /// since cluster updates may flip spins more than once
+    pub fn record(&mut self, lattice: &Lattice, flipped: usize) {
+        let attempted = lattice.size * lattice.size;
+        let flipped = flipped.min(attempted);
+        self.sweeps += 1;
+        self.attempted += attempted as u64;
+        self.accepted += flipped as u64;
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            sweep: self.sweeps,
+            magnetization: lattice.magnetization(),
+            energy: lattice.energy_per_spin(),
+            acceptance: flipped as f64 / attempted as f64,
+        });
+    }
+
+    /// Latest recorded sample
+    pub fn last(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    /// Ratio of accepted to attempted flips since the recording started
+    pub fn acceptance_ratio(&self) -> f64 {
+        if self.attempted == 0 {
+            return 0.0;
+        }
+        self.accepted as f64 / self.attempted as f64
+    }
+
+    /// Mean acceptance ratio of the latest window samples
+    pub fn rolling_acceptance(&self, window: usize) -> f64 {
+        let skip = self.samples.len().saturating_sub(window);
+        let count = self.samples.len() - skip;
+        if count == 0 {
+            return 0.0;
+        }
+        let total: f64 = self
+            .samples
+            .iter()
+            .skip(skip)
+            .map(|sample| sample.acceptance)
+            .sum();
+        total / count as f64
+    }
+
+    /// Magnetic susceptibility over the latest window samples
+    /// chi = N * (<m^2> - <m>^2) / (k_B * T), with m the magnetization per spin
+    pub fn susceptibility(&self, lattice: &Lattice, window: usize) -> f64 {
+        let skip = self.samples.len().saturating_sub(window);
+        let count = self.samples.len() - skip;
+        if count == 0 || lattice.temperature == 0.0 {
+            return 0.0;
+        }
+        let (sum, sum_squares) =
+            self.samples
+                .iter()
+                .skip(skip)
+                .fold((0.0, 0.0), |(sum, sum_squares), sample| {
+                    let m = sample.magnetization;
+                    (sum + m, sum_squares + m * m)
+                });
+        let mean = sum / count as f64;
+        let variance = (sum_squares / count as f64 - mean * mean).max(0.0);
+        (lattice.size * lattice.size) as f64 * variance / (KB * lattice.temperature)
+    }
+
+    /// Heat capacity per spin over the latest window samples, in units of k_B
+    /// C = N * (<e^2> - <e>^2) / (k_B * T)^2, with e the energy per spin
+    pub fn heat_capacity(&self, lattice: &Lattice, window: usize) -> f64 {
+        let skip = self.samples.len().saturating_sub(window);
+        if skip == self.samples.len() || lattice.temperature == 0.0 {
+            return 0.0;
+        }
+        let energies: Vec<f64> = self
+            .samples
+            .iter()
+            .skip(skip)
+            .map(|sample| sample.energy)
+            .collect();
+        let (_, variance) = mean_and_variance(&energies);
+        let thermal_energy = KB * lattice.temperature;
+        (lattice.size * lattice.size) as f64 * variance / (thermal_energy * thermal_energy)
+    }
+
+    /// Whether the energy per spin stopped drifting over the latest 2 * window samples
+    /// The means of the two latest windows must agree within two standard errors
+    /// and their variances within a factor of two
+    pub fn is_equilibrated(&self, window: usize) -> bool {
+        if window == 0 || self.samples.len() < 2 * window {
+            return false;
+        }
+        let skip = self.samples.len() - 2 * window;
+        let energies: Vec<f64> = self
+            .samples
+            .iter()
+            .skip(skip)
+            .map(|sample| sample.energy)
+            .collect();
+        let (older, newer) = energies.split_at(window);
+        let (older_mean, older_variance) = mean_and_variance(older);
+        let (newer_mean, newer_variance) = mean_and_variance(newer);
+
+        let standard_error = ((older_variance + newer_variance) / window as f64).sqrt();
+        let means_agree = (newer_mean - older_mean).abs() <= 2.0 * standard_error + f64::EPSILON;
+        let (low, high) = if older_variance < newer_variance {
+            (older_variance, newer_variance)
+        } else {
+            (newer_variance, older_variance)
+        };
+        let variances_agree = high <= 2.0 * low + f64::EPSILON;
+        means_agree && variances_agree
+    }
+
+    /// Histogram of an observable over the latest window samples
+    /// Return the center and count of bins evenly spread between the lowest and highest value
+    pub fn histogram(
+        &self,
+        window: usize,
+        bins: usize,
+        observable: impl Fn(&Sample) -> f64,
+    ) -> Vec<(f64, usize)> {
+        let skip = self.samples.len().saturating_sub(window);
+        let values: Vec<f64> = self.samples.iter().skip(skip).map(observable).collect();
+        if values.is_empty() || bins == 0 {
+            return Vec::new();
+        }
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if max == min {
+            return vec![(min, values.len())];
+        }
+        let width = (max - min) / bins as f64;
+        let mut counts = vec![0; bins];
+        for value in values {
+            // The highest value belongs to the last bin
+            let bin = (((value - min) / width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(bin, count)| (min + (bin as f64 + 0.5) * width, count))
+            .collect()
+    }
+
+    /// Write the kept samples as CSV, one row per sweep, oldest first
+    pub fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "sweep,energy,magnetization,acceptance")?;
+        for sample in &self.samples {
+            writeln!(
+                out,
+                "{},{},{},{}",
+                sample.sweep, sample.energy, sample.magnetization, sample.acceptance
+            )?;
+        }
+        out.flush()
+    }
+
+    /// Drop the samples taken after sweep, e.g. when the lattice goes back to a checkpoint
+    /// The flip counters keep counting the undone sweeps
+    pub fn rewind(&mut self, sweep: u64) {
+        while self.last().is_some_and(|sample| sample.sweep > sweep) {
+            self.samples.pop_back();
+        }
+        self.sweeps = self.sweeps.min(sweep);
+    }
+
+    /// Drop all samples and restart the counters
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.sweeps = 0;
+        self.attempted = 0;
+        self.accepted = 0;
+    }
+}
+
+fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / count;
+    (mean, variance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algorithm::Algorithm;
+
+    #[test]
+    fn test_record_keeps_capacity() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(3);
+
+        for _ in 0..5 {
+            recorder.record(&lattice, 0);
+        }
+
+        assert_eq!(recorder.samples.len(), 3);
+        assert_eq!(recorder.sweeps, 5);
+        assert_eq!(recorder.samples.front().unwrap().sweep, 3);
+        assert_eq!(recorder.last().unwrap().sweep, 5);
+    }
+
+    #[test]
+    fn test_rewind_drops_later_samples() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(10);
+        for _ in 0..5 {
+            recorder.record(&lattice, 0);
+        }
+
+        recorder.rewind(3);
+        recorder.record(&lattice, 0);
+
+        assert_eq!(recorder.samples.len(), 4);
+        assert_eq!(recorder.sweeps, 4);
+        assert_eq!(recorder.last().unwrap().sweep, 4);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut lattice = Lattice::new(5, 1.0, 1.0);
+        for spins in &mut lattice.value {
+            spins.value.fill(1);
+        }
+        let mut recorder = Recorder::new(3);
+        recorder.record(&lattice, 5);
+        let mut out = Vec::new();
+
+        recorder.write_csv(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "sweep,energy,magnetization,acceptance\n1,-2,1,0.2\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram_bins_window() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(10);
+        for flipped in [25, 0, 0, 5, 10, 25] {
+            recorder.record(&lattice, flipped);
+        }
+
+        let histogram = recorder.histogram(5, 2, |sample| sample.acceptance);
+
+        assert_eq!(histogram, vec![(0.25, 4), (0.75, 1)]);
+        assert_eq!(
+            recorder.histogram(2, 4, |sample| sample.sweep as f64).len(),
+            4
+        );
+        assert_eq!(recorder.histogram(5, 3, |_| 1.0), vec![(1.0, 5)]);
+    }
+
+    #[test]
+    fn test_acceptance_ratio() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(3);
+
+        recorder.record(&lattice, 25);
+        recorder.record(&lattice, 0);
+
+        assert_eq!(recorder.last().unwrap().acceptance, 0.0);
+        assert_eq!(recorder.acceptance_ratio(), 0.5);
+    }
+
+    #[test]
+    fn test_cluster_sweeps_accept_at_most_every_flip() {
+        let mut lattice = Lattice::with_seed(8, 1.0, 2.0, 7);
+        let mut recorder = Recorder::new(10);
+
+        for _ in 0..10 {
+            let flipped = lattice.sweep_with(Algorithm::Wolff, |_, _| {});
+            recorder.record(&lattice, flipped);
+        }
+
+        assert!(recorder
+            .samples
+            .iter()
+            .all(|sample| (0.0..=1.0).contains(&sample.acceptance)));
+        assert!(recorder.acceptance_ratio() <= 1.0);
+        assert!(recorder.rolling_acceptance(10) <= 1.0);
+    }
+
+    #[test]
+    fn test_rolling_acceptance() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(5);
+
+        assert_eq!(recorder.rolling_acceptance(2), 0.0);
+        for flipped in [25, 25, 25, 0] {
+            recorder.record(&lattice, flipped);
+        }
+
+        assert_eq!(recorder.rolling_acceptance(2), 0.5);
+        assert_eq!(recorder.rolling_acceptance(10), 0.75);
+    }
+
+    #[test]
+    fn test_susceptibility_of_constant_magnetization() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(3);
+
+        assert_eq!(recorder.susceptibility(&lattice, 10), 0.0);
+        recorder.record(&lattice, 0);
+        recorder.record(&lattice, 0);
+
+        assert_eq!(recorder.susceptibility(&lattice, 10), 0.0);
+    }
+
+    #[test]
+    fn test_heat_capacity_of_energy_fluctuations() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(3);
+
+        assert_eq!(recorder.heat_capacity(&lattice, 10), 0.0);
+        for energy in [5.0 * KB, -KB, KB] {
+            recorder.samples.push_back(Sample {
+                energy,
+                ..Default::default()
+            });
+        }
+
+        // Only the two latest energies, -k_B and k_B, are in the window
+        assert!((recorder.heat_capacity(&lattice, 2) - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_equilibrated_once_energy_settles() {
+        let mut lattice = Lattice::with_seed(6, 1.0, 1.0, 42);
+        let mut recorder = Recorder::new(10);
+
+        recorder.record(&lattice, 0);
+        for spins in &mut lattice.value {
+            spins.value.fill(1);
+        }
+        for _ in 0..7 {
+            recorder.record(&lattice, 0);
+        }
+        assert!(!recorder.is_equilibrated(4));
+
+        recorder.record(&lattice, 0);
+        assert!(recorder.is_equilibrated(4));
+        assert!(!recorder.is_equilibrated(5));
+    }
+
+    #[test]
+    fn test_magnetization_range() {
+        let lattice = Lattice::new(5, 1.0, 1.0);
+        let mut recorder = Recorder::new(3);
+
+        recorder.record(&lattice, 0);
+
+        let magnetization = recorder.last().unwrap().magnetization;
+        assert!((-1.0..=1.0).contains(&magnetization));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{algorithm::Algorithm, Lattice};
+use std::io::{self, Write};
+
+/// Observables averaged at one temperature of a scan, in units of J
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScanPoint {
+    /// reduced temperature k_B T / |J|
+    pub reduced_temperature: f64,
+    /// mean absolute magnetization per spin <|m|>
+    pub magnetization: f64,
+    /// magnetic susceptibility per spin chi = N * (<m^2> - <|m|>^2) / T*
+    pub susceptibility: f64,
+    /// heat capacity per spin C = N * (<e^2> - <e>^2) / T*^2, with e in units of |J|
+    pub heat_capacity: f64,
+}
+
+/// Evenly spaced values from start to end, both included
+pub fn temperatures(start: f64, end: f64, steps: usize) -> Vec<f64> {
+    match steps {
+        0 => Vec::new(),
+        1 => vec![start],
+        _ => (0..steps)
+            .map(|step| start + (end - start) * step as f64 / (steps - 1) as f64)
+            .collect(),
+    }
+}
+
+/// Run equilibration sweeps, then average the observables over sweeps more sweeps
+/// The lattice needs a non zero interactivity to work in units of J
+pub fn measure(
+    lattice: &mut Lattice,
+    algorithm: Algorithm,
+    equilibration: usize,
+    sweeps: usize,
+) -> ScanPoint {
+    for _ in 0..equilibration {
+        lattice.sweep_with(algorithm, |_, _| {});
+    }
+
+    let interactivity = lattice.interactivity.abs();
+    let (mut sum_m, mut sum_m2, mut sum_e, mut sum_e2) = (0.0, 0.0, 0.0, 0.0);
+    for _ in 0..sweeps {
+        lattice.sweep_with(algorithm, |_, _| {});
+        let m = lattice.magnetization().abs();
+        let e = lattice.energy_per_spin() / interactivity;
+        sum_m += m;
+        sum_m2 += m * m;
+        sum_e += e;
+        sum_e2 += e * e;
+    }
+
+    let reduced_temperature = lattice.reduced_temperature();
+    let spins = (lattice.size * lattice.size) as f64;
+    let count = sweeps.max(1) as f64;
+    let (mean_m, mean_e) = (sum_m / count, sum_e / count);
+    let variance_m = (sum_m2 / count - mean_m * mean_m).max(0.0);
+    let variance_e = (sum_e2 / count - mean_e * mean_e).max(0.0);
+    let (susceptibility, heat_capacity) = if reduced_temperature > 0.0 {
+        (
+            spins * variance_m / reduced_temperature,
+            spins * variance_e / (reduced_temperature * reduced_temperature),
+        )
+    } else {
+        (0.0, 0.0)
+    };
+    ScanPoint {
+        reduced_temperature,
+        magnetization: mean_m,
+        susceptibility,
+        heat_capacity,
+    }
+}
+
+/// Write scan points as CSV, one row per temperature
+pub fn write_csv(points: &[ScanPoint], out: &mut impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "temperature,magnetization,susceptibility,heat_capacity"
+    )?;
+    for point in points {
+        writeln!(
+            out,
+            "{},{},{},{}",
+            point.reduced_temperature,
+            point.magnetization,
+            point.susceptibility,
+            point.heat_capacity
+        )?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_temperatures_include_both_ends() {
+        assert_eq!(temperatures(1.0, 3.0, 5), vec![1.0, 1.5, 2.0, 2.5, 3.0]);
+        assert_eq!(temperatures(1.0, 3.0, 1), vec![1.0]);
+        assert!(temperatures(1.0, 3.0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_cold_aligned_lattice_does_not_fluctuate() {
+        let mut lattice = Lattice::with_seed(6, 1.0, 0.0, 42);
+        lattice.set_reduced_temperature(0.1);
+        for spins in &mut lattice.value {
+            spins.value.fill(1);
+        }
+
+        let point = measure(&mut lattice, Algorithm::HeatBath, 5, 20);
+
+        assert_eq!(point.magnetization, 1.0);
+        assert_eq!(point.susceptibility, 0.0);
+        assert_eq!(point.heat_capacity, 0.0);
+        assert!((point.reduced_temperature - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let points = [ScanPoint {
+            reduced_temperature: 2.0,
+            magnetization: 0.5,
+            susceptibility: 1.5,
+            heat_capacity: 0.25,
+        }];
+        let mut out = Vec::new();
+
+        write_csv(&points, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "temperature,magnetization,susceptibility,heat_capacity\n2,0.5,1.5,0.25\n"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{boundary::Boundary, snapshot::Snapshot, Lattice};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// Bytes starting every session file
+pub const SESSION_MAGIC: [u8; 4] = *b"ISES";
+
+/// Version of the session format, bumped on incompatible changes
+pub const SESSION_VERSION: u32 = 1;
+
+const TAG_SWEEP: u8 = 0;
+const TAG_INTERACTIVITY: u8 = 1;
+const TAG_TEMPERATURE: u8 = 2;
+const TAG_SET_SPIN: u8 = 3;
+const TAG_LATTICE: u8 = 4;
+const TAG_BOUNDARY: u8 = 5;
+
+/// Change of the simulation captured in a session
+#[derive(Clone, Debug)]
+pub enum SessionEvent {
+    /// a finished sweep with the x and y of every accepted flip, in order
+    Sweep(Vec<(usize, usize)>),
+    /// interactivity set to a new value
+    Interactivity(f64),
+    /// temperature set to a new value
+    Temperature(f64),
+    /// spin at x and y set by hand
+    SetSpin { x: usize, y: usize, spin: i32 },
+    /// whole lattice replaced, e.g. at the start or when a snapshot is loaded
+    Lattice(Box<Lattice>),
+    /// boundary condition switched
+    Boundary(Boundary),
+}
+
+impl SessionEvent {
+    /// Apply the event to a lattice
+    /// Fail when a point is outside of the lattice
+    pub fn apply(&self, lattice: &mut Lattice) -> io::Result<()> {
+        let size = lattice.size;
+        let check = |x: usize, y: usize| {
+            if x < size && y < size {
+                Ok(())
+            } else {
+                Err(invalid_data(format!(
+                    "Session point ({x}, {y}) is outside of the lattice"
+                )))
+            }
+        };
+        match self {
+            SessionEvent::Sweep(flips) => {
+                for &(x, y) in flips {
+                    check(x, y)?;
+                    lattice.flip(x, y);
+                }
+            }
+            SessionEvent::Interactivity(interactivity) => lattice.interactivity = *interactivity,
+            SessionEvent::Temperature(temperature) => lattice.temperature = *temperature,
+            SessionEvent::SetSpin { x, y, spin } => {
+                check(*x, *y)?;
+                lattice.set_spin(*x, *y, *spin);
+            }
+            SessionEvent::Lattice(value) => *lattice = (**value).clone(),
+            SessionEvent::Boundary(boundary) => lattice.boundary = *boundary,
+        }
+        Ok(())
+    }
+
+    /// Encode the event as a tag byte followed by its little-endian payload
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            SessionEvent::Sweep(flips) => {
+                writer.write_all(&[TAG_SWEEP])?;
+                write_u32(writer, flips.len())?;
+                for &(x, y) in flips {
+                    write_u32(writer, x)?;
+                    write_u32(writer, y)?;
+                }
+            }
+            SessionEvent::Interactivity(interactivity) => {
+                writer.write_all(&[TAG_INTERACTIVITY])?;
+                writer.write_all(&interactivity.to_le_bytes())?;
+            }
+            SessionEvent::Temperature(temperature) => {
+                writer.write_all(&[TAG_TEMPERATURE])?;
+                writer.write_all(&temperature.to_le_bytes())?;
+            }
+            SessionEvent::SetSpin { x, y, spin } => {
+                writer.write_all(&[TAG_SET_SPIN])?;
+                write_u32(writer, *x)?;
+                write_u32(writer, *y)?;
+                writer.write_all(&(*spin as i8).to_le_bytes())?;
+            }
+            SessionEvent::Lattice(lattice) => {
+                let bytes = Snapshot::new(lattice).to_bytes()?;
+                writer.write_all(&[TAG_LATTICE])?;
+                write_u32(writer, bytes.len())?;
+                writer.write_all(&bytes)?;
+            }
+            SessionEvent::Boundary(boundary) => {
+                // Index of the boundary in Boundary::ALL
+                let index = Boundary::ALL
+                    .iter()
+                    .position(|b| b == boundary)
+                    .unwrap_or(0);
+                writer.write_all(&[TAG_BOUNDARY, index as u8])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode the next event, None at the end of the session
+    fn read_from(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut tag = [0; 1];
+        match reader.read_exact(&mut tag) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let event = match tag[0] {
+            TAG_SWEEP => {
+                let count = read_u32(reader)?;
+                let mut flips = Vec::with_capacity(count.min(1 << 16));
+                for _ in 0..count {
+                    flips.push((read_u32(reader)?, read_u32(reader)?));
+                }
+                SessionEvent::Sweep(flips)
+            }
+            TAG_INTERACTIVITY => SessionEvent::Interactivity(read_f64(reader)?),
+            TAG_TEMPERATURE => SessionEvent::Temperature(read_f64(reader)?),
+            TAG_SET_SPIN => {
+                let x = read_u32(reader)?;
+                let y = read_u32(reader)?;
+                let mut spin = [0; 1];
+                reader.read_exact(&mut spin)?;
+                SessionEvent::SetSpin {
+                    x,
+                    y,
+                    spin: i32::from(i8::from_le_bytes(spin)),
+                }
+            }
+            TAG_LATTICE => {
+                let mut bytes = vec![0; read_u32(reader)?];
+                reader.read_exact(&mut bytes)?;
+                SessionEvent::Lattice(Box::new(Snapshot::from_bytes(&bytes)?.into_lattice()))
+            }
+            TAG_BOUNDARY => {
+                let mut index = [0; 1];
+                reader.read_exact(&mut index)?;
+                let boundary = Boundary::ALL.get(usize::from(index[0])).ok_or_else(|| {
+                    invalid_data(format!("Unknown session boundary {}", index[0]))
+                })?;
+                SessionEvent::Boundary(*boundary)
+            }
+            tag => return Err(invalid_data(format!("Unknown session event tag {tag}"))),
+        };
+        Ok(Some(event))
+    }
+}
+
+/// Write session events into a stream after the session header
+#[derive(Debug)]
+pub struct SessionWriter<W: Write> {
+    writer: W,
+}
+
+impl SessionWriter<BufWriter<File>> {
+    /// Create a session file
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> SessionWriter<W> {
+    /// Create a new SessionWriter, writing the header into writer
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&SESSION_MAGIC)?;
+        writer.write_all(&SESSION_VERSION.to_le_bytes())?;
+        Ok(Self { writer })
+    }
+
+    /// Append an event to the session
+    pub fn write(&mut self, event: &SessionEvent) -> io::Result<()> {
+        event.write_to(&mut self.writer)
+    }
+
+    /// Flush buffered events into the stream
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Take the underlying stream out of the writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Read session events from a stream, oldest first
+#[derive(Debug)]
+pub struct SessionReader<R: Read> {
+    reader: R,
+}
+
+impl SessionReader<BufReader<File>> {
+    /// Open a session file
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> SessionReader<R> {
+    /// Create a new SessionReader, checking the header from reader
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != SESSION_MAGIC {
+            return Err(invalid_data("Not a session file".to_string()));
+        }
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SESSION_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported session version {version}"
+            )));
+        }
+        Ok(Self { reader })
+    }
+
+    /// Read the next event, None at the end of the session
+    pub fn next_event(&mut self) -> io::Result<Option<SessionEvent>> {
+        SessionEvent::read_from(&mut self.reader)
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_u32(writer: &mut impl Write, value: usize) -> io::Result<()> {
+    let value = u32::try_from(value)
+        .map_err(|_| invalid_data(format!("Value {value} does not fit in a session")))?;
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<usize> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes) as usize)
+}
+
+fn read_f64(reader: &mut impl Read) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algorithm::Algorithm;
+
+    #[test]
+    fn test_replay_reproduces_lattice() {
+        let mut lattice = Lattice::with_seed(6, 1.0, 2.0, 42);
+        let mut writer = SessionWriter::new(Vec::new()).unwrap();
+        writer
+            .write(&SessionEvent::Lattice(Box::new(lattice.clone())))
+            .unwrap();
+        for sweep in 0..5 {
+            if sweep == 2 {
+                lattice.temperature = 1.0e-30;
+                writer.write(&SessionEvent::Temperature(1.0e-30)).unwrap();
+                lattice.set_spin(1, 2, -1);
+                writer
+                    .write(&SessionEvent::SetSpin {
+                        x: 1,
+                        y: 2,
+                        spin: -1,
+                    })
+                    .unwrap();
+                lattice.boundary = Boundary::Periodic;
+                writer
+                    .write(&SessionEvent::Boundary(Boundary::Periodic))
+                    .unwrap();
+            }
+            let mut flips = Vec::new();
+            lattice.sweep_with(Algorithm::Metropolis, |x, y| flips.push((x, y)));
+            writer.write(&SessionEvent::Sweep(flips)).unwrap();
+        }
+        let bytes = writer.into_inner();
+
+        let mut reader = SessionReader::new(bytes.as_slice()).unwrap();
+        let mut result = Lattice::default();
+        while let Some(event) = reader.next_event().unwrap() {
+            event.apply(&mut result).unwrap();
+        }
+
+        assert_eq!(result.temperature, 1.0e-30);
+        assert_eq!(result.boundary, Boundary::Periodic);
+        for (spins, expected) in result.value.iter().zip(&lattice.value) {
+            assert_eq!(spins.value, expected.value);
+        }
+    }
+
+    #[test]
+    fn test_reject_unknown_header() {
+        assert!(SessionReader::new(&b"NOPE\x01\x00\x00\x00"[..]).is_err());
+    }
+
+    #[test]
+    fn test_reject_point_outside_lattice() {
+        let mut lattice = Lattice::with_seed(3, 1.0, 2.0, 42);
+
+        let result = SessionEvent::Sweep(vec![(3, 0)]).apply(&mut lattice);
+
+        assert!(result.is_err());
+    }
+}
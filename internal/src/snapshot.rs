@@ -0,0 +1,164 @@
+use crate::Lattice;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+};
+
+/// Version of the snapshot format, bumped on incompatible changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Default file extension of snapshot files
+pub const SNAPSHOT_EXTENSION: &str = "ising";
+
+/// Keyword of the PNG text chunk exported images embed their snapshot in
+pub const SNAPSHOT_PNG_KEYWORD: &str = "ising-snapshot";
+
+/// First bytes of every PNG image
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Serialized lattice spins and parameters
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    /// snapshot format version
+    pub version: u32,
+    /// the saved lattice
+    pub lattice: Lattice,
+}
+
+impl Snapshot {
+    /// Create a new Snapshot of a lattice
+    pub fn new(lattice: &Lattice) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            lattice: lattice.clone(),
+        }
+    }
+
+    /// Serialize the snapshot into JSON bytes
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Deserialize a snapshot from JSON bytes
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let snapshot: Snapshot = serde_json::from_slice(bytes)?;
+        snapshot.validate()
+    }
+
+    /// Read the snapshot embedded in an image written by export::write_png
+    pub fn from_png(bytes: &[u8]) -> io::Result<Self> {
+        let reader = png::Decoder::new(bytes)
+            .read_info()
+            .map_err(io::Error::other)?;
+        let text = reader
+            .info()
+            .compressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == SNAPSHOT_PNG_KEYWORD)
+            .map(|chunk| chunk.get_text())
+            .transpose()
+            .map_err(io::Error::other)?
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Image has no embedded snapshot")
+            })?;
+        Self::from_bytes(text.as_bytes())
+    }
+
+    /// Read a snapshot file, or the snapshot embedded in an exported PNG image
+    pub fn from_file_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.starts_with(&PNG_SIGNATURE) {
+            Self::from_png(bytes)
+        } else {
+            Self::from_bytes(bytes)
+        }
+    }
+
+    /// Write the snapshot into a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    /// Read a snapshot from a file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let snapshot: Snapshot = serde_json::from_reader(reader)?;
+        snapshot.validate()
+    }
+
+    /// Take the lattice out of the snapshot
+    /// The random number generator restarts from the saved seed
+    pub fn into_lattice(self) -> Lattice {
+        let mut lattice = self.lattice;
+        lattice.reseed_rng();
+        lattice
+    }
+
+    /// Check the version and the lattice shape
+    fn validate(self) -> io::Result<Self> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported snapshot version {}", self.version),
+            ));
+        }
+        let lattice = &self.lattice;
+        let is_square = lattice.value.len() == lattice.size
+            && lattice
+                .value
+                .iter()
+                .all(|spins| spins.value.len() == lattice.size);
+        if lattice.size == 0 || !is_square {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Snapshot lattice is not a square of its size",
+            ));
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let lattice = Lattice::with_seed(5, 1.0, 2.0, 42);
+
+        let bytes = Snapshot::new(&lattice).to_bytes().unwrap();
+        let result = Snapshot::from_bytes(&bytes).unwrap().into_lattice();
+
+        assert_eq!(result.value.len(), 5);
+        assert_eq!(result.seed, 42);
+        assert_eq!(result.temperature, 2.0);
+        for (spins, expected) in result.value.iter().zip(&lattice.value) {
+            assert_eq!(spins.value, expected.value);
+        }
+    }
+
+    #[test]
+    fn test_read_snapshot_embedded_in_png() {
+        let lattice = Lattice::with_seed(5, 1.0, 2.0, 42);
+
+        let bytes = crate::export::to_png(&lattice, 2).unwrap();
+        let result = Snapshot::from_file_bytes(&bytes).unwrap().into_lattice();
+
+        assert_eq!(result.seed, 42);
+        assert_eq!(result.temperature, 2.0);
+        for (spins, expected) in result.value.iter().zip(&lattice.value) {
+            assert_eq!(spins.value, expected.value);
+        }
+    }
+
+    #[test]
+    fn test_reject_malformed_lattice() {
+        let mut lattice = Lattice::with_seed(5, 1.0, 2.0, 42);
+        lattice.size = 6;
+
+        let bytes = Snapshot::new(&lattice).to_bytes().unwrap();
+
+        assert!(Snapshot::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::Lattice;
+use rustfft::{num_complex::Complex, FftPlanner};
+
+impl Lattice {
+    /// Structure factor S(k) = |sum_r s_r e^(-i k.r)|^2 / N over the wave vectors of the lattice
+    /// Rows are k_y and columns k_x, shifted so k = 0 sits at (size / 2, size / 2)
+    /// An ordered lattice gathers all its weight in the central peak S(0) = N
+    pub fn structure_factor(&self) -> Vec<Vec<f64>> {
+        let size = self.size;
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(size);
+        let mut rows: Vec<Vec<Complex<f64>>> = self
+            .value
+            .iter()
+            .map(|spins| {
+                spins
+                    .value
+                    .iter()
+                    .map(|spin| Complex::new(f64::from(*spin), 0.0))
+                    .collect()
+            })
+            .collect();
+        for row in &mut rows {
+            fft.process(row);
+        }
+        // Columns are transformed as the rows of the transposed rows
+        let mut columns: Vec<Vec<Complex<f64>>> = (0..size)
+            .map(|kx| rows.iter().map(|row| row[kx]).collect())
+            .collect();
+        for column in &mut columns {
+            fft.process(column);
+        }
+
+        let spins = (size * size) as f64;
+        let unshift = |k: usize| (k + size - size / 2) % size;
+        (0..size)
+            .map(|ky| {
+                (0..size)
+                    .map(|kx| columns[unshift(kx)][unshift(ky)].norm_sqr() / spins)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ordered_lattice_peaks_at_the_center() {
+        let mut lattice = Lattice::with_seed(4, 1.0, 1.0, 42);
+        for y in 0..4 {
+            for x in 0..4 {
+                lattice.set_spin(x, y, 1);
+            }
+        }
+
+        let structure_factor = lattice.structure_factor();
+
+        for (ky, row) in structure_factor.iter().enumerate() {
+            for (kx, value) in row.iter().enumerate() {
+                let expected = if (kx, ky) == (2, 2) { 16.0 } else { 0.0 };
+                assert!((value - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_checkerboard_peaks_at_the_zone_corner() {
+        let mut lattice = Lattice::with_seed(4, 1.0, 1.0, 42);
+        for y in 0..4 {
+            for x in 0..4 {
+                lattice.set_spin(x, y, if (x + y) % 2 == 0 { 1 } else { -1 });
+            }
+        }
+
+        let structure_factor = lattice.structure_factor();
+
+        assert!((structure_factor[0][0] - 16.0).abs() < 1e-9);
+        assert!(structure_factor[2][2].abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_structure_factor_averages_to_one() {
+        let lattice = Lattice::with_seed(7, 1.0, 1.0, 42);
+
+        let structure_factor = lattice.structure_factor();
+
+        // Parseval: the mean over k of |s_k|^2 / N is the mean of s_r^2
+        let mean = structure_factor.iter().flatten().sum::<f64>() / 49.0;
+        assert!((mean - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,210 @@
+use std::{fs, io};
+
+/// Linear temperature ramp run one step per sweep
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Deserialize)]
+pub struct Anneal {
+    /// temperature of the first sweep
+    pub from: f64,
+    /// temperature of the last sweep
+    pub to: f64,
+    /// number of sweeps the ramp lasts
+    pub sweeps: usize,
+    /// number of sweeps already run
+    #[serde(skip)]
+    pub done: usize,
+}
+
+impl Anneal {
+    /// Create a new Anneal ramping from `from` to `to` over sweeps sweeps
+    pub fn new(from: f64, to: f64, sweeps: usize) -> Self {
+        Self {
+            from,
+            to,
+            sweeps: sweeps.max(1),
+            done: 0,
+        }
+    }
+
+    /// Temperature of the given sweep of the ramp
+    pub fn temperature_at(&self, sweep: usize) -> f64 {
+        let progress = if self.sweeps <= 1 {
+            1.0
+        } else {
+            sweep as f64 / (self.sweeps - 1) as f64
+        };
+        self.from + (self.to - self.from) * progress
+    }
+
+    /// Temperature of the next sweep, None once the ramp is over
+    pub fn next_temperature(&mut self) -> Option<f64> {
+        if self.is_finished() {
+            return None;
+        }
+        let temperature = self.temperature_at(self.done);
+        self.done += 1;
+        Some(temperature)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.sweeps
+    }
+}
+
+/// Annealing stages run one after the other
+/// Loaded from a TOML file listing the stages in order, e.g.
+/// ```toml
+/// [[stage]]
+/// from = 20000.0
+/// to = 20000.0
+/// sweeps = 100
+///
+/// [[stage]]
+/// from = 20000.0
+/// to = 1000.0
+/// sweeps = 400
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize)]
+pub struct Schedule {
+    /// ramps in running order
+    #[serde(rename = "stage")]
+    pub stages: Vec<Anneal>,
+    /// index of the running stage
+    #[serde(skip)]
+    pub stage: usize,
+}
+
+impl Schedule {
+    /// Create a new Schedule running stages in order
+    pub fn new(stages: Vec<Anneal>) -> Self {
+        Self { stages, stage: 0 }
+    }
+
+    /// Load a schedule from a TOML file
+    pub fn load(path: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to read schedule {path}. Error {e}"),
+            )
+        })?;
+        let schedule: Self = toml::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse schedule {path}. Error {e}"),
+            )
+        })?;
+        if schedule.stages.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse schedule {path}. Error no stage"),
+            ));
+        }
+        Ok(schedule)
+    }
+
+    /// Temperature of the next sweep, moving to the next stage when one is over
+    /// None once every stage is over
+    pub fn next_temperature(&mut self) -> Option<f64> {
+        while let Some(stage) = self.stages.get_mut(self.stage) {
+            if let Some(temperature) = stage.next_temperature() {
+                return Some(temperature);
+            }
+            self.stage += 1;
+        }
+        None
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stages[self.stage..].iter().all(Anneal::is_finished)
+    }
+
+    /// Number of sweeps of every stage together
+    pub fn total_sweeps(&self) -> usize {
+        self.stages.iter().map(|stage| stage.sweeps).sum()
+    }
+
+    /// Number of sweeps left before the schedule is over
+    pub fn remaining_sweeps(&self) -> usize {
+        self.stages
+            .iter()
+            .map(|stage| stage.sweeps.saturating_sub(stage.done))
+            .sum()
+    }
+
+    /// Planned temperature of every sweep of the schedule as (sweep, temperature)
+    pub fn planned_temperatures(&self) -> Vec<(f64, f64)> {
+        self.stages
+            .iter()
+            .flat_map(|stage| (0..stage.sweeps).map(|sweep| stage.temperature_at(sweep)))
+            .enumerate()
+            .map(|(sweep, temperature)| (sweep as f64, temperature))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn load_str(name: &str, content: &str) -> io::Result<Schedule> {
+        let path = std::env::temp_dir().join(format!("r-ising-schedule-test-{name}.toml"));
+        fs::write(&path, content).unwrap();
+        let schedule = Schedule::load(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        schedule
+    }
+
+    #[test]
+    fn test_load_reads_stages_in_order() {
+        let schedule = load_str(
+            "valid",
+            "[[stage]]\nfrom = 4.0\nto = 4.0\nsweeps = 2\n\n\
+             [[stage]]\nfrom = 4.0\nto = 1.0\nsweeps = 4\n",
+        )
+        .unwrap();
+        assert_eq!(
+            schedule,
+            Schedule::new(vec![Anneal::new(4.0, 4.0, 2), Anneal::new(4.0, 1.0, 4)])
+        );
+        assert_eq!(schedule.total_sweeps(), 6);
+    }
+
+    #[test]
+    fn test_load_rejects_bad_schedules() {
+        let missing = Schedule::load("/nonexistent/r-ising-schedule.toml").unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert!(missing.to_string().contains("Failed to read schedule"));
+
+        let empty = load_str("empty", "stage = []\n").unwrap_err();
+        assert_eq!(empty.kind(), io::ErrorKind::InvalidData);
+        assert!(empty.to_string().contains("no stage"));
+
+        let mistyped = load_str(
+            "mistyped",
+            "[[stage]]\nfrom = \"hot\"\nto = 1.0\nsweeps = 4\n",
+        )
+        .unwrap_err();
+        assert_eq!(mistyped.kind(), io::ErrorKind::InvalidData);
+        assert!(mistyped.to_string().contains("Failed to parse schedule"));
+    }
+
+    #[test]
+    fn test_planned_temperatures_follow_the_stages() {
+        let schedule = Schedule::new(vec![Anneal::new(4.0, 4.0, 2), Anneal::new(3.0, 1.0, 3)]);
+        assert_eq!(
+            schedule.planned_temperatures(),
+            vec![(0.0, 4.0), (1.0, 4.0), (2.0, 3.0), (3.0, 2.0), (4.0, 1.0)]
+        );
+
+        let mut running = schedule.clone();
+        let run: Vec<f64> = std::iter::from_fn(|| running.next_temperature()).collect();
+        let planned: Vec<f64> = schedule
+            .planned_temperatures()
+            .into_iter()
+            .map(|(_, t)| t)
+            .collect();
+        assert_eq!(run, planned);
+        assert!(running.is_finished());
+        assert_eq!(running.remaining_sweeps(), 0);
+    }
+}
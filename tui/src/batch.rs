@@ -0,0 +1,29 @@
+use internal::{algorithm::Algorithm, recorder::Recorder, Lattice};
+use std::io::{self, Write};
+
+/// Run sweeps without the UI, writing the observables of every sweep as CSV
+pub fn run(
+    mut lattice: Lattice,
+    algorithm: Algorithm,
+    sweeps: usize,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut recorder = Recorder::new(1);
+    writeln!(out, "sweep,temperature,magnetization,energy,acceptance")?;
+    for _ in 0..sweeps {
+        let flipped = lattice.sweep_with(algorithm, |_, _| {});
+        recorder.record(&lattice, flipped);
+        if let Some(sample) = recorder.last() {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                sample.sweep,
+                lattice.temperature,
+                sample.magnetization,
+                sample.energy,
+                sample.acceptance
+            )?;
+        }
+    }
+    out.flush()
+}
@@ -0,0 +1,158 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Parameter change typed in the command prompt, e.g. `temp 2.269` or `size 100`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Temperature(f64),
+    Interactivity(f64),
+    Size(usize),
+    Increment(f64),
+    Speed(f64),
+    AnnealMax(f64),
+    AnnealMin(f64),
+    AnnealSweeps(usize),
+    Seed(u64),
+}
+
+/// Largest lattice side accepted by `size`, matching the GUI slider
+pub const MAX_SIZE: usize = 1000;
+
+/// Command names and their arguments shown when a command is unknown
+pub const USAGE: &str = "temp <K>, j <J>, size <N>, inc <step>, speed <sweeps/s>, \
+    anneal-max <K>, anneal-min <K>, anneal-sweeps <N>, seed <N>";
+
+impl Command {
+    /// Whether the command changes the lattice and so is disabled while replaying
+    pub fn changes_lattice(self) -> bool {
+        matches!(
+            self,
+            Command::Temperature(_)
+                | Command::Interactivity(_)
+                | Command::Size(_)
+                | Command::Seed(_)
+        )
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut words = input.split_whitespace();
+        let (Some(name), Some(value), None) = (words.next(), words.next(), words.next()) else {
+            return Err(format!("Expected '<command> <value>', one of {USAGE}"));
+        };
+        let command = match name {
+            "temp" | "temperature" => Command::Temperature(parse_value(value)?),
+            "j" | "interactivity" => Command::Interactivity(parse_value(value)?),
+            "size" => Command::Size(parse_value(value)?),
+            "inc" | "increment" => Command::Increment(parse_value(value)?),
+            "speed" => Command::Speed(parse_value(value)?),
+            "anneal-max" => Command::AnnealMax(parse_value(value)?),
+            "anneal-min" => Command::AnnealMin(parse_value(value)?),
+            "anneal-sweeps" => Command::AnnealSweeps(parse_value(value)?),
+            "seed" => Command::Seed(parse_value(value)?),
+            _ => return Err(format!("Unknown command '{name}', one of {USAGE}")),
+        };
+        match command {
+            Command::Temperature(t) | Command::AnnealMax(t) | Command::AnnealMin(t)
+                if !t.is_finite() || t < 0.0 =>
+            {
+                Err("Temperature must be a finite value of at least 0".to_string())
+            }
+            Command::Interactivity(j) | Command::Increment(j) if !j.is_finite() => {
+                Err(format!("Value '{value}' must be finite"))
+            }
+            Command::Speed(speed) if !speed.is_finite() || speed <= 0.0 => {
+                Err("Speed must be a finite number of sweeps per second above 0".to_string())
+            }
+            Command::Size(0) | Command::AnnealSweeps(0) => {
+                Err(format!("Value '{value}' must be at least 1"))
+            }
+            Command::Size(size) if size > MAX_SIZE => {
+                Err(format!("Value '{value}' must be at most {MAX_SIZE}"))
+            }
+            command => Ok(command),
+        }
+    }
+}
+
+fn parse_value<T>(value: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("Invalid value '{value}'. Error {e}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parses_valid_commands() {
+        assert_eq!("temp 2.269".parse(), Ok(Command::Temperature(2.269)));
+        assert_eq!("temperature 0".parse(), Ok(Command::Temperature(0.0)));
+        assert_eq!("j -1.5".parse(), Ok(Command::Interactivity(-1.5)));
+        assert_eq!("size 100".parse(), Ok(Command::Size(100)));
+        assert_eq!("inc 0.1".parse(), Ok(Command::Increment(0.1)));
+        assert_eq!("speed 30".parse(), Ok(Command::Speed(30.0)));
+        assert_eq!("anneal-max 5".parse(), Ok(Command::AnnealMax(5.0)));
+        assert_eq!("anneal-min 0.5".parse(), Ok(Command::AnnealMin(0.5)));
+        assert_eq!("anneal-sweeps 200".parse(), Ok(Command::AnnealSweeps(200)));
+        assert_eq!("  seed   42 ".parse(), Ok(Command::Seed(42)));
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_malformed_commands() {
+        assert!("warp 9"
+            .parse::<Command>()
+            .unwrap_err()
+            .contains("Unknown command 'warp'"));
+        assert!("temp".parse::<Command>().is_err());
+        assert!("temp 1 2".parse::<Command>().is_err());
+        assert!("size ten"
+            .parse::<Command>()
+            .unwrap_err()
+            .contains("Invalid value 'ten'"));
+        assert!("seed -1".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn test_rejects_non_finite_and_negative_values() {
+        for input in [
+            "temp NaN",
+            "temp inf",
+            "temp -1",
+            "anneal-max -0.5",
+            "anneal-min inf",
+        ] {
+            assert!(input.parse::<Command>().is_err(), "{input}");
+        }
+        for input in ["j NaN", "inc inf", "inc -inf"] {
+            assert!(input.parse::<Command>().is_err(), "{input}");
+        }
+        for input in ["speed 0", "speed -2", "speed NaN"] {
+            assert!(input.parse::<Command>().is_err(), "{input}");
+        }
+        assert_eq!("inc -0.1".parse(), Ok(Command::Increment(-0.1)));
+    }
+
+    #[test]
+    fn test_size_bounds() {
+        assert!("size 0".parse::<Command>().is_err());
+        assert_eq!("size 1".parse(), Ok(Command::Size(1)));
+        assert_eq!(
+            format!("size {MAX_SIZE}").parse(),
+            Ok(Command::Size(MAX_SIZE))
+        );
+        let err = format!("size {}", MAX_SIZE + 1)
+            .parse::<Command>()
+            .unwrap_err();
+        assert!(err.contains(&format!("at most {MAX_SIZE}")), "{err}");
+        assert!("size 100000".parse::<Command>().is_err());
+        assert!("anneal-sweeps 0".parse::<Command>().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use crossterm::event::KeyCode;
+use std::{collections::HashMap, fs, io};
+
+/// Actions that can be bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    ToggleHelp,
+    CloseHelp,
+    IncreaseInteractivity,
+    DecreaseInteractivity,
+    IncreaseTemperature,
+    DecreaseTemperature,
+    IncreaseSpeed,
+    DecreaseSpeed,
+    IncreaseIncrement,
+    DecreaseIncrement,
+    ZoomIn,
+    ZoomOut,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ToggleEnergyChart,
+    ToggleRenderMode,
+    ToggleEnergyColoring,
+    ToggleClusterColoring,
+    SaveSnapshot,
+    LoadSnapshot,
+    ExportLattice,
+    TogglePause,
+    ReplayFaster,
+    ReplaySlower,
+    ToggleAnneal,
+    CycleAlgorithm,
+    GrowLattice,
+    ShrinkLattice,
+    OpenCommand,
+    ToggleComparison,
+    NextTab,
+    ShowSimulation,
+    ShowStatistics,
+    ShowSettings,
+    EditSetting,
+    ToggleLog,
+    CycleBoundary,
+    ResetLattice,
+    Reseed,
+}
+
+impl Action {
+    /// Every action with its description, in the order shown in the help overlay
+    pub const ALL: [(Action, &'static str); 42] = [
+        (Action::Quit, "Quit"),
+        (Action::ToggleHelp, "Toggle this help"),
+        (Action::CloseHelp, "Close this help or the log"),
+        (Action::IncreaseInteractivity, "Increase interactivity J"),
+        (Action::DecreaseInteractivity, "Decrease interactivity J"),
+        (Action::IncreaseTemperature, "Increase temperature"),
+        (Action::DecreaseTemperature, "Decrease temperature"),
+        (Action::IncreaseSpeed, "Increase target sweeps per second"),
+        (Action::DecreaseSpeed, "Decrease target sweeps per second"),
+        (Action::IncreaseIncrement, "Increase variable increment"),
+        (Action::DecreaseIncrement, "Decrease variable increment"),
+        (Action::ZoomIn, "Zoom in"),
+        (Action::ZoomOut, "Zoom out"),
+        (Action::PanLeft, "Pan the viewport left"),
+        (Action::PanRight, "Pan the viewport right"),
+        (Action::PanUp, "Pan the viewport up"),
+        (Action::PanDown, "Pan the viewport down"),
+        (Action::ToggleEnergyChart, "Toggle energy chart"),
+        (
+            Action::ToggleRenderMode,
+            "Switch arrow / half-block / ASCII rendering",
+        ),
+        (
+            Action::ToggleEnergyColoring,
+            "Color cells by local bond energy instead of spin",
+        ),
+        (
+            Action::ToggleClusterColoring,
+            "Color each same-spin domain with its own color",
+        ),
+        (Action::SaveSnapshot, "Write the lattice to a snapshot file"),
+        (Action::LoadSnapshot, "Open a lattice snapshot file"),
+        (Action::ExportLattice, "Export the lattice to PNG and text"),
+        (Action::TogglePause, "Pause or resume the simulation"),
+        (Action::ReplayFaster, "Replay more sweeps per tick"),
+        (Action::ReplaySlower, "Replay fewer sweeps per tick"),
+        (Action::ToggleAnneal, "Start or stop the annealing schedule"),
+        (
+            Action::CycleAlgorithm,
+            "Cycle Metropolis / heat-bath / Wolff updates",
+        ),
+        (Action::GrowLattice, "Grow the lattice size"),
+        (Action::ShrinkLattice, "Shrink the lattice size"),
+        (Action::OpenCommand, "Set a parameter, e.g. `temp 2.269`"),
+        (
+            Action::ToggleComparison,
+            "Compare T / 2, T, and 2T side by side",
+        ),
+        (Action::NextTab, "Switch to the next tab"),
+        (Action::ShowSimulation, "Show the simulation tab"),
+        (Action::ShowStatistics, "Show the statistics tab"),
+        (Action::ShowSettings, "Show the settings tab"),
+        (Action::EditSetting, "Edit the selected setting"),
+        (Action::ToggleLog, "Open or close the message log"),
+        (
+            Action::CycleBoundary,
+            "Cycle mirror / open / periodic / fixed edges",
+        ),
+        (Action::ResetLattice, "Reset the lattice to a chosen state"),
+        (Action::Reseed, "Restart from a new random or typed seed"),
+    ];
+
+    /// Whether the action changes the lattice and so is disabled while replaying
+    pub fn changes_lattice(self) -> bool {
+        matches!(
+            self,
+            Action::IncreaseInteractivity
+                | Action::DecreaseInteractivity
+                | Action::IncreaseTemperature
+                | Action::DecreaseTemperature
+                | Action::LoadSnapshot
+                | Action::ToggleAnneal
+                | Action::GrowLattice
+                | Action::ShrinkLattice
+                | Action::ResetLattice
+                | Action::Reseed
+        )
+    }
+}
+
+/// One or many keys bound to an action in the keybindings file
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Mapping of keys to actions
+/// Loaded from a TOML file where each listed action replaces its default keys, e.g.
+/// ```toml
+/// increase_temperature = "K"
+/// decrease_temperature = "J"
+/// quit = ["q", "Esc"]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = [
+            (KeyCode::Char('q'), Action::Quit),
+            (KeyCode::Char('Q'), Action::Quit),
+            (KeyCode::Char('?'), Action::ToggleHelp),
+            (KeyCode::Esc, Action::CloseHelp),
+            (KeyCode::Char('i'), Action::IncreaseInteractivity),
+            (KeyCode::Char('I'), Action::DecreaseInteractivity),
+            (KeyCode::Char('t'), Action::IncreaseTemperature),
+            (KeyCode::Char('T'), Action::DecreaseTemperature),
+            (KeyCode::Char('d'), Action::IncreaseSpeed),
+            (KeyCode::Char('D'), Action::DecreaseSpeed),
+            (KeyCode::Char('>'), Action::IncreaseIncrement),
+            (KeyCode::Char('<'), Action::DecreaseIncrement),
+            (KeyCode::Char('+'), Action::ZoomIn),
+            (KeyCode::Char('-'), Action::ZoomOut),
+            (KeyCode::Left, Action::PanLeft),
+            (KeyCode::Right, Action::PanRight),
+            (KeyCode::Up, Action::PanUp),
+            (KeyCode::Down, Action::PanDown),
+            (KeyCode::Char('c'), Action::ToggleEnergyChart),
+            (KeyCode::Char('m'), Action::ToggleRenderMode),
+            (KeyCode::Char('e'), Action::ToggleEnergyColoring),
+            (KeyCode::Char('k'), Action::ToggleClusterColoring),
+            (KeyCode::Char('w'), Action::SaveSnapshot),
+            (KeyCode::Char('o'), Action::LoadSnapshot),
+            (KeyCode::Char('x'), Action::ExportLattice),
+            (KeyCode::Char(' '), Action::TogglePause),
+            (KeyCode::Char(']'), Action::ReplayFaster),
+            (KeyCode::Char('['), Action::ReplaySlower),
+            (KeyCode::Char('a'), Action::ToggleAnneal),
+            (KeyCode::Char('g'), Action::CycleAlgorithm),
+            (KeyCode::Char('s'), Action::GrowLattice),
+            (KeyCode::Char('S'), Action::ShrinkLattice),
+            (KeyCode::Char(':'), Action::OpenCommand),
+            (KeyCode::Char('v'), Action::ToggleComparison),
+            (KeyCode::Tab, Action::NextTab),
+            (KeyCode::Char('1'), Action::ShowSimulation),
+            (KeyCode::Char('2'), Action::ShowStatistics),
+            (KeyCode::Char('3'), Action::ShowSettings),
+            (KeyCode::Enter, Action::EditSetting),
+            (KeyCode::Char('l'), Action::ToggleLog),
+            (KeyCode::Char('b'), Action::CycleBoundary),
+            (KeyCode::Char('r'), Action::ResetLattice),
+            (KeyCode::Char('n'), Action::Reseed),
+        ];
+        Self {
+            bindings: HashMap::from(bindings),
+        }
+    }
+}
+
+impl Keymap {
+    /// Load the default keymap overridden by a TOML keybindings file
+    pub fn load(path: &str) -> io::Result<Self> {
+        let invalid_data = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let content = fs::read_to_string(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to read keybindings {path}. Error {e}"),
+            )
+        })?;
+        let overrides: HashMap<Action, Keys> = toml::from_str(&content)
+            .map_err(|e| invalid_data(format!("Failed to parse keybindings {path}. Error {e}")))?;
+
+        let mut keymap = Self::default();
+        for (action, keys) in overrides {
+            let keys = match keys {
+                Keys::One(key) => vec![key],
+                Keys::Many(keys) => keys,
+            };
+            keymap.bindings.retain(|_, bound| *bound != action);
+            for key in keys {
+                let code = parse_key(&key)
+                    .ok_or_else(|| invalid_data(format!("Unknown key '{key}' in {path}")))?;
+                keymap.bindings.insert(code, action);
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Action bound to a key, if any
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings.get(&code).copied()
+    }
+
+    /// Keys bound to an action joined for display
+    pub fn keys(&self, action: Action) -> String {
+        let mut keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(code, _)| code.to_string())
+            .collect();
+        keys.sort();
+        keys.join(" / ")
+    }
+}
+
+/// Parse a single character or a named key like `Left`, `Esc`, or `Tab`
+fn parse_key(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(KeyCode::Char(c)),
+        _ => match key {
+            "Left" => Some(KeyCode::Left),
+            "Right" => Some(KeyCode::Right),
+            "Up" => Some(KeyCode::Up),
+            "Down" => Some(KeyCode::Down),
+            "Esc" => Some(KeyCode::Esc),
+            "Tab" => Some(KeyCode::Tab),
+            "Enter" => Some(KeyCode::Enter),
+            "Backspace" => Some(KeyCode::Backspace),
+            "Space" => Some(KeyCode::Char(' ')),
+            _ => None,
+        },
+    }
+}
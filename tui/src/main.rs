@@ -1,23 +1,272 @@
+use anneal::{Anneal, Schedule};
+use clap::Parser;
+use command::Command;
 use core::f64;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use internal::Lattice;
+use crossterm::event::{
+    self, Event, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+};
+use internal::{
+    algorithm::Algorithm,
+    export,
+    initial_state::InitialState,
+    recorder::Recorder,
+    session::{SessionEvent, SessionWriter},
+    snapshot::{Snapshot, SNAPSHOT_EXTENSION},
+    Lattice,
+};
+use keymap::{Action, Keymap};
+use menu::{Menu, MenuEvent, MenuKind};
+use messages::{Level, Message, Messages};
+use pacer::Pacer;
+use prompt::{Prompt, PromptEvent, PromptKind};
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
-    style::{Color, Stylize},
-    symbols::border,
+    layout::{Constraint, Flex, Layout, Position, Rect},
+    style::{Color, Style, Stylize},
+    symbols::{self, border},
     text::Line,
-    widgets::{Block, BorderType, Paragraph, Widget},
+    widgets::{
+        Axis, BarChart, Block, Chart, Clear, Dataset, GraphType, Paragraph, Row, Sparkline, Table,
+        Tabs, Widget,
+    },
     DefaultTerminal, Frame,
 };
-use std::time::Instant;
+use replay::Replay;
+use settings::Setting;
+use std::cell::Cell;
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::iter::repeat_n;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::{io, time::Duration};
+use tab::Tab;
+use terminal::TerminalGuard;
+use theme::Theme;
+use throughput::Throughput;
+use viewport::Viewport;
+
+mod anneal;
+mod batch;
+mod command;
+mod keymap;
+mod menu;
+mod messages;
+mod pacer;
+mod prompt;
+mod replay;
+mod settings;
+mod tab;
+mod terminal;
+mod theme;
+mod throughput;
+mod viewport;
+
+/// Ising model simulation in the terminal
+#[derive(Parser, Debug)]
+struct Args {
+    /// Color theme, `default`, `monochrome`, or a path to a TOML theme file
+    #[arg(long, default_value = "default")]
+    theme: String,
+    /// Skip the UI, run the simulation, and print the observables of every sweep as CSV
+    #[arg(long)]
+    batch: bool,
+    /// Number of sweeps run in batch mode
+    #[arg(long, default_value_t = 1000, requires = "batch")]
+    sweeps: usize,
+    /// Draw spins as `+` and `-` without colors, the default when the terminal lacks colors
+    #[arg(long)]
+    ascii: bool,
+    /// Path to a TOML file remapping the default keybindings
+    #[arg(long)]
+    keys: Option<String>,
+    /// Record parameter changes and accepted flips into a session file
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<String>,
+    /// Play back a session file recorded with --record
+    #[arg(long)]
+    replay: Option<String>,
+    /// Starting temperature of the automatic temperature sweep
+    #[arg(long, default_value_t = 20_000.0)]
+    anneal_max: f64,
+    /// Final temperature of the automatic temperature sweep
+    #[arg(long, default_value_t = 1_000.0)]
+    anneal_min: f64,
+    /// Number of sweeps the automatic temperature sweep lasts
+    #[arg(long, default_value_t = 500)]
+    anneal_sweeps: usize,
+    /// Ring the terminal bell when the simulation reaches equilibrium
+    #[arg(long)]
+    bell: bool,
+    /// Path to a TOML annealing schedule run by the anneal key instead of the single sweep
+    #[arg(long)]
+    schedule: Option<String>,
+}
+
+/// Parameters and their meaning shown in the help overlay
+const PARAMETERS: [(&str, &str); 3] = [
+    (
+        "Interactivity",
+        "Coupling J between neighbours, J > 0 aligns spins",
+    ),
+    (
+        "Temperature",
+        "Thermal noise in Kelvin, higher flips more spins",
+    ),
+    ("Increment", "Step used when changing J and temperature"),
+];
+
+/// Lattice size, interactivity, and temperature the simulation starts with
+const INIT_SIZE: usize = 25;
+const INIT_INTERACTIVITY: f64 = 10_000.0;
+const INIT_TEMPERATURE: f64 = 10_000.0;
+
+/// Longest time spent running due sweeps before the next frame is drawn
+const FRAME_BUDGET: Duration = Duration::from_millis(50);
+
+/// RGB colors of the lowest and highest local bond energy in the energy coloring mode
+const ENERGY_COLORS: ((u8, u8, u8), (u8, u8, u8)) = ((20, 20, 60), (255, 230, 80));
+
+/// Colors cycled through by the clusters in the cluster coloring mode
+const CLUSTER_COLORS: [Color; 12] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+];
+
+/// Pixels per spin side in exported PNG images
+const EXPORT_SCALE: usize = 8;
+
+/// Number of spins added to or removed from each side when resizing the lattice
+const SIZE_STEP: usize = 5;
+
+/// Maximum number of pixels per side of the minimap drawn while zoomed or panned
+const MINIMAP_PIXELS: usize = 16;
+
+/// Temperature of the colder and hotter compared lattices relative to the main lattice
+const COMPARISON_FACTORS: [f64; 2] = [0.5, 2.0];
+
+/// Number of latest samples the susceptibility is estimated from
+const SUSCEPTIBILITY_WINDOW: usize = 50;
+
+/// Number of latest samples the rolling acceptance ratio is averaged over
+const ACCEPTANCE_WINDOW: usize = 20;
+
+/// Number of samples in each of the two energy windows compared to detect equilibrium
+const EQUILIBRATION_WINDOW: usize = 50;
+
+/// Rolling acceptance ratios below or above these bounds are flagged as a useless regime
+const ACCEPTANCE_BOUNDS: (f64, f64) = (0.01, 0.99);
+
+/// How spins are drawn in the terminal
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum RenderMode {
+    /// Three cells per spin with an arrow
+    #[default]
+    Arrow,
+    /// Two spins per cell stacked with a half-block
+    HalfBlock,
+    /// Three cells per spin with `+` or `-` and no colors
+    Ascii,
+}
+
+impl RenderMode {
+    fn name(self) -> &'static str {
+        match self {
+            RenderMode::Arrow => "Arrow",
+            RenderMode::HalfBlock => "Half-block",
+            RenderMode::Ascii => "ASCII",
+        }
+    }
+}
+
+/// What the colors of the cells show
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Coloring {
+    /// Theme color of the spin sign
+    #[default]
+    Spin,
+    /// Local bond energy, domain walls are bright
+    Energy,
+    /// Distinct color per connected same-spin domain
+    Cluster,
+}
+
+impl Coloring {
+    // Switch to coloring, or back to spin colors when it is already active
+    fn toggle(self, coloring: Coloring) -> Self {
+        if self == coloring {
+            Coloring::Spin
+        } else {
+            coloring
+        }
+    }
+}
+
+/// Number of bins of the magnetization histogram
+const HISTOGRAM_BINS: usize = 10;
 
 #[derive(Debug, Default)]
 struct App {
+    tab: Tab,
+    // Index of the setting selected in the settings tab
+    selected_setting: usize,
+    theme: Theme,
+    keymap: Keymap,
+    // Recent events, the latest shown in the message line
+    messages: Messages,
+    show_log: bool,
+    // Number of messages scrolled back from the newest in the log view
+    log_scroll: usize,
+    // Text input popup capturing keys while open
+    prompt: Option<Prompt>,
+    // Choice popup capturing keys while open
+    menu: Option<Menu>,
     lattice: Lattice,
+    // Colder and hotter copies of the lattice shown side by side
+    comparison: [Lattice; 2],
+    comparing: bool,
+    algorithm: Algorithm,
+    recorder: Recorder,
     increment: f64,
-    delay: Duration,
+    pacer: Pacer,
+    show_energy_chart: bool,
+    render_mode: RenderMode,
+    coloring: Coloring,
+    viewport: Viewport,
+    // Number of spin columns and rows visible in the last drawn frame
+    visible_span: Cell<(usize, usize)>,
+    // Terminal area covered by spins in the last drawn frame
+    lattice_rect: Cell<Rect>,
+    // Spin painted while dragging the mouse
+    paint_spin: Option<i32>,
+    show_help: bool,
+    throughput: Throughput,
+    paused: bool,
+    // Session file receiving every change of the lattice
+    session: Option<SessionWriter<BufWriter<File>>>,
+    // Recorded session played back instead of simulating
+    replay: Option<Replay>,
+    // Temperature sweep run by the anneal key without --schedule
+    anneal_ramp: Anneal,
+    // Schedule loaded with --schedule, replacing the single temperature sweep
+    schedule: Option<Schedule>,
+    // Running annealing schedule
+    anneal: Option<Schedule>,
+    // Whether the energy settled since the last change of the lattice or its parameters
+    equilibrated: bool,
+    // Recorded sweeps at the last change, the detection only testing the samples taken since
+    changed_at: u64,
+    bell: bool,
     exit: bool,
 }
 
@@ -25,27 +274,30 @@ impl App {
     /// Run app until user quit
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         // Init lattice and values
-        let init_interactivity = 10_000.0;
-        let init_temperature = 10_000.0;
         self.increment = 1000.0;
-        self.delay = Duration::from_millis(10);
-        let mut last_tick = Instant::now();
 
-        self.lattice = Lattice::new(25, init_interactivity, init_temperature);
+        self.lattice = initial_lattice();
+        self.recorder = Recorder::new(300);
+        self.record(SessionEvent::Lattice(Box::new(self.lattice.clone())));
 
         while !self.exit {
+            let frame_start = Instant::now();
             terminal.draw(|frame| self.draw(frame))?;
+            self.throughput.frame_time = frame_start.elapsed();
+            self.throughput.update(self.recorder.sweeps, Instant::now());
 
             // Start event pooling
-            let timeout = self.delay.saturating_sub(last_tick.elapsed());
-            if event::poll(timeout)? {
+            if event::poll(self.pacer.wait(Instant::now()))? {
                 self.handle_events()?
             }
 
-            // Update lattice after delay
-            if last_tick.elapsed() >= self.delay {
+            // Run the sweeps due since the last frame, dropping those over the frame budget
+            let batch_start = Instant::now();
+            for _ in 0..self.pacer.due(batch_start) {
                 self.on_tick();
-                last_tick = Instant::now()
+                if batch_start.elapsed() >= FRAME_BUDGET {
+                    break;
+                }
             }
         }
         Ok(())
@@ -64,42 +316,472 @@ impl App {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 self.handle_key_event(key_event)
             }
+            Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event),
             _ => {}
         };
         Ok(())
     }
 
-    fn handle_key_event(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Char('q') | KeyCode::Char('Q') => self.exit(),
-            KeyCode::Char('+') => self.increase_increment(),
-            KeyCode::Char('-') => self.decrease_increment(),
-            KeyCode::Char('i') => self.increase_interactivity(),
-            KeyCode::Char('t') => self.increase_temperature(),
-            KeyCode::Char('d') => self.increase_delay(),
-            KeyCode::Char('I') => self.decrease_interactivity(),
-            KeyCode::Char('T') => self.decrease_temperature(),
-            KeyCode::Char('D') => self.decrease_delay(),
+    // Click flips a spin, dragging paints the clicked spin new value
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        if self.tab != Tab::Simulation {
+            return;
+        }
+        let Some((x, y)) = self.spin_at(mouse_event.column, mouse_event.row) else {
+            return;
+        };
+        if self.replay.is_some() {
+            return;
+        }
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.lattice.flip(x, y);
+                let spin = self.lattice.value[y].value[x];
+                self.paint_spin = Some(spin);
+                self.record(SessionEvent::SetSpin { x, y, spin });
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(spin) = self.paint_spin {
+                    if self.lattice.value[y].value[x] != spin {
+                        self.lattice.set_spin(x, y, spin);
+                        self.record(SessionEvent::SetSpin { x, y, spin });
+                    }
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.paint_spin = None,
             _ => {}
         }
     }
 
-    // Render a lattice into Lines
-    fn render_lattice(&self) -> Vec<Line> {
+    // Find the lattice point drawn at a terminal column and row
+    fn spin_at(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        let rect = self.lattice_rect.get();
+        if !rect.contains(Position::new(column, row)) {
+            return None;
+        }
+        let (columns, rows) = self.visible_span.get();
+        let (x_range, y_range) = self.viewport.visible(self.lattice.size, columns, rows);
+        let zoom = self.viewport.zoom;
+        let dx = usize::from(column - rect.x);
+        let dy = usize::from(row - rect.y);
+        let (x, y) = match self.render_mode {
+            RenderMode::Arrow | RenderMode::Ascii => (dx / (3 * zoom), dy / zoom),
+            // A half-block cell holds two rows, pick the upper one
+            RenderMode::HalfBlock => (dx / zoom, 2 * dy / zoom),
+        };
+        let (x, y) = (x_range.start + x, y_range.start + y);
+        (x_range.contains(&x) && y_range.contains(&y)).then_some((x, y))
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if let Some(prompt) = &mut self.prompt {
+            match prompt.handle_key(key_event.code) {
+                PromptEvent::Editing => {}
+                PromptEvent::Cancelled => self.prompt = None,
+                PromptEvent::Submitted(input) => {
+                    let kind = prompt.kind;
+                    self.prompt = None;
+                    self.submit_prompt(kind, input);
+                }
+            }
+            return;
+        }
+
+        if let Some(menu) = &mut self.menu {
+            match menu.handle_key(key_event.code) {
+                MenuEvent::Browsing => {}
+                MenuEvent::Cancelled => self.menu = None,
+                MenuEvent::Selected(index) => {
+                    let kind = menu.kind;
+                    self.menu = None;
+                    self.select_menu(kind, index);
+                }
+            }
+            return;
+        }
+
+        let Some(action) = self.keymap.action(key_event.code) else {
+            let help = self.keymap.keys(Action::ToggleHelp);
+            self.messages.warning(format!(
+                "Unknown key '{}', press {help} for help",
+                key_event.code
+            ));
+            return;
+        };
+        if self.replay.is_some() && action.changes_lattice() {
+            self.messages.warning("Disabled while replaying a session");
+            return;
+        }
+        match action {
+            Action::Quit => self.exit(),
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::CloseHelp => {
+                self.show_help = false;
+                self.show_log = false;
+            }
+            Action::CycleBoundary => self.cycle_boundary(),
+            Action::ResetLattice => self.open_menu(MenuKind::InitialState),
+            // Prefill a random seed, accepted as is or replaced by a typed one
+            Action::Reseed => {
+                let input = format!("seed {}", rand::random::<u64>());
+                self.prompt = Some(Prompt::new(PromptKind::Command, input));
+            }
+            Action::ToggleLog => {
+                self.show_log = !self.show_log;
+                self.log_scroll = 0;
+            }
+            Action::IncreaseInteractivity => self.increase_interactivity(),
+            Action::DecreaseInteractivity => self.decrease_interactivity(),
+            Action::IncreaseTemperature => self.increase_temperature(),
+            Action::DecreaseTemperature => self.decrease_temperature(),
+            Action::IncreaseSpeed => self.increase_speed(),
+            Action::DecreaseSpeed => self.decrease_speed(),
+            Action::IncreaseIncrement => self.increase_increment(),
+            Action::DecreaseIncrement => self.decrease_increment(),
+            Action::ZoomIn => self.viewport.zoom_in(),
+            Action::ZoomOut => self.viewport.zoom_out(),
+            Action::PanLeft => self.pan(-1, 0),
+            Action::PanRight => self.pan(1, 0),
+            Action::PanUp if self.show_log => self.scroll_log(1),
+            Action::PanDown if self.show_log => self.scroll_log(-1),
+            Action::PanUp if self.tab == Tab::Settings => self.select_setting(-1),
+            Action::PanDown if self.tab == Tab::Settings => self.select_setting(1),
+            Action::PanUp => self.pan(0, -1),
+            Action::PanDown => self.pan(0, 1),
+            Action::ToggleEnergyChart => self.toggle_energy_chart(),
+            Action::ToggleRenderMode => self.toggle_render_mode(),
+            Action::ToggleEnergyColoring => self.coloring = self.coloring.toggle(Coloring::Energy),
+            Action::ToggleClusterColoring => {
+                self.coloring = self.coloring.toggle(Coloring::Cluster)
+            }
+            Action::SaveSnapshot => self.open_prompt(PromptKind::SaveSnapshot),
+            Action::LoadSnapshot => self.open_prompt(PromptKind::LoadSnapshot),
+            Action::OpenCommand => self.open_prompt(PromptKind::Command),
+            Action::ExportLattice => self.export_lattice(),
+            Action::TogglePause => self.paused = !self.paused,
+            Action::ReplayFaster => self.replay.iter_mut().for_each(Replay::faster),
+            Action::ReplaySlower => self.replay.iter_mut().for_each(Replay::slower),
+            Action::ToggleAnneal => self.toggle_anneal(),
+            Action::CycleAlgorithm => self.algorithm = self.algorithm.next(),
+            Action::ToggleComparison => self.toggle_comparison(),
+            Action::NextTab => self.tab = self.tab.next(),
+            Action::ShowSimulation => self.tab = Tab::Simulation,
+            Action::ShowStatistics => self.tab = Tab::Statistics,
+            Action::ShowSettings => self.tab = Tab::Settings,
+            Action::EditSetting if self.tab == Tab::Settings => self.edit_setting(),
+            Action::EditSetting => {}
+            Action::GrowLattice => {
+                self.resize_lattice((self.lattice.size + SIZE_STEP).min(command::MAX_SIZE))
+            }
+            Action::ShrinkLattice => {
+                self.resize_lattice(self.lattice.size.saturating_sub(SIZE_STEP).max(1))
+            }
+        }
+    }
+
+    // Write the lattice to timestamped PNG and text files and report them in the message line
+    fn export_lattice(&mut self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let png_path = format!("lattice-{timestamp}.png");
+        let text_path = format!("lattice-{timestamp}.txt");
+        let result = export::save_png(&self.lattice, &png_path, EXPORT_SCALE)
+            .and_then(|()| export::save_text(&self.lattice, &text_path));
+        match result {
+            Ok(()) => self
+                .messages
+                .info(format!("Exported lattice to {png_path} and {text_path}")),
+            Err(e) => self
+                .messages
+                .error(format!("Failed to export lattice. Error {e}")),
+        }
+    }
+
+    fn open_prompt(&mut self, kind: PromptKind) {
+        let input = match kind {
+            PromptKind::SaveSnapshot | PromptKind::LoadSnapshot => {
+                format!("lattice.{SNAPSHOT_EXTENSION}")
+            }
+            PromptKind::Command => String::new(),
+        };
+        self.prompt = Some(Prompt::new(kind, input));
+    }
+
+    fn open_menu(&mut self, kind: MenuKind) {
+        let items = match kind {
+            MenuKind::InitialState => InitialState::PRESETS.iter().map(|s| s.name()).collect(),
+        };
+        self.menu = Some(Menu::new(kind, items));
+    }
+
+    // Act on the item chosen in a menu
+    fn select_menu(&mut self, kind: MenuKind, index: usize) {
+        match kind {
+            MenuKind::InitialState => self.reset_lattice(InitialState::PRESETS[index]),
+        }
+    }
+
+    // Restart the lattice with a new random seed from an initial state
+    fn reset_lattice(&mut self, state: InitialState) {
+        self.restart_lattice(rand::random(), state);
+        self.messages.info(format!(
+            "Reset lattice to {} with seed {}",
+            state.name(),
+            self.lattice.seed
+        ));
+    }
+
+    // Regenerate the lattice from a seed and an initial state, keeping its parameters
+    fn restart_lattice(&mut self, seed: u64, state: InitialState) {
+        let boundary = self.lattice.boundary;
+        self.lattice = Lattice::with_seed(
+            self.lattice.size,
+            self.lattice.interactivity,
+            self.lattice.temperature,
+            seed,
+        );
+        self.lattice.boundary = boundary;
+        // A new lattice is already random, keep it so the seed matches Lattice::with_seed
+        if state != InitialState::Random {
+            self.lattice.reset_spins(state);
+        }
+        self.recorder.clear();
+        if self.comparing {
+            self.comparison = [self.lattice.clone(), self.lattice.clone()];
+        }
+        self.record(SessionEvent::Lattice(Box::new(self.lattice.clone())));
+    }
+
+    // Run the submitted prompt input and report the outcome in the message line
+    fn submit_prompt(&mut self, kind: PromptKind, input: String) {
+        if input.is_empty() {
+            return;
+        }
+        let result = match kind {
+            PromptKind::SaveSnapshot => match Snapshot::new(&self.lattice).save(&input) {
+                Ok(()) => Ok(format!("Saved snapshot to {input}")),
+                Err(e) => Err(format!("Failed to save snapshot to {input}. Error {e}")),
+            },
+            PromptKind::LoadSnapshot => match Snapshot::load(&input) {
+                Ok(snapshot) => {
+                    self.lattice = snapshot.into_lattice();
+                    self.recorder.clear();
+                    self.viewport = Viewport::default();
+                    self.record(SessionEvent::Lattice(Box::new(self.lattice.clone())));
+                    Ok(format!("Loaded snapshot from {input}"))
+                }
+                Err(e) => Err(format!("Failed to load snapshot from {input}. Error {e}")),
+            },
+            PromptKind::Command => input
+                .parse::<Command>()
+                .and_then(|command| self.run_command(command)),
+        };
+        match result {
+            Ok(message) => self.messages.info(message),
+            Err(message) => self.messages.error(message),
+        }
+    }
+
+    // Set a parameter to the exact value of a command, return the message to show
+    fn run_command(&mut self, command: Command) -> Result<String, String> {
+        if self.replay.is_some() && command.changes_lattice() {
+            return Err("Disabled while replaying a session".to_string());
+        }
+        let message = match command {
+            Command::Temperature(temperature) => {
+                self.lattice.temperature = temperature;
+                self.record(SessionEvent::Temperature(temperature));
+                format!("Temperature set to {temperature} K")
+            }
+            Command::Interactivity(interactivity) => {
+                self.lattice.interactivity = interactivity;
+                self.record(SessionEvent::Interactivity(interactivity));
+                format!("Interactivity set to {interactivity}")
+            }
+            Command::Size(size) => {
+                self.resize_lattice(size);
+                format!("Lattice size set to {size}")
+            }
+            Command::Increment(increment) => {
+                self.increment = increment;
+                format!("Variable increment set to {increment}")
+            }
+            Command::Speed(speed) => {
+                self.pacer.target = speed;
+                format!("Target speed set to {speed} sweeps/s")
+            }
+            Command::AnnealMax(temperature) => {
+                self.anneal_ramp.from = temperature;
+                format!("Temperature sweep starts at {temperature} K")
+            }
+            Command::AnnealMin(temperature) => {
+                self.anneal_ramp.to = temperature;
+                format!("Temperature sweep ends at {temperature} K")
+            }
+            Command::AnnealSweeps(sweeps) => {
+                self.anneal_ramp.sweeps = sweeps;
+                format!("Temperature sweep lasts {sweeps} sweeps")
+            }
+            Command::Seed(seed) => {
+                self.restart_lattice(seed, InitialState::Random);
+                format!("Restarted lattice from seed {seed}")
+            }
+        };
+        Ok(message)
+    }
+
+    // Number of spin columns and rows fitting in an area for the render mode and zoom
+    fn fitting_span(&self, area: Rect) -> (usize, usize) {
+        let (cell_width, spins_per_line) = match self.render_mode {
+            RenderMode::Arrow | RenderMode::Ascii => (3, 1),
+            RenderMode::HalfBlock => (1, 2),
+        };
+        let zoom = self.viewport.zoom;
+        (
+            usize::from(area.width) / (cell_width * zoom),
+            usize::from(area.height) * spins_per_line / zoom,
+        )
+    }
+
+    // Collect cells of a size x size grid visible in an area,
+    // each repeated zoom times in both directions
+    fn visible_cells<T: Clone>(
+        &self,
+        size: usize,
+        area: Rect,
+        cell: impl Fn(usize, usize) -> T,
+    ) -> Vec<Vec<T>> {
+        let (columns, rows) = self.fitting_span(area);
+        let (x_range, y_range) = self.viewport.visible(size, columns, rows);
+        let zoom = self.viewport.zoom;
+
+        y_range
+            .flat_map(|y| repeat_n(y, zoom))
+            .map(|y| {
+                x_range
+                    .clone()
+                    .flat_map(|x| repeat_n(cell(x, y), zoom))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Collect spins of a lattice visible in an area
+    fn visible_spins(&self, lattice: &Lattice, area: Rect) -> Vec<Vec<i32>> {
+        self.visible_cells(lattice.size, area, |x, y| lattice.value[y].value[x])
+    }
+
+    // Collect local bond energies of a lattice visible in an area, scaled from 0 to 1
+    fn visible_energy_levels(&self, lattice: &Lattice, area: Rect) -> Vec<Vec<f64>> {
+        let map = lattice.local_energy_map();
+        let bound = 4.0 * lattice.interactivity.abs();
+        self.visible_cells(lattice.size, area, |x, y| {
+            if bound == 0.0 {
+                0.5
+            } else {
+                ((map[y][x] / bound + 1.0) / 2.0).clamp(0.0, 1.0)
+            }
+        })
+    }
+
+    // Render the visible part of a lattice into Lines based on the render mode
+    // Cells are colored by spin sign, local bond energy, or cluster based on the coloring
+    fn render_lattice(&self, lattice: &Lattice, area: Rect) -> Vec<Line<'_>> {
+        let spins = self.visible_spins(lattice, area);
+        let colors = match (self.coloring, self.render_mode) {
+            (Coloring::Spin, RenderMode::Ascii) => return render_lattice_ascii(&spins),
+            (Coloring::Spin, _) => map_cells(&spins, |spin| self.spin_color(*spin)),
+            (Coloring::Energy, mode) => {
+                let levels = self.visible_energy_levels(lattice, area);
+                if mode == RenderMode::Ascii {
+                    return render_energy_ascii(&levels);
+                }
+                map_cells(&levels, |level| energy_color(*level))
+            }
+            (Coloring::Cluster, mode) => {
+                let labels = lattice.cluster_labels();
+                let labels = self.visible_cells(lattice.size, area, |x, y| labels[y][x]);
+                if mode == RenderMode::Ascii {
+                    return render_cluster_ascii(&labels);
+                }
+                map_cells(&labels, |label| {
+                    CLUSTER_COLORS[label % CLUSTER_COLORS.len()]
+                })
+            }
+        };
+        match self.render_mode {
+            RenderMode::HalfBlock => render_lattice_half_block(&colors),
+            _ => self.render_lattice_arrow(&spins, &colors),
+        }
+    }
+
+    fn spin_color(&self, spin: i32) -> Color {
+        if spin == 1 {
+            self.theme.spin_up
+        } else {
+            self.theme.spin_down
+        }
+    }
+
+    // Render a lattice centered in a titled block, return the area covered by its spins
+    fn render_lattice_pane(
+        &self,
+        lattice: &Lattice,
+        block: Block,
+        area: Rect,
+        buf: &mut Buffer,
+    ) -> Rect {
+        let inner = block.inner(area);
+        let lattice_line = self.render_lattice(lattice, inner);
+        let lines_width = lattice_line.first().map_or(0, |line| line.width()) as u16;
+        let lines_height = lattice_line.len() as u16;
+        Paragraph::new(lattice_line)
+            .centered()
+            .block(block)
+            .render(area, buf);
+        Rect::new(
+            inner.x + inner.width.saturating_sub(lines_width) / 2,
+            inner.y,
+            lines_width.min(inner.width),
+            lines_height.min(inner.height),
+        )
+    }
+
+    // Render the lattices compared side by side, ordered by temperature
+    // The main lattice takes the middle pane and its spin area is returned
+    fn render_comparison(&self, area: Rect, buf: &mut Buffer) -> Rect {
+        let panes = Layout::horizontal([Constraint::Ratio(1, 3); 3]).split(area);
+        let [colder, hotter] = &self.comparison;
+        for (lattice, pane) in [(colder, panes[0]), (hotter, panes[2])] {
+            let block = Block::bordered().title(comparison_title(lattice));
+            self.render_lattice_pane(lattice, block, pane, buf);
+        }
+        let block = Block::bordered()
+            .title(comparison_title(&self.lattice).bold())
+            .title(Line::from(self.viewport_indicator()).gray().right_aligned());
+        self.render_lattice_pane(&self.lattice, block, panes[1], buf)
+    }
+
+    // Render spins into Lines with three cells per spin, the background taken from colors
+    fn render_lattice_arrow(
+        &self,
+        spins: &[Vec<i32>],
+        colors: &[Vec<Color>],
+    ) -> Vec<Line<'static>> {
         let mut lattice_line = vec![];
 
-        let up = " ^ ".fg(Color::Yellow).bg(Color::Red);
-        let down = " v ".fg(Color::Yellow).bg(Color::White);
-        for y_text in &self.lattice.value {
+        for (y_text, y_colors) in spins.iter().zip(colors) {
             let mut x_row = vec![];
 
-            for x in &y_text.value {
+            for (x, color) in y_text.iter().zip(y_colors) {
                 match x {
                     -1 => {
-                        x_row.push(down.clone());
+                        x_row.push(" v ".fg(self.theme.arrow).bg(*color));
                     }
                     1 => {
-                        x_row.push(up.clone());
+                        x_row.push(" ^ ".fg(self.theme.arrow).bg(*color));
                     }
                     _ => {
                         continue;
@@ -111,10 +793,589 @@ impl App {
         lattice_line
     }
 
-    // Run Metropolis Algorithm after delay second
+    // Render the whole lattice down-sampled in the top right corner of area
+    // Only drawn in color while part of the lattice is hidden, the visible region is outlined
+    fn render_minimap(&self, area: Rect, buf: &mut Buffer) {
+        if self.render_mode == RenderMode::Ascii {
+            return;
+        }
+        let size = self.lattice.size;
+        let (columns, rows) = self.visible_span.get();
+        let (x_range, y_range) = self.viewport.visible(size, columns, rows);
+        if x_range.len() >= size && y_range.len() >= size {
+            return;
+        }
+        let scale = size.div_ceil(MINIMAP_PIXELS).max(1);
+        let pixels = size.div_ceil(scale);
+        let width = pixels as u16 + 2;
+        let height = pixels.div_ceil(2) as u16 + 2;
+        if area.width < width + 2 || area.height < height + 2 {
+            return;
+        }
+
+        // A pixel is visible when its block of spins overlaps the visible region
+        let visible = |px: usize, py: usize| {
+            (px * scale..(px + 1) * scale).any(|x| x_range.contains(&x))
+                && (py * scale..(py + 1) * scale).any(|y| y_range.contains(&y))
+        };
+        let outlined = |px: usize, py: usize| {
+            visible(px, py)
+                && [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .into_iter()
+                    .any(
+                        |(dx, dy)| match (px.checked_add_signed(dx), py.checked_add_signed(dy)) {
+                            (Some(nx), Some(ny)) if nx < pixels && ny < pixels => !visible(nx, ny),
+                            _ => true,
+                        },
+                    )
+        };
+        let color = |px: usize, py: usize| {
+            if outlined(px, py) {
+                return self.theme.arrow;
+            }
+            let total: i32 = (py * scale..((py + 1) * scale).min(size))
+                .flat_map(|y| {
+                    let spins = &self.lattice.value[y].value;
+                    (px * scale..((px + 1) * scale).min(size)).map(move |x| spins[x])
+                })
+                .sum();
+            if total >= 0 {
+                self.theme.spin_up
+            } else {
+                self.theme.spin_down
+            }
+        };
+
+        let lines: Vec<Line> = (0..pixels)
+            .step_by(2)
+            .map(|py| {
+                Line::from_iter((0..pixels).map(|px| {
+                    let cell = "▀".fg(color(px, py));
+                    if py + 1 < pixels {
+                        cell.bg(color(px, py + 1))
+                    } else {
+                        cell
+                    }
+                }))
+            })
+            .collect();
+        let map_area = Rect::new(area.right() - width - 1, area.y + 1, width, height);
+        Clear.render(map_area, buf);
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" Map "))
+            .render(map_area, buf);
+    }
+
+    // Describe the visible region of the lattice
+    fn viewport_indicator(&self) -> String {
+        let (columns, rows) = self.visible_span.get();
+        let (x_range, y_range) = self.viewport.visible(self.lattice.size, columns, rows);
+        format!(
+            " x {}..{} y {}..{} of {} | zoom {}x ",
+            x_range.start,
+            x_range.end,
+            y_range.start,
+            y_range.end,
+            self.lattice.size,
+            self.viewport.zoom
+        )
+    }
+
+    // Describe whether the simulation is paused, recorded, or replayed
+    fn playback_indicator(&self) -> String {
+        let mut indicator = String::new();
+        if self.paused {
+            indicator.push_str(" Paused ");
+        }
+        if self.equilibrated {
+            indicator.push_str(" ≈ equilibrated ");
+        }
+        if self.session.is_some() {
+            indicator.push_str(" REC ");
+        }
+        if let Some(anneal) = &self.anneal {
+            let total = anneal.total_sweeps();
+            let done = total - anneal.remaining_sweeps();
+            indicator.push_str(&format!(" Anneal {done}/{total} "));
+        }
+        if let Some(replay) = &self.replay {
+            let state = if replay.finished { "done" } else { "playing" };
+            indicator.push_str(&format!(" Replay {}x {state} ", replay.speed));
+        }
+        indicator
+    }
+
+    // Map recorded magnetization from -1..1 into 0..200 for the sparkline
+    fn magnetization_data(&self) -> Vec<u64> {
+        self.recorder
+            .samples
+            .iter()
+            .map(|sample| ((sample.magnetization + 1.0) * 100.0).round() as u64)
+            .collect()
+    }
+
+    // Map recorded acceptance ratios from 0..1 into 0..100 for the sparkline
+    fn acceptance_data(&self) -> Vec<u64> {
+        self.recorder
+            .samples
+            .iter()
+            .map(|sample| (sample.acceptance * 100.0).round() as u64)
+            .collect()
+    }
+
+    // Collect recorded energy per spin as (sweep, energy) points
+    fn energy_data(&self) -> Vec<(f64, f64)> {
+        self.recorder
+            .samples
+            .iter()
+            .map(|sample| (sample.sweep as f64, sample.energy))
+            .collect()
+    }
+
+    // Render energy per spin versus sweep number
+    fn render_energy_chart(&self, area: Rect, buf: &mut Buffer) {
+        let data = self.energy_data();
+        let (first_sweep, last_sweep) = match (data.first(), data.last()) {
+            (Some(first), Some(last)) => (first.0, last.0.max(first.0 + 1.0)),
+            _ => (0.0, 1.0),
+        };
+        let (min_energy, max_energy) = data
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, e)| {
+                (min.min(*e), max.max(*e))
+            });
+        // Pad the y axis so a flat line is not drawn on the border
+        let (min_energy, max_energy) = if min_energy.is_finite() {
+            (min_energy - 0.1, max_energy + 0.1)
+        } else {
+            (-1.0, 1.0)
+        };
+
+        let dataset = Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().magenta())
+            .data(&data);
+        let x_axis = Axis::default()
+            .title("Sweep")
+            .bounds([first_sweep, last_sweep])
+            .labels([format!("{first_sweep:.0}"), format!("{last_sweep:.0}")]);
+        let y_axis = Axis::default()
+            .title("E/N")
+            .bounds([min_energy, max_energy])
+            .labels([format!("{min_energy:.2}"), format!("{max_energy:.2}")]);
+
+        Chart::new(vec![dataset])
+            .block(Block::bordered().title(format!(
+                " Energy per spin <{}> ",
+                self.keymap.keys(Action::ToggleEnergyChart)
+            )))
+            .x_axis(x_axis)
+            .y_axis(y_axis)
+            .render(area, buf);
+    }
+
+    // Render the live observables of the simulation
+    fn render_statistics(&self, area: Rect, buf: &mut Buffer) {
+        let (magnetization, energy, acceptance) =
+            self.recorder.last().map_or((0.0, 0.0, 0.0), |s| {
+                (s.magnetization, s.energy, s.acceptance)
+            });
+        let rolling_acceptance = self.recorder.rolling_acceptance(ACCEPTANCE_WINDOW);
+        let susceptibility = self
+            .recorder
+            .susceptibility(&self.lattice, SUSCEPTIBILITY_WINDOW);
+        let sweeps = self.recorder.sweeps;
+        let seed = self.lattice.seed;
+        // A ratio pinned at 0 or 1 means the temperature is far off the interesting range
+        let (low, high) = ACCEPTANCE_BOUNDS;
+        let pinned =
+            !self.recorder.samples.is_empty() && !(low..=high).contains(&rolling_acceptance);
+        let rolling_line = Line::from(format!(" {rolling_acceptance:.4} rolling"));
+        let rolling_line = if pinned {
+            rolling_line.red().bold()
+        } else {
+            rolling_line.cyan()
+        };
+
+        let statistics = vec![
+            Line::from(" Magnetization"),
+            Line::from(format!(" {magnetization:.4}")).green().bold(),
+            Line::from(" Energy per spin"),
+            Line::from(format!(" {energy:.4}")).magenta().bold(),
+            Line::from(" Acceptance ratio"),
+            Line::from(format!(" {acceptance:.4}")).cyan().bold(),
+            rolling_line,
+            Line::from(" Susceptibility χ"),
+            Line::from(format!(" {susceptibility:.4e}")).red().bold(),
+            Line::from(" Sweeps"),
+            Line::from(format!(" {sweeps}")).yellow().bold(),
+            Line::from(" Seed"),
+            Line::from(format!(" {seed}")).blue().bold(),
+        ];
+
+        let block = Block::bordered().title(" Statistics ");
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let [statistics_area, acceptance_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(4)]).areas(inner);
+        Paragraph::new(statistics).render(statistics_area, buf);
+
+        let data = self.acceptance_data();
+        // Only show the latest sweeps that fit in the sparkline width
+        let visible = data
+            .len()
+            .saturating_sub(usize::from(acceptance_area.width));
+        Sparkline::default()
+            .block(Block::new().title(" Acceptance history"))
+            .data(&data[visible..])
+            .max(100)
+            .cyan()
+            .render(acceptance_area, buf);
+    }
+
+    // Render the lattice with live observables
+    fn render_simulation(&self, area: Rect, buf: &mut Buffer) {
+        let [area, statistics_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(24)]).areas(area);
+        self.render_statistics(statistics_area, buf);
+
+        let chart_height = if self.show_energy_chart { 12 } else { 0 };
+        let anneal_height = if self.anneal.is_some() { 8 } else { 0 };
+        let [lattice_area, anneal_area, chart_area, sparkline_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(anneal_height),
+            Constraint::Length(chart_height),
+            Constraint::Length(4),
+        ])
+        .areas(area);
+
+        let lattice_rect = if self.comparing {
+            self.render_comparison(lattice_area, buf)
+        } else {
+            let lattice_block =
+                Block::new().title(Line::from(self.viewport_indicator()).gray().right_aligned());
+            self.render_lattice_pane(&self.lattice, lattice_block, lattice_area, buf)
+        };
+        // Remember where the centered spins are drawn to map mouse events back to spins
+        self.lattice_rect.set(lattice_rect);
+        self.visible_span.set(self.fitting_span(lattice_rect));
+        self.render_minimap(lattice_area, buf);
+
+        if let Some(anneal) = &self.anneal {
+            render_anneal_chart(anneal, anneal_area, buf);
+        }
+        if self.show_energy_chart {
+            self.render_energy_chart(chart_area, buf);
+        }
+
+        let magnetization = self.recorder.last().map_or(0.0, |s| s.magnetization);
+        let sparkline_title = Line::from(vec![
+            " Magnetization".into(),
+            format!(" = {magnetization:.3} ").green().bold(),
+        ]);
+        let data = self.magnetization_data();
+        // Only show the latest sweeps that fit in the sparkline width
+        let visible = data.len().saturating_sub(usize::from(sparkline_area.width));
+        Sparkline::default()
+            .block(Block::new().title(sparkline_title))
+            .data(&data[visible..])
+            .max(200)
+            .green()
+            .render(sparkline_area, buf);
+    }
+
+    // Render the tab titles with the selected tab highlighted
+    fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
+        let keys = [
+            Action::ShowSimulation,
+            Action::ShowStatistics,
+            Action::ShowSettings,
+        ];
+        let titles = Tab::ALL
+            .iter()
+            .zip(keys)
+            .map(|(tab, action)| format!(" {} <{}> ", tab.title(), self.keymap.keys(action)));
+        Tabs::new(titles)
+            .select(self.tab.index())
+            .highlight_style(Style::default().yellow().bold())
+            .render(area, buf);
+    }
+
+    // Render the recorded observables as full size charts and a histogram
+    fn render_statistics_tab(&self, area: Rect, buf: &mut Buffer) {
+        let [charts_area, statistics_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(24)]).areas(area);
+        self.render_statistics(statistics_area, buf);
+        let [chart_area, histogram_area] =
+            Layout::vertical([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)]).areas(charts_area);
+        self.render_energy_chart(chart_area, buf);
+        self.render_magnetization_histogram(histogram_area, buf);
+    }
+
+    // Count recorded magnetization samples falling in equal bins over -1..1
+    fn magnetization_histogram(&self) -> [u64; HISTOGRAM_BINS] {
+        let mut bins = [0; HISTOGRAM_BINS];
+        for sample in &self.recorder.samples {
+            let bin = ((sample.magnetization + 1.0) / 2.0 * HISTOGRAM_BINS as f64) as usize;
+            bins[bin.min(HISTOGRAM_BINS - 1)] += 1;
+        }
+        bins
+    }
+
+    // Render the distribution of recorded magnetization
+    fn render_magnetization_histogram(&self, area: Rect, buf: &mut Buffer) {
+        let labels: Vec<String> = (0..HISTOGRAM_BINS)
+            .map(|bin| format!("{:.1}", -1.0 + 2.0 * bin as f64 / HISTOGRAM_BINS as f64))
+            .collect();
+        let bins = self.magnetization_histogram();
+        let data: Vec<(&str, u64)> = labels.iter().map(String::as_str).zip(bins).collect();
+        let bar_width = (area.width.saturating_sub(2) / HISTOGRAM_BINS as u16)
+            .saturating_sub(1)
+            .max(1);
+
+        BarChart::default()
+            .block(Block::bordered().title(" Magnetization histogram "))
+            .data(data.as_slice())
+            .bar_width(bar_width)
+            .bar_style(Style::default().green())
+            .render(area, buf);
+    }
+
+    // Render every parameter with the selected one highlighted
+    fn render_settings(&self, area: Rect, buf: &mut Buffer) {
+        let rows = Setting::ALL.iter().enumerate().map(|(index, &setting)| {
+            let row = Row::new([setting.name().to_string(), self.setting_value(setting)]);
+            if index == self.selected_setting {
+                row.reversed()
+            } else {
+                row
+            }
+        });
+        let edit = self.keymap.keys(Action::EditSetting);
+        let up = self.keymap.keys(Action::PanUp);
+        let down = self.keymap.keys(Action::PanDown);
+
+        Table::new(rows, [Constraint::Length(32), Constraint::Min(0)])
+            .block(Block::bordered().title(" Settings ").title_bottom(
+                Line::from(format!(" Select <{up} / {down}> Edit <{edit}> ")).right_aligned(),
+            ))
+            .render(area, buf);
+    }
+
+    // Render the latest message with a hint to open the log
+    fn render_message_line(&self, area: Rect, buf: &mut Buffer) {
+        let hint = format!(" Log <{}> ", self.keymap.keys(Action::ToggleLog));
+        let [message_area, hint_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(hint.len() as u16)])
+                .areas(area);
+        if let Some(message) = self.messages.latest() {
+            message_line(message).render(message_area, buf);
+        }
+        Line::from(hint).gray().render(hint_area, buf);
+    }
+
+    fn scroll_log(&mut self, delta: isize) {
+        self.log_scroll = self
+            .log_scroll
+            .saturating_add_signed(delta)
+            .min(self.messages.len().saturating_sub(1))
+    }
+
+    // Render every kept message in a centered popup, newest at the bottom
+    fn render_log(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let lines: Vec<Line> = self.messages.messages.iter().map(message_line).collect();
+        let height = usize::from(area.height.saturating_sub(2));
+        let top = lines.len().saturating_sub(height + self.log_scroll);
+        let scroll = format!(
+            " Scroll <{} / {}> Close <{}> ",
+            self.keymap.keys(Action::PanUp),
+            self.keymap.keys(Action::PanDown),
+            self.keymap.keys(Action::CloseHelp)
+        );
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .scroll((top as u16, 0))
+            .block(
+                Block::bordered()
+                    .title(Line::from(" Log ").bold().centered())
+                    .title_bottom(Line::from(scroll).centered())
+                    .border_type(self.theme.border.into()),
+            )
+            .render(area, buf);
+    }
+
+    // Render the keybindings in a centered popup over the app
+    fn render_help(&self, area: Rect, buf: &mut Buffer) {
+        let mut rows: Vec<Row> = Action::ALL
+            .iter()
+            .map(|&(action, description)| {
+                Row::new([self.keymap.keys(action).bold().yellow(), description.into()])
+            })
+            .collect();
+        rows.push(Row::new([
+            "Mouse".bold().yellow(),
+            "Click to flip a spin, drag to paint".into(),
+        ]));
+        rows.push(Row::default());
+        rows.extend(
+            PARAMETERS
+                .iter()
+                .map(|&(parameter, meaning)| Row::new([parameter.bold().blue(), meaning.into()])),
+        );
+
+        let [area] = Layout::vertical([Constraint::Length(rows.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(72)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let close = self.keymap.keys(Action::CloseHelp);
+        Clear.render(area, buf);
+        Table::new(rows, [Constraint::Length(16), Constraint::Min(0)])
+            .block(
+                Block::bordered()
+                    .title(Line::from(" Help ").bold().centered())
+                    .title_bottom(Line::from(format!(" Close <{close}> ")).centered())
+                    .border_type(self.theme.border.into()),
+            )
+            .render(area, buf);
+    }
+
+    // Run a sweep of the active algorithm and record its observables
+    // While replaying, the next recorded sweeps are played back instead
     fn on_tick(&mut self) {
-        let (x_rand, y_rand) = self.lattice.pick_random_point();
-        self.lattice.metropolis_algo_calculation(x_rand, y_rand);
+        if self.paused {
+            return;
+        }
+        if self.comparing {
+            self.tick_comparison();
+        }
+        if self.replay.is_some() {
+            self.replay_tick();
+            self.detect_equilibrium();
+            return;
+        }
+        if let Some(temperature) = self.anneal.as_mut().and_then(Schedule::next_temperature) {
+            self.lattice.temperature = temperature;
+            self.record(SessionEvent::Temperature(temperature));
+        }
+        let recording = self.session.is_some();
+        let mut flips = Vec::new();
+        let flipped = self.lattice.sweep_with(self.algorithm, |x, y| {
+            if recording {
+                flips.push((x, y))
+            }
+        });
+        self.recorder.record(&self.lattice, flipped);
+        if recording {
+            self.record(SessionEvent::Sweep(flips));
+        }
+        self.detect_equilibrium();
+        if self.anneal.as_ref().is_some_and(Schedule::is_finished) {
+            self.anneal = None;
+            self.paused = true;
+            self.messages.info(format!(
+                "Annealing finished at {:.2} K, paused",
+                self.lattice.temperature
+            ));
+        }
+    }
+
+    // Notify once when the energy settles, until the lattice or its parameters change again
+    fn detect_equilibrium(&mut self) {
+        let since_change = self.recorder.sweeps.saturating_sub(self.changed_at);
+        if self.equilibrated
+            || since_change < 2 * EQUILIBRATION_WINDOW as u64
+            || !self.recorder.is_equilibrated(EQUILIBRATION_WINDOW)
+        {
+            return;
+        }
+        self.equilibrated = true;
+        self.messages.info(format!(
+            "Equilibrated after {} sweeps, measurements are meaningful",
+            self.recorder.sweeps
+        ));
+        if self.bell {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|()| stdout.flush());
+        }
+    }
+
+    // Apply recorded events until speed sweeps are played back or the session ends
+    fn replay_tick(&mut self) {
+        let Some(mut replay) = self.replay.take() else {
+            return;
+        };
+        let mut sweeps = 0;
+        while !replay.finished && sweeps < replay.speed {
+            let result = replay.next_event().and_then(|event| match event {
+                Some(event) => self.apply_event(&event),
+                None => Ok(false),
+            });
+            match result {
+                Ok(true) => sweeps += 1,
+                Ok(false) if replay.finished => {
+                    self.messages.info("Replay finished");
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    replay.finished = true;
+                    self.messages
+                        .error(format!("Failed to replay session. Error {e}"));
+                }
+            }
+        }
+        self.replay = Some(replay);
+    }
+
+    // Apply a recorded event to the app, return whether it was a sweep
+    fn apply_event(&mut self, event: &SessionEvent) -> io::Result<bool> {
+        event.apply(&mut self.lattice)?;
+        match event {
+            SessionEvent::Sweep(flips) => {
+                self.recorder.record(&self.lattice, flips.len());
+                return Ok(true);
+            }
+            SessionEvent::Lattice(_) => {
+                self.recorder.clear();
+                self.viewport = Viewport::default();
+            }
+            _ => {}
+        }
+        self.restart_detection();
+        Ok(false)
+    }
+
+    // Restart the equilibrium detection from the latest recorded sweep
+    fn restart_detection(&mut self) {
+        self.equilibrated = false;
+        self.changed_at = self.recorder.sweeps;
+    }
+
+    // Append an event to the recorded session, stopping the recording on failure
+    // Any change but a sweep also restarts the equilibrium detection
+    fn record(&mut self, event: SessionEvent) {
+        if !matches!(event, SessionEvent::Sweep(_)) {
+            self.restart_detection();
+        }
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        if let Err(e) = session.write(&event) {
+            self.session = None;
+            self.messages
+                .error(format!("Stopped recording session. Error {e}"));
+        }
     }
 
     fn exit(&mut self) {
@@ -122,53 +1383,337 @@ impl App {
     }
 
     fn increase_interactivity(&mut self) {
-        self.lattice.interactivity += self.increment
+        self.lattice.interactivity += self.increment;
+        self.record(SessionEvent::Interactivity(self.lattice.interactivity))
     }
 
     fn increase_temperature(&mut self) {
-        self.lattice.temperature += self.increment
+        self.lattice.temperature += self.increment;
+        self.record(SessionEvent::Temperature(self.lattice.temperature))
     }
 
     fn increase_increment(&mut self) {
         self.increment += 10.0
     }
 
-    fn increase_delay(&mut self) {
-        self.delay += Duration::from_millis(10)
+    fn increase_speed(&mut self) {
+        if self.pacer.is_fastest() {
+            self.messages.warning(format!(
+                "Target speed is already at {} sweeps/s",
+                self.pacer.target
+            ));
+            return;
+        }
+        self.pacer.speed_up()
+    }
+
+    fn pan(&mut self, dx: isize, dy: isize) {
+        let (columns, rows) = self.visible_span.get();
+        self.viewport.pan(dx, dy, self.lattice.size, columns, rows)
+    }
+
+    fn toggle_energy_chart(&mut self) {
+        self.show_energy_chart = !self.show_energy_chart
+    }
+
+    fn select_setting(&mut self, delta: isize) {
+        self.selected_setting = self
+            .selected_setting
+            .saturating_add_signed(delta)
+            .min(Setting::ALL.len() - 1)
+    }
+
+    // Open the command prompt prefilled with the selected setting, or cycle it in place
+    fn edit_setting(&mut self) {
+        let setting = Setting::ALL[self.selected_setting];
+        match setting.command() {
+            Some(command) => {
+                let input = format!("{command} {}", self.setting_value(setting));
+                self.prompt = Some(Prompt::new(PromptKind::Command, input));
+            }
+            None if setting == Setting::Algorithm => self.algorithm = self.algorithm.next(),
+            None if setting == Setting::Boundary => self.cycle_boundary(),
+            None => self.toggle_render_mode(),
+        }
+    }
+
+    // Current value of a setting as typed in the command prompt
+    fn setting_value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Temperature => self.lattice.temperature.to_string(),
+            Setting::Interactivity => self.lattice.interactivity.to_string(),
+            Setting::Increment => self.increment.to_string(),
+            Setting::Speed => self.pacer.target.to_string(),
+            Setting::Size => self.lattice.size.to_string(),
+            Setting::Seed => self.lattice.seed.to_string(),
+            Setting::Algorithm => self.algorithm.name().to_string(),
+            Setting::Boundary => self.lattice.boundary.name().to_string(),
+            Setting::RenderMode => self.render_mode.name().to_string(),
+            Setting::AnnealMax => self.anneal_ramp.from.to_string(),
+            Setting::AnnealMin => self.anneal_ramp.to.to_string(),
+            Setting::AnnealSweeps => self.anneal_ramp.sweeps.to_string(),
+        }
+    }
+
+    // Resize the lattice keeping the existing spins and center the view on it
+    fn resize_lattice(&mut self, size: usize) {
+        self.lattice.set_size(size);
+        self.lattice.update_lattice();
+        let (columns, rows) = self.visible_span.get();
+        self.viewport.center(size, columns, rows);
+        self.record(SessionEvent::Lattice(Box::new(self.lattice.clone())));
+    }
+
+    // Start comparing from copies of the current lattice, or go back to a single lattice
+    fn toggle_comparison(&mut self) {
+        self.comparing = !self.comparing;
+        if self.comparing {
+            self.comparison = [self.lattice.clone(), self.lattice.clone()];
+        }
+    }
+
+    // Sweep the compared lattices, following the main lattice parameters and size
+    fn tick_comparison(&mut self) {
+        for (lattice, factor) in self.comparison.iter_mut().zip(COMPARISON_FACTORS) {
+            if lattice.size != self.lattice.size {
+                *lattice = self.lattice.clone();
+            }
+            lattice.interactivity = self.lattice.interactivity;
+            lattice.temperature = self.lattice.temperature * factor;
+            lattice.boundary = self.lattice.boundary;
+            lattice.sweep_with(self.algorithm, |_, _| {});
+        }
+    }
+
+    fn cycle_boundary(&mut self) {
+        self.lattice.boundary = self.lattice.boundary.next();
+        self.record(SessionEvent::Boundary(self.lattice.boundary))
+    }
+
+    fn toggle_anneal(&mut self) {
+        if self.anneal.take().is_some() {
+            self.messages.info("Annealing stopped");
+            return;
+        }
+        self.anneal = Some(
+            self.schedule
+                .clone()
+                .unwrap_or_else(|| Schedule::new(vec![self.anneal_ramp])),
+        );
+        self.paused = false;
+    }
+
+    fn toggle_render_mode(&mut self) {
+        self.render_mode = match self.render_mode {
+            RenderMode::Arrow => RenderMode::HalfBlock,
+            RenderMode::HalfBlock => RenderMode::Ascii,
+            RenderMode::Ascii => RenderMode::Arrow,
+        }
     }
 
     fn decrease_interactivity(&mut self) {
-        self.lattice.interactivity -= self.increment
+        self.lattice.interactivity -= self.increment;
+        self.record(SessionEvent::Interactivity(self.lattice.interactivity))
     }
 
     fn decrease_temperature(&mut self) {
         if self.lattice.temperature == 0.0 {
-            self.lattice.temperature = 0.0;
+            self.messages.warning("Temperature is already at 0 K");
             return;
         }
-        self.lattice.temperature -= self.increment
+        self.lattice.temperature -= self.increment;
+        if self.lattice.temperature < 0.0 {
+            self.lattice.temperature = 0.0;
+            self.messages.warning("Temperature clamped to 0 K");
+        }
+        self.record(SessionEvent::Temperature(self.lattice.temperature))
     }
 
     fn decrease_increment(&mut self) {
         self.increment -= 10.0
     }
 
-    fn decrease_delay(&mut self) {
-        if self.delay == Duration::from_millis(0) {
-            self.delay = Duration::from_millis(0);
+    fn decrease_speed(&mut self) {
+        if self.pacer.is_slowest() {
+            self.messages.warning(format!(
+                "Target speed is already at {} sweeps/s",
+                self.pacer.target
+            ));
             return;
         }
-        self.delay -= Duration::from_millis(10)
+        self.pacer.slow_down()
+    }
+}
+
+// Message prefixed with its time, colored by its level
+fn message_line(message: &Message) -> Line<'static> {
+    let line = Line::from(format!(" [{}] {}", message.time, message.text));
+    match message.level {
+        Level::Info => line,
+        Level::Warning => line.yellow(),
+        Level::Error => line.red().bold(),
     }
 }
 
+// Lattice the simulation starts with
+fn initial_lattice() -> Lattice {
+    Lattice::new(INIT_SIZE, INIT_INTERACTIVITY, INIT_TEMPERATURE)
+}
+
+// Render the planned temperature of a schedule with the sweeps already run highlighted
+fn render_anneal_chart(schedule: &Schedule, area: Rect, buf: &mut Buffer) {
+    let planned = schedule.planned_temperatures();
+    let total = schedule.total_sweeps();
+    let done = total - schedule.remaining_sweeps();
+    let last_sweep = (total.max(2) - 1) as f64;
+    let (min_temperature, max_temperature) = planned
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, t)| {
+            (min.min(*t), max.max(*t))
+        });
+    // Pad the y axis so a flat stage is not drawn on the border
+    let padding = ((max_temperature - min_temperature) * 0.05).max(1.0);
+    let (min_temperature, max_temperature) = if min_temperature.is_finite() {
+        (min_temperature - padding, max_temperature + padding)
+    } else {
+        (0.0, 1.0)
+    };
+
+    let datasets = vec![
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().dark_gray())
+            .data(&planned),
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().yellow())
+            .data(&planned[..done.min(planned.len())]),
+    ];
+    let x_axis = Axis::default()
+        .bounds([0.0, last_sweep])
+        .labels(["0".to_string(), format!("{last_sweep:.0}")]);
+    let y_axis = Axis::default()
+        .title("T")
+        .bounds([min_temperature, max_temperature])
+        .labels([
+            format!("{min_temperature:.0}"),
+            format!("{max_temperature:.0}"),
+        ]);
+    let stage = (schedule.stage + 1).min(schedule.stages.len());
+
+    Chart::new(datasets)
+        .block(Block::bordered().title(format!(
+            " Anneal stage {stage}/{} | {} sweeps left ",
+            schedule.stages.len(),
+            schedule.remaining_sweeps()
+        )))
+        .x_axis(x_axis)
+        .y_axis(y_axis)
+        .render(area, buf);
+}
+
+// Render two rows of cell colors into one line of upper half-blocks
+// The upper cell is the foreground and the lower cell is the background
+fn render_lattice_half_block(colors: &[Vec<Color>]) -> Vec<Line<'static>> {
+    colors
+        .chunks(2)
+        .map(|rows| {
+            let upper = &rows[0];
+            let lower = rows.get(1);
+            Line::from_iter(upper.iter().enumerate().map(|(x, color)| {
+                let cell = "▀".fg(*color);
+                match lower {
+                    Some(lower) => cell.bg(lower[x]),
+                    None => cell,
+                }
+            }))
+        })
+        .collect()
+}
+
+// Color of a local bond energy level, from dark satisfied bonds to bright domain walls
+fn energy_color(level: f64) -> Color {
+    let (low, high) = ENERGY_COLORS;
+    let mix =
+        |low: u8, high: u8| (f64::from(low) + (f64::from(high) - f64::from(low)) * level) as u8;
+    Color::Rgb(mix(low.0, high.0), mix(low.1, high.1), mix(low.2, high.2))
+}
+
+// Apply f to every cell of a grid
+fn map_cells<T, U>(cells: &[Vec<T>], f: impl Fn(&T) -> U) -> Vec<Vec<U>> {
+    cells
+        .iter()
+        .map(|row| row.iter().map(&f).collect())
+        .collect()
+}
+
+// Render cluster labels into plain Lines with three cells per spin, one letter per label
+fn render_cluster_ascii(labels: &[Vec<usize>]) -> Vec<Line<'static>> {
+    labels
+        .iter()
+        .map(|row| {
+            Line::from_iter(row.iter().map(|label| {
+                let letter = char::from(b'a' + (label % 26) as u8);
+                format!(" {letter} ")
+            }))
+        })
+        .collect()
+}
+
+// Render local bond energy levels into plain Lines with three cells per spin
+fn render_energy_ascii(levels: &[Vec<f64>]) -> Vec<Line<'static>> {
+    const SHADES: [&str; 5] = [" . ", " : ", " - ", " = ", " # "];
+    levels
+        .iter()
+        .map(|row| {
+            Line::from_iter(row.iter().map(|level| {
+                let shade = (level * (SHADES.len() - 1) as f64).round() as usize;
+                SHADES[shade.min(SHADES.len() - 1)]
+            }))
+        })
+        .collect()
+}
+
+// Render spins into plain Lines with three cells per spin
+fn render_lattice_ascii(spins: &[Vec<i32>]) -> Vec<Line<'static>> {
+    spins
+        .iter()
+        .map(|row| {
+            Line::from(
+                row.iter()
+                    .map(|spin| if *spin == 1 { " + " } else { " - " })
+                    .collect::<String>(),
+            )
+        })
+        .collect()
+}
+
+// Whether the terminal is unlikely to draw colors, following NO_COLOR and TERM
+fn lacks_color_support() -> bool {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let term = env::var("TERM").unwrap_or_default();
+    no_color || term.is_empty() || term == "dumb"
+}
+
+// Temperature and magnetization of a compared lattice
+fn comparison_title(lattice: &Lattice) -> Line<'static> {
+    Line::from(format!(
+        " T = {:.2} K | M = {:.3} ",
+        lattice.temperature,
+        lattice.magnetization()
+    ))
+}
+
 impl Widget for &App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let title = Line::from("The r-ising model".bold());
         let interactivity = self.lattice.interactivity;
         let temperature = self.lattice.temperature;
         let increment = self.increment;
-        let delay = self.delay.as_millis();
+        let seed = self.lattice.seed;
+        let speed = self.pacer.target;
 
         let instructions = Line::from(vec![
             " Interactivity".into(),
@@ -177,28 +1722,190 @@ impl Widget for &App {
             format!(" = {temperature:.2} K").blue().bold(),
             " Variable Increment".into(),
             format!(" = {increment:.2}").red(),
+            " Seed".into(),
+            format!(" = {seed} ").blue(),
         ]);
 
         let block = Block::bordered()
             .title(title.centered())
-            .title(Line::from(" Quit <q/Q> ").red().bold().left_aligned())
-            .title(Line::from(" Delay ").gray().right_aligned())
-            .title(Line::from(format!(" {delay:.2}ms ")).red().right_aligned())
+            .title(
+                Line::from(format!(" Quit <{}> ", self.keymap.keys(Action::Quit)))
+                    .red()
+                    .bold()
+                    .left_aligned(),
+            )
+            .title(
+                Line::from(format!(
+                    " Mode <{}>{} ",
+                    self.keymap.keys(Action::ToggleRenderMode),
+                    match self.coloring {
+                        Coloring::Spin => "",
+                        Coloring::Energy => " energy",
+                        Coloring::Cluster => " clusters",
+                    }
+                ))
+                .gray()
+                .left_aligned(),
+            )
+            .title(
+                Line::from(format!(
+                    " {} <{}> ",
+                    self.algorithm.name(),
+                    self.keymap.keys(Action::CycleAlgorithm)
+                ))
+                .cyan()
+                .bold()
+                .left_aligned(),
+            )
+            .title(
+                Line::from(format!(
+                    " {} edges <{}> ",
+                    self.lattice.boundary.name(),
+                    self.keymap.keys(Action::CycleBoundary)
+                ))
+                .magenta()
+                .left_aligned(),
+            )
+            .title(
+                Line::from(format!(" Help <{}> ", self.keymap.keys(Action::ToggleHelp)))
+                    .gray()
+                    .left_aligned(),
+            )
+            .title(
+                Line::from(self.playback_indicator())
+                    .yellow()
+                    .bold()
+                    .right_aligned(),
+            )
+            .title(Line::from(" Target ").gray().right_aligned())
+            .title(
+                Line::from(format!(" {speed} sweeps/s "))
+                    .red()
+                    .right_aligned(),
+            )
             .title_bottom(instructions.centered())
+            .title_bottom(
+                Line::from(format!(
+                    " {:.0} sweeps/s | frame {:.1}ms ",
+                    self.throughput.sweeps_per_second,
+                    self.throughput.frame_time.as_secs_f64() * 1000.0
+                ))
+                .gray()
+                .right_aligned(),
+            )
             .border_set(border::THICK)
-            .border_type(BorderType::Rounded);
+            .border_type(self.theme.border.into());
 
-        let lattice_line = self.render_lattice();
-        Paragraph::new(lattice_line)
-            .centered()
-            .block(block)
-            .render(area, buf);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [tabs_area, inner, message_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+        self.render_tabs(tabs_area, buf);
+        self.render_message_line(message_area, buf);
+        match self.tab {
+            Tab::Simulation => self.render_simulation(inner, buf),
+            Tab::Statistics => self.render_statistics_tab(inner, buf),
+            Tab::Settings => self.render_settings(inner, buf),
+        }
+
+        if self.show_log {
+            self.render_log(area, buf);
+        }
+
+        if self.show_help {
+            self.render_help(area, buf);
+        }
+
+        if let Some(prompt) = &self.prompt {
+            prompt.render(area, buf);
+        }
+
+        if let Some(menu) = &self.menu {
+            menu.render(area, buf);
+        }
     }
 }
 
 fn main() -> io::Result<()> {
-    let mut terminal = ratatui::init();
-    let app_result = App::default().run(&mut terminal);
-    ratatui::restore();
+    let args = Args::parse();
+    if args.batch {
+        return batch::run(
+            initial_lattice(),
+            Algorithm::default(),
+            args.sweeps,
+            &mut io::stdout().lock(),
+        );
+    }
+    let mut app = App {
+        theme: Theme::load(&args.theme)?,
+        keymap: match &args.keys {
+            Some(path) => Keymap::load(path)?,
+            None => Keymap::default(),
+        },
+        session: args
+            .record
+            .as_deref()
+            .map(SessionWriter::create)
+            .transpose()?,
+        replay: args.replay.as_deref().map(Replay::open).transpose()?,
+        anneal_ramp: Anneal::new(args.anneal_max, args.anneal_min, args.anneal_sweeps),
+        bell: args.bell,
+        schedule: args.schedule.as_deref().map(Schedule::load).transpose()?,
+        render_mode: if args.ascii || lacks_color_support() {
+            RenderMode::Ascii
+        } else {
+            RenderMode::default()
+        },
+        ..Default::default()
+    };
+
+    let mut guard = TerminalGuard::init()?;
+    let app_result = app.run(&mut guard.terminal);
+    drop(guard);
+    if let Some(session) = &mut app.session {
+        session.flush()?;
+    }
     app_result
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sweep(app: &mut App, sweeps: usize) {
+        for _ in 0..sweeps {
+            app.apply_event(&SessionEvent::Sweep(Vec::new())).unwrap();
+            app.detect_equilibrium();
+        }
+    }
+
+    #[test]
+    fn test_changes_restart_the_equilibrium_detection() {
+        let mut app = App {
+            recorder: Recorder::new(300),
+            ..Default::default()
+        };
+        let lattice = Lattice::with_seed(16, 1.0, 2.0, 1);
+        app.apply_event(&SessionEvent::Lattice(Box::new(lattice)))
+            .unwrap();
+        sweep(&mut app, 2 * EQUILIBRATION_WINDOW);
+        assert!(app.equilibrated);
+
+        app.apply_event(&SessionEvent::Temperature(3.0)).unwrap();
+        sweep(&mut app, 2 * EQUILIBRATION_WINDOW - 1);
+        assert!(!app.equilibrated);
+
+        sweep(&mut app, 1);
+        assert!(app.equilibrated);
+
+        app.cycle_boundary();
+        assert!(!app.equilibrated);
+        sweep(&mut app, 2 * EQUILIBRATION_WINDOW - 1);
+        assert!(!app.equilibrated);
+    }
+}
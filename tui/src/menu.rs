@@ -0,0 +1,107 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+
+/// What the menu choice is used for once selected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuKind {
+    InitialState,
+}
+
+/// Result of browsing a menu with a key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuEvent {
+    /// selection moved or key ignored, keep the menu open
+    Browsing,
+    /// Enter or a number key pressed with the index of the chosen item
+    Selected(usize),
+    /// Esc pressed
+    Cancelled,
+}
+
+/// List of choices shown in a centered popup
+#[derive(Debug, Clone, PartialEq)]
+pub struct Menu {
+    pub kind: MenuKind,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Menu {
+    /// Create a new Menu with the first item selected
+    pub fn new(kind: MenuKind, items: Vec<String>) -> Self {
+        Self {
+            kind,
+            items,
+            selected: 0,
+        }
+    }
+
+    /// Move the selection or choose an item with a pressed key
+    pub fn handle_key(&mut self, code: KeyCode) -> MenuEvent {
+        match code {
+            KeyCode::Enter => MenuEvent::Selected(self.selected),
+            KeyCode::Esc => MenuEvent::Cancelled,
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                MenuEvent::Browsing
+            }
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+                MenuEvent::Browsing
+            }
+            KeyCode::Char(c) => match c.to_digit(10) {
+                Some(digit) if (1..=self.items.len()).contains(&(digit as usize)) => {
+                    MenuEvent::Selected(digit as usize - 1)
+                }
+                _ => MenuEvent::Browsing,
+            },
+            _ => MenuEvent::Browsing,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self.kind {
+            MenuKind::InitialState => " Reset lattice to ",
+        }
+    }
+}
+
+impl Widget for &Menu {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Length(self.items.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(40)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let lines: Vec<Line> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let line = Line::from(format!(" {} {item}", index + 1));
+                if index == self.selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title(Line::from(self.title()).bold())
+                    .title_bottom(Line::from(" Choose <Enter> Cancel <Esc> ").right_aligned()),
+            )
+            .render(area, buf);
+    }
+}
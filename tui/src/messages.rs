@@ -0,0 +1,79 @@
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Maximum number of kept messages, older ones are dropped
+const CAPACITY: usize = 500;
+
+/// Severity of a message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Event reported to the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub level: Level,
+    pub text: String,
+    /// UTC wall clock time formatted as HH:MM:SS
+    pub time: String,
+}
+
+/// Recent messages, oldest first
+#[derive(Debug, Default)]
+pub struct Messages {
+    pub messages: VecDeque<Message>,
+}
+
+impl Messages {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text.into())
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.push(Level::Warning, text.into())
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text.into())
+    }
+
+    /// Latest message, if any
+    pub fn latest(&self) -> Option<&Message> {
+        self.messages.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn push(&mut self, level: Level, text: String) {
+        if self.messages.len() == CAPACITY {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(Message {
+            level,
+            text,
+            time: clock_time(),
+        });
+    }
+}
+
+// Current UTC time of day as HH:MM:SS
+fn clock_time() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
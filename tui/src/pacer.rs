@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+/// Target speeds stepped through by the speed keys, in sweeps per second
+const SPEEDS: [f64; 19] = [
+    0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 10_000.0,
+    20_000.0, 50_000.0, 100_000.0, 200_000.0, 500_000.0,
+];
+
+/// Longest wait for input before the next frame is drawn
+const MAX_WAIT: Duration = Duration::from_millis(250);
+
+/// Spread sweeps over time to reach a target number of sweeps per second
+#[derive(Debug)]
+pub struct Pacer {
+    /// sweeps per second aimed for
+    pub target: f64,
+    // Fraction of a sweep owed since the last due sweeps were taken
+    owed: f64,
+    last: Option<Instant>,
+}
+
+impl Default for Pacer {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+impl Pacer {
+    /// Create a new Pacer aiming for target sweeps per second
+    pub fn new(target: f64) -> Self {
+        Self {
+            target,
+            owed: 0.0,
+            last: None,
+        }
+    }
+
+    /// Number of whole sweeps due since the last call
+    /// Due sweeps the caller skips are dropped rather than caught up later
+    pub fn due(&mut self, now: Instant) -> usize {
+        let last = self.last.replace(now).unwrap_or(now);
+        self.owed += now.duration_since(last).as_secs_f64() * self.target;
+        let due = self.owed.floor();
+        self.owed -= due;
+        due as usize
+    }
+
+    /// Time left until the next sweep is due, capped so frames keep being drawn
+    pub fn wait(&self, now: Instant) -> Duration {
+        let Some(last) = self.last else {
+            return Duration::ZERO;
+        };
+        let until_due = Duration::from_secs_f64((1.0 - self.owed) / self.target);
+        (last + until_due)
+            .saturating_duration_since(now)
+            .min(MAX_WAIT)
+    }
+
+    /// Step the target up to the next preset speed
+    pub fn speed_up(&mut self) {
+        self.target = SPEEDS
+            .into_iter()
+            .find(|speed| *speed > self.target)
+            .unwrap_or(self.target);
+    }
+
+    /// Step the target down to the previous preset speed
+    pub fn slow_down(&mut self) {
+        self.target = SPEEDS
+            .into_iter()
+            .rev()
+            .find(|speed| *speed < self.target)
+            .unwrap_or(self.target);
+    }
+
+    pub fn is_fastest(&self) -> bool {
+        self.target >= SPEEDS[SPEEDS.len() - 1]
+    }
+
+    pub fn is_slowest(&self) -> bool {
+        self.target <= SPEEDS[0]
+    }
+}
@@ -0,0 +1,92 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+
+/// What the prompt input is used for once submitted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PromptKind {
+    SaveSnapshot,
+    LoadSnapshot,
+    Command,
+}
+
+/// Result of editing a prompt with a key
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptEvent {
+    /// input changed or key ignored, keep the prompt open
+    Editing,
+    /// Enter pressed with the final input
+    Submitted(String),
+    /// Esc pressed
+    Cancelled,
+}
+
+/// Single line text input shown in a centered popup
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
+impl Prompt {
+    /// Create a new Prompt prefilled with input
+    pub fn new(kind: PromptKind, input: impl Into<String>) -> Self {
+        Self {
+            kind,
+            input: input.into(),
+        }
+    }
+
+    /// Edit the input with a pressed key
+    pub fn handle_key(&mut self, code: KeyCode) -> PromptEvent {
+        match code {
+            KeyCode::Enter => PromptEvent::Submitted(self.input.trim().to_string()),
+            KeyCode::Esc => PromptEvent::Cancelled,
+            KeyCode::Backspace => {
+                self.input.pop();
+                PromptEvent::Editing
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                PromptEvent::Editing
+            }
+            _ => PromptEvent::Editing,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self.kind {
+            PromptKind::SaveSnapshot => " Save snapshot to ",
+            PromptKind::LoadSnapshot => " Load snapshot from ",
+            PromptKind::Command => " Command ",
+        }
+    }
+}
+
+impl Widget for &Prompt {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Length(3)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+        Paragraph::new(Line::from(vec![
+            self.input.as_str().into(),
+            "_".slow_blink(),
+        ]))
+        .block(
+            Block::bordered()
+                .title(Line::from(self.title()).bold())
+                .title_bottom(Line::from(" Confirm <Enter> Cancel <Esc> ").right_aligned()),
+        )
+        .render(area, buf);
+    }
+}
@@ -0,0 +1,47 @@
+use internal::session::{SessionEvent, SessionReader};
+use std::{fs::File, io, io::BufReader};
+
+/// Maximum number of sweeps replayed per tick
+const MAX_SPEED: usize = 64;
+
+/// Playback of a recorded session
+#[derive(Debug)]
+pub struct Replay {
+    reader: SessionReader<BufReader<File>>,
+    /// number of sweeps replayed per tick
+    pub speed: usize,
+    /// whether the end of the session was reached
+    pub finished: bool,
+}
+
+impl Replay {
+    /// Open a session file for playback at one sweep per tick
+    pub fn open(path: &str) -> io::Result<Self> {
+        let reader = SessionReader::open(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to open session {path}. Error {e}"),
+            )
+        })?;
+        Ok(Self {
+            reader,
+            speed: 1,
+            finished: false,
+        })
+    }
+
+    /// Read the next recorded event, marking the replay finished at the end
+    pub fn next_event(&mut self) -> io::Result<Option<SessionEvent>> {
+        let event = self.reader.next_event()?;
+        self.finished = event.is_none();
+        Ok(event)
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2).min(MAX_SPEED)
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2).max(1)
+    }
+}
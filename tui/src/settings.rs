@@ -0,0 +1,67 @@
+/// Parameter listed in the settings tab
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Setting {
+    Temperature,
+    Interactivity,
+    Increment,
+    Speed,
+    Size,
+    Seed,
+    Algorithm,
+    Boundary,
+    RenderMode,
+    AnnealMax,
+    AnnealMin,
+    AnnealSweeps,
+}
+
+impl Setting {
+    /// Every setting in display order
+    pub const ALL: [Setting; 12] = [
+        Setting::Temperature,
+        Setting::Interactivity,
+        Setting::Increment,
+        Setting::Speed,
+        Setting::Size,
+        Setting::Seed,
+        Setting::Algorithm,
+        Setting::Boundary,
+        Setting::RenderMode,
+        Setting::AnnealMax,
+        Setting::AnnealMin,
+        Setting::AnnealSweeps,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Setting::Temperature => "Temperature (K)",
+            Setting::Interactivity => "Interactivity J",
+            Setting::Increment => "Variable increment",
+            Setting::Speed => "Target speed (sweeps/s)",
+            Setting::Size => "Lattice size",
+            Setting::Seed => "Random seed",
+            Setting::Algorithm => "Algorithm",
+            Setting::Boundary => "Boundary condition",
+            Setting::RenderMode => "Render mode",
+            Setting::AnnealMax => "Sweep start temperature (K)",
+            Setting::AnnealMin => "Sweep end temperature (K)",
+            Setting::AnnealSweeps => "Sweep length (sweeps)",
+        }
+    }
+
+    /// Command prompt name editing the setting, None for settings cycled in place
+    pub fn command(self) -> Option<&'static str> {
+        match self {
+            Setting::Temperature => Some("temp"),
+            Setting::Interactivity => Some("j"),
+            Setting::Increment => Some("inc"),
+            Setting::Speed => Some("speed"),
+            Setting::Size => Some("size"),
+            Setting::Seed => Some("seed"),
+            Setting::Algorithm | Setting::Boundary | Setting::RenderMode => None,
+            Setting::AnnealMax => Some("anneal-max"),
+            Setting::AnnealMin => Some("anneal-min"),
+            Setting::AnnealSweeps => Some("anneal-sweeps"),
+        }
+    }
+}
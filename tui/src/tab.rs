@@ -0,0 +1,34 @@
+/// Screens of the app, switched with Tab or number keys
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Tab {
+    /// the lattice with live observables
+    #[default]
+    Simulation,
+    /// charts and histograms of the recorded observables
+    Statistics,
+    /// every parameter, listed and editable
+    Settings,
+}
+
+impl Tab {
+    /// Every tab in display order
+    pub const ALL: [Tab; 3] = [Tab::Simulation, Tab::Statistics, Tab::Settings];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Simulation => "Simulation",
+            Tab::Statistics => "Statistics",
+            Tab::Settings => "Settings",
+        }
+    }
+
+    /// Tab following this one, wrapping back to the first
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    /// Position of the tab in display order
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|tab| *tab == self).unwrap_or(0)
+    }
+}
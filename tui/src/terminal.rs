@@ -0,0 +1,41 @@
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use ratatui::DefaultTerminal;
+use std::{io, panic};
+
+/// Terminal in raw mode on the alternate screen with mouse capture
+/// Restored when dropped, including while unwinding from a panic
+pub struct TerminalGuard {
+    pub terminal: DefaultTerminal,
+}
+
+impl TerminalGuard {
+    /// Take over the terminal, restoring it before any panic message is printed
+    pub fn init() -> io::Result<Self> {
+        let terminal = ratatui::init();
+        install_panic_hook();
+        // Build the guard first so a failure below still restores the terminal
+        let guard = Self { terminal };
+        crossterm::execute!(io::stdout(), EnableMouseCapture)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+fn restore() {
+    let _ = crossterm::execute!(io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+}
+
+// Restore the terminal then run the previous hook printing the panic
+fn install_panic_hook() {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        hook(info);
+    }));
+}
@@ -0,0 +1,85 @@
+use ratatui::{style::Color, widgets::BorderType};
+use std::{fs, io};
+
+/// Colors and border used to draw the app
+/// Loaded from a TOML file where missing fields fall back to the default theme, e.g.
+/// ```toml
+/// spin_up = "red"
+/// spin_down = "#ffffff"
+/// arrow = "yellow"
+/// border = "Rounded"
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// background of spin up cells
+    pub spin_up: Color,
+    /// background of spin down cells
+    pub spin_down: Color,
+    /// arrow drawn on top of the spin cells
+    pub arrow: Color,
+    /// border style of the main block
+    pub border: Border,
+}
+
+/// Border styles available for themes
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub enum Border {
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+}
+
+impl From<Border> for BorderType {
+    fn from(border: Border) -> Self {
+        match border {
+            Border::Plain => BorderType::Plain,
+            Border::Rounded => BorderType::Rounded,
+            Border::Double => BorderType::Double,
+            Border::Thick => BorderType::Thick,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            spin_up: Color::Red,
+            spin_down: Color::White,
+            arrow: Color::Yellow,
+            border: Border::Rounded,
+        }
+    }
+}
+
+impl Theme {
+    /// Shades of gray theme, spins are told apart by brightness and arrows
+    pub fn monochrome() -> Self {
+        Self {
+            spin_up: Color::White,
+            spin_down: Color::DarkGray,
+            arrow: Color::Black,
+            border: Border::Plain,
+        }
+    }
+
+    /// Load a theme by name (`default` or `monochrome`) or from a TOML file path
+    pub fn load(name: &str) -> io::Result<Self> {
+        match name {
+            "default" => Ok(Self::default()),
+            "monochrome" => Ok(Self::monochrome()),
+            path => {
+                let content = fs::read_to_string(path).map_err(|e| {
+                    io::Error::new(e.kind(), format!("Failed to read theme {path}. Error {e}"))
+                })?;
+                toml::from_str(&content).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Failed to parse theme {path}. Error {e}"),
+                    )
+                })
+            }
+        }
+    }
+}
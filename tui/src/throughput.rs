@@ -0,0 +1,41 @@
+use std::time::{Duration, Instant};
+
+/// How often the sweep rate is refreshed
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Achieved simulation speed and terminal drawing time
+#[derive(Debug, Default)]
+pub struct Throughput {
+    /// sweeps run per second in the last finished window
+    pub sweeps_per_second: f64,
+    /// time spent drawing the last frame
+    pub frame_time: Duration,
+    window_start: Option<Instant>,
+    window_first_sweep: u64,
+}
+
+impl Throughput {
+    /// Update the sweep rate from the total number of recorded sweeps
+    pub fn update(&mut self, sweeps: u64, now: Instant) {
+        let Some(start) = self.window_start else {
+            self.restart(sweeps, now);
+            return;
+        };
+        // Recorded sweeps restart from 0 when a new lattice is loaded
+        if sweeps < self.window_first_sweep {
+            self.restart(sweeps, now);
+            return;
+        }
+        let elapsed = now.duration_since(start);
+        if elapsed >= WINDOW {
+            self.sweeps_per_second =
+                (sweeps - self.window_first_sweep) as f64 / elapsed.as_secs_f64();
+            self.restart(sweeps, now);
+        }
+    }
+
+    fn restart(&mut self, sweeps: u64, now: Instant) {
+        self.window_start = Some(now);
+        self.window_first_sweep = sweeps;
+    }
+}
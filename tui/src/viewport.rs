@@ -0,0 +1,67 @@
+use std::ops::Range;
+
+/// Maximum number of terminal cells a spin can be zoomed into
+const MAX_ZOOM: usize = 8;
+
+/// Visible region of the lattice
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// most left visible spin
+    pub x: usize,
+    /// most top visible spin
+    pub y: usize,
+    /// number of times each spin is repeated in both directions
+    pub zoom: usize,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            zoom: 1,
+        }
+    }
+}
+
+impl Viewport {
+    /// Move the viewport by dx and dy spins, keeping it inside the lattice
+    /// columns and rows are the number of spins fitting in the view
+    pub fn pan(&mut self, dx: isize, dy: isize, size: usize, columns: usize, rows: usize) {
+        self.x = self
+            .x
+            .saturating_add_signed(dx)
+            .min(size.saturating_sub(columns));
+        self.y = self
+            .y
+            .saturating_add_signed(dy)
+            .min(size.saturating_sub(rows));
+    }
+
+    /// Move the viewport so the view is centered on the lattice
+    /// columns and rows are the number of spins fitting in the view
+    pub fn center(&mut self, size: usize, columns: usize, rows: usize) {
+        self.x = size.saturating_sub(columns) / 2;
+        self.y = size.saturating_sub(rows) / 2;
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom + 1).min(MAX_ZOOM)
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1).max(1)
+    }
+
+    /// Visible x and y spin ranges of a lattice with columns x rows spins fitting in the view
+    pub fn visible(
+        &self,
+        size: usize,
+        columns: usize,
+        rows: usize,
+    ) -> (Range<usize>, Range<usize>) {
+        let x = self.x.min(size.saturating_sub(columns));
+        let y = self.y.min(size.saturating_sub(rows));
+        (x..(x + columns).min(size), y..(y + rows).min(size))
+    }
+}
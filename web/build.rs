@@ -0,0 +1,82 @@
+/// Generate the gRPC service of `web::grpc` from the methods of `proto/ising.proto`
+/// The messages are written by hand in `web::grpc::proto`, so no `protoc` is needed
+#[cfg(feature = "grpc")]
+fn main() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    println!("cargo:rerun-if-changed=proto/ising.proto");
+    let methods = [
+        (
+            "create_simulation",
+            "CreateSimulation",
+            "CreateSimulationRequest",
+            "SimulationInfo",
+        ),
+        (
+            "list_simulations",
+            "ListSimulations",
+            "ListSimulationsRequest",
+            "ListSimulationsResponse",
+        ),
+        (
+            "get_simulation",
+            "GetSimulation",
+            "SimulationRequest",
+            "SimulationInfo",
+        ),
+        (
+            "get_state",
+            "GetState",
+            "SimulationRequest",
+            "SimulationState",
+        ),
+        (
+            "update_simulation",
+            "UpdateSimulation",
+            "UpdateSimulationRequest",
+            "SimulationInfo",
+        ),
+        (
+            "delete_simulation",
+            "DeleteSimulation",
+            "SimulationRequest",
+            "DeleteSimulationResponse",
+        ),
+        (
+            "stream_frames",
+            "StreamFrames",
+            "StreamFramesRequest",
+            "Frame",
+        ),
+        (
+            "stream_observables",
+            "StreamObservables",
+            "StreamObservablesRequest",
+            "Sample",
+        ),
+    ];
+    let service = methods
+        .into_iter()
+        .fold(
+            Service::builder().name("Simulations").package("ising.v1"),
+            |service, (name, route, input, output)| {
+                let method = Method::builder()
+                    .name(name)
+                    .route_name(route)
+                    .input_type(format!("crate::grpc::proto::{input}"))
+                    .output_type(format!("crate::grpc::proto::{output}"))
+                    .codec_path("tonic_prost::ProstCodec");
+                let method = if name.starts_with("stream_") {
+                    method.server_streaming()
+                } else {
+                    method
+                };
+                service.method(method.build())
+            },
+        )
+        .build();
+    Builder::new().compile(&[service]);
+}
+
+#[cfg(not(feature = "grpc"))]
+fn main() {}
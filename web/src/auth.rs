@@ -0,0 +1,286 @@
+use crate::config::Config;
+use crate::rate_limit::API_KEY_HEADER;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::warn;
+
+/// Query parameter carrying the API key of WebSocket clients, which can't set headers
+pub const API_KEY_PARAM: &str = "api_key";
+
+/// Name of the API key a request was authenticated with, added to its extensions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyName(pub String);
+
+/// Name of the admin key a request was authenticated with, added to its extensions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Admin(pub String);
+
+/// API keys of the compute endpoints with the number of requests made with each,
+/// and the admin keys of the admin endpoints
+/// Every compute request is allowed when no API key is configured,
+/// no admin request when no admin key is
+#[derive(Clone, Default)]
+pub struct ApiKeys {
+    /// key names by key
+    names: Arc<HashMap<String, String>>,
+    /// admin key names by key
+    admins: Arc<HashMap<String, String>>,
+    /// authenticated requests by key name
+    usage: Arc<Mutex<BTreeMap<String, u64>>>,
+}
+
+impl ApiKeys {
+    /// API and admin keys of a config
+    pub fn new(config: &Config) -> Self {
+        let names = config
+            .api_keys
+            .iter()
+            .map(|(name, key)| (key.clone(), name.clone()))
+            .collect();
+        let admins = config
+            .admin_keys
+            .iter()
+            .map(|(name, key)| (key.clone(), name.clone()))
+            .collect();
+        let usage = config
+            .api_keys
+            .keys()
+            .map(|name| (name.clone(), 0))
+            .collect();
+        Self {
+            names: Arc::new(names),
+            admins: Arc::new(admins),
+            usage: Arc::new(Mutex::new(usage)),
+        }
+    }
+
+    /// Whether the endpoints are open to anyone
+    pub fn is_open(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Whether the admin endpoints are enabled
+    pub fn has_admins(&self) -> bool {
+        !self.admins.is_empty()
+    }
+
+    /// Name of an admin key, None for an unknown key
+    pub fn authenticate_admin(&self, key: &str) -> Option<String> {
+        self.admins.get(key).cloned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, u64>> {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Name of a key, counting a request made with it, None for an unknown key
+    pub fn authenticate(&self, key: &str) -> Option<String> {
+        let name = self.names.get(key)?;
+        *self.lock().entry(name.clone()).or_default() += 1;
+        Some(name.clone())
+    }
+
+    /// Authenticated requests by key name
+    pub fn usage(&self) -> BTreeMap<String, u64> {
+        self.lock().clone()
+    }
+
+    /// Usage of every key in the Prometheus text format
+    pub fn metrics(&self) -> String {
+        let mut metrics = String::from(
+            "# HELP ising_api_key_requests_total Compute requests authenticated by API key\n\
+             # TYPE ising_api_key_requests_total counter\n",
+        );
+        for (name, requests) in self.usage() {
+            let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+            let _ = writeln!(
+                metrics,
+                "ising_api_key_requests_total{{key=\"{name}\"}} {requests}"
+            );
+        }
+        metrics
+    }
+}
+
+/// API key of a request, from the API key header, a bearer token, or the query string
+fn request_key(request: &Request) -> Option<String> {
+    let headers = request.headers();
+    if let Some(key) = headers.get(API_KEY_HEADER) {
+        return key.to_str().ok().map(String::from);
+    }
+    if let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Some(token.trim().to_string());
+    }
+    request.uri().query()?.split('&').find_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        (name == API_KEY_PARAM).then(|| value.to_string())
+    })
+}
+
+/// Middleware answering 401 Unauthorized to requests without a known API key
+/// The name of the key is added to the extensions of the requests let through,
+/// admin keys passing as well with their `Admin` name
+pub async fn require_key(
+    State(keys): State<ApiKeys>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let key = request_key(&request);
+    if let Some(name) = key.as_deref().and_then(|key| keys.authenticate_admin(key)) {
+        request.extensions_mut().insert(Admin(name));
+        return next.run(request).await;
+    }
+    if keys.is_open() {
+        return next.run(request).await;
+    }
+    match key.and_then(|key| keys.authenticate(&key)) {
+        Some(name) => {
+            request.extensions_mut().insert(KeyName(name));
+            next.run(request).await
+        }
+        None => {
+            warn!(
+                "Refusing {} {} without a valid API key",
+                request.method(),
+                request.uri().path()
+            );
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                "Missing or unknown API key",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Middleware answering 401 Unauthorized to requests without a known admin key,
+/// or 404 Not Found to every request when no admin key is configured
+/// The name of the key is added to the extensions of the requests let through
+pub async fn require_admin(
+    State(keys): State<ApiKeys>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !keys.has_admins() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    match request_key(&request).and_then(|key| keys.authenticate_admin(&key)) {
+        Some(name) => {
+            request.extensions_mut().insert(Admin(name));
+            next.run(request).await
+        }
+        None => {
+            warn!(
+                "Refusing {} {} without a valid admin key",
+                request.method(),
+                request.uri().path()
+            );
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                "Missing or unknown admin key",
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::{
+        body::Body,
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_known_keys_pass_and_are_counted() {
+        let config = Config {
+            api_keys: BTreeMap::from([
+                ("lab".to_string(), "k1".to_string()),
+                ("teacher".to_string(), "k2".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let keys = ApiKeys::new(&config);
+        let app = Router::new()
+            .route("/api/jobs/sweep", post(|| async { "queued" }))
+            .layer(middleware::from_fn_with_state(keys.clone(), require_key));
+        let request = |uri: &str, key: Option<&str>| {
+            let mut request = Request::post(uri);
+            if let Some(key) = key {
+                request = request.header(API_KEY_HEADER, key);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let missing = app.clone().oneshot(request("/api/jobs/sweep", None)).await;
+        let unknown = app
+            .clone()
+            .oneshot(request("/api/jobs/sweep", Some("k3")))
+            .await;
+        let header = app
+            .clone()
+            .oneshot(request("/api/jobs/sweep", Some("k1")))
+            .await;
+        let query = app
+            .oneshot(request("/api/jobs/sweep?api_key=k1", None))
+            .await;
+
+        assert_eq!(missing.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(unknown.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(header.unwrap().status(), StatusCode::OK);
+        assert_eq!(query.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            keys.usage(),
+            BTreeMap::from([("lab".to_string(), 2), ("teacher".to_string(), 0)])
+        );
+        assert!(keys
+            .metrics()
+            .contains("ising_api_key_requests_total{key=\"lab\"} 2\n"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_need_an_admin_key() {
+        let config = Config {
+            api_keys: BTreeMap::from([("lab".to_string(), "k1".to_string())]),
+            admin_keys: BTreeMap::from([("ops".to_string(), "a1".to_string())]),
+            ..Default::default()
+        };
+        let app = |config: &Config| {
+            Router::new()
+                .route("/api/admin/simulations", get(|| async { "[]" }))
+                .layer(middleware::from_fn_with_state(
+                    ApiKeys::new(config),
+                    require_admin,
+                ))
+        };
+        let request = |key: &str| {
+            Request::get("/api/admin/simulations")
+                .header(API_KEY_HEADER, key)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let api_key = app(&config).oneshot(request("k1")).await;
+        let admin_key = app(&config).oneshot(request("a1")).await;
+        let disabled = app(&Config::default()).oneshot(request("a1")).await;
+
+        assert_eq!(api_key.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(admin_key.unwrap().status(), StatusCode::OK);
+        assert_eq!(disabled.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,142 @@
+use axum::extract::{OriginalUri, Request};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+/// Seconds browsers reuse a static file before revalidating it with its ETag
+pub const MAX_AGE: u64 = 24 * 60 * 60;
+
+/// Seconds browsers reuse a file whose name carries a hash of its content
+pub const IMMUTABLE_MAX_AGE: u64 = 365 * 24 * 60 * 60;
+
+/// Whether a file name carries a hash of its content, like `gui-1a2b3c4d5e6f7a8b_bg.wasm`
+/// built by trunk with `filehash = true`, so a new build never reuses the name
+pub fn is_hashed(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.split('-').skip(1).any(|part| {
+        let hex = part.chars().take_while(char::is_ascii_hexdigit).count();
+        hex >= 16 && matches!(part[hex..].chars().next(), None | Some('.' | '_'))
+    })
+}
+
+/// Cache-Control of the static file at path
+/// The service worker is always revalidated so browsers pick up new builds
+pub fn cache_control(path: &str) -> HeaderValue {
+    if path.ends_with("/sw.js") {
+        HeaderValue::from_static("no-cache")
+    } else if is_hashed(path) {
+        HeaderValue::from_str(&format!("public, max-age={IMMUTABLE_MAX_AGE}, immutable"))
+            .expect("Valid header value")
+    } else {
+        HeaderValue::from_str(&format!("public, max-age={MAX_AGE}")).expect("Valid header value")
+    }
+}
+
+/// Weak ETag of a file served with its length and modification time
+fn etag(headers: &HeaderMap) -> Option<HeaderValue> {
+    let length = headers.get(header::CONTENT_LENGTH)?.to_str().ok()?;
+    let modified: String = headers
+        .get(header::LAST_MODIFIED)?
+        .to_str()
+        .ok()?
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    HeaderValue::from_str(&format!("W/\"{length}-{modified}\"")).ok()
+}
+
+/// Whether an If-None-Match header lists etag, compared weakly
+fn matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let weak = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let (Ok(if_none_match), Ok(etag)) = (if_none_match.to_str(), etag.to_str()) else {
+        return false;
+    };
+    if_none_match.trim() == "*" || if_none_match.split(',').any(|tag| weak(tag) == weak(etag))
+}
+
+/// Middleware adding Cache-Control and an ETag to static files, answering
+/// 304 Not Modified when the browser already has the file
+pub async fn cache(request: Request, next: Next) -> Response {
+    // Nested services only see the path below their prefix
+    let path = match request.extensions().get::<OriginalUri>() {
+        Some(OriginalUri(uri)) => uri.path().to_string(),
+        None => request.uri().path().to_string(),
+    };
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+    let mut response = next.run(request).await;
+    let cache_control = cache_control(&path);
+    match response.status() {
+        StatusCode::OK => {}
+        StatusCode::NOT_MODIFIED => {
+            response
+                .headers_mut()
+                .insert(header::CACHE_CONTROL, cache_control);
+            return response;
+        }
+        _ => return response,
+    }
+
+    let Some(etag) = etag(response.headers()) else {
+        return response;
+    };
+    if if_none_match.is_some_and(|if_none_match| matches(&if_none_match, &etag)) {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, cache_control)],
+        )
+            .into_response();
+    }
+    let headers = response.headers_mut();
+    headers.insert(header::ETAG, etag);
+    headers.insert(header::CACHE_CONTROL, cache_control);
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get_service, Router};
+    use tower::ServiceExt;
+    use tower_http::services::ServeFile;
+
+    #[test]
+    fn test_hashed_names_are_cached_for_good() {
+        assert!(is_hashed("/gui-1a2b3c4d5e6f7a8b_bg.wasm"));
+        assert!(is_hashed("/assets/index-1a2b3c4d5e6f7a8b.css"));
+        assert!(!is_hashed("/gui_bg.wasm"));
+        assert!(!is_hashed("/assets/favicon-32x32.png"));
+        assert_eq!(cache_control("/sw.js"), "no-cache");
+        assert_eq!(
+            cache_control("/gui-1a2b3c4d5e6f7a8b.js"),
+            "public, max-age=31536000, immutable"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_matching_etag_is_not_modified() {
+        let path = std::env::temp_dir().join("r-ising-caching-test.wasm");
+        std::fs::write(&path, b"\0asm").unwrap();
+        let app = Router::new()
+            .route_service("/gui_bg.wasm", get_service(ServeFile::new(&path)))
+            .layer(middleware::from_fn(cache));
+        let request = |etag: Option<&HeaderValue>| {
+            let mut request = Request::get("/gui_bg.wasm");
+            if let Some(etag) = etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let first = app.clone().oneshot(request(None)).await.unwrap();
+        let etag = first.headers()[header::ETAG].clone();
+        let second = app.oneshot(request(Some(&etag))).await.unwrap();
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(
+            first.headers()[header::CACHE_CONTROL],
+            "public, max-age=86400"
+        );
+        assert!(etag.to_str().unwrap().starts_with("W/\"4-"));
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+    }
+}
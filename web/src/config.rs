@@ -1,4 +1,14 @@
-use std::env;
+use axum::http::HeaderValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::{env, fs};
+use tracing::{debug, info};
+
+/// HTTP methods allowed to cross-origin pages by default
+const DEFAULT_CORS_METHODS: [&str; 4] = ["GET", "POST", "PATCH", "DELETE"];
+
+/// HTTP methods cross-origin pages may be allowed to use
+const CORS_METHODS: [&str; 7] = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
 
 /// Struct Config for setup environment variables
 #[derive(PartialEq, Debug, Clone)]
@@ -17,6 +27,11 @@ pub struct Config {
     /// Default to INFO.
     /// Set to DEBUG for development. Usually set to INFO or WARN in production.
     pub log_level: tracing::Level,
+    /// Log Format
+    /// Text lines for people, or one JSON object per line with the request ID of every
+    /// request for log collectors.
+    /// Default to `Text`. Can be `Text` or `Json`.
+    pub log_format: LogFormat,
     /// Environment
     /// Type of environment.
     /// Default to `Release`. Can be `Development` or `Release`.
@@ -25,6 +40,68 @@ pub struct Config {
     /// Filse to be served produced by `trunk`
     /// Default to "./dist"
     pub dist_path: String,
+    /// Rate Limit Per Minute
+    /// Compute requests a client may send per minute, see `web::rate_limit`.
+    /// Default to 60.
+    pub rate_limit_per_minute: u32,
+    /// Rate Limit Burst
+    /// Compute requests a client may send at once before being limited to the rate above.
+    /// Default to 10.
+    pub rate_limit_burst: u32,
+    /// Max Concurrent Requests
+    /// Compute requests of a client handled at the same time, open streams included.
+    /// Default to 4.
+    pub max_concurrent_requests: u32,
+    /// CORS Allowed Origins
+    /// Origins of pages calling the API from a browser, e.g. `https://example.org`.
+    /// Comma separated in the environment variable, `*` allows any origin.
+    /// Default to none, disabling CORS.
+    pub cors_allowed_origins: Vec<String>,
+    /// CORS Allowed Methods
+    /// HTTP methods those pages may use.
+    /// Default to GET, POST, PATCH, and DELETE.
+    pub cors_allowed_methods: Vec<String>,
+    /// API Keys
+    /// Keys by name required by the compute endpoints, see `web::auth`.
+    /// Set in the config file or one `name:key` per line in the file at `API_KEYS_FILE`.
+    /// Default to none, leaving every endpoint open.
+    pub api_keys: BTreeMap<String, String>,
+    /// Admin Keys
+    /// Keys by name required by the admin endpoints, differing from the API keys.
+    /// Set in the config file or one `name:key` per line in the file at `ADMIN_KEYS_FILE`.
+    /// Default to none, disabling the admin endpoints.
+    pub admin_keys: BTreeMap<String, String>,
+    /// Store Path
+    /// Directory of the database keeping managed simulations across restarts, see `web::store`.
+    /// Created when missing.
+    /// Default to none, keeping simulations in memory only.
+    pub store_path: Option<String>,
+    /// Max Lattices Per Client
+    /// Server-side simulations a client may run or keep stored, see `web::manager`.
+    /// Default to 4.
+    pub max_lattices_per_client: u32,
+    /// Max Sites Per Client
+    /// Spins summed over the simulations of a client.
+    /// Default to 131072, two lattices of the largest size.
+    pub max_sites_per_client: u32,
+    /// Max Sweeps Per Second
+    /// Sweeps per second shared by the running simulations of a client.
+    /// Default to 40, two simulations at full speed.
+    pub max_sweeps_per_second: u32,
+    /// Simulation Idle TTL
+    /// Seconds a simulation created through the API may go without clients or requests
+    /// before it is evicted, to the store when there is one.
+    /// Default to 3600.
+    pub simulation_idle_ttl: u32,
+    /// Max Animation Sweeps
+    /// Sweeps an animated GIF of a simulation may run, see `web::render`.
+    /// Default to 2000.
+    pub max_animation_sweeps: u32,
+    /// gRPC Port
+    /// Listening port of the gRPC API, see `web::grpc`, on the service endpoint.
+    /// Only served by a server built with the `grpc` feature.
+    /// Default to none, disabling the gRPC API.
+    pub grpc_port: Option<u16>,
 }
 
 /// Environment Type
@@ -40,6 +117,13 @@ impl std::fmt::Display for Environment {
     }
 }
 
+/// Log Format Type
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
 impl Default for Config {
     /// By default running on localhost:8080 in release
     /// with log-level info and data from memory
@@ -47,85 +131,569 @@ impl Default for Config {
         let svc_endpoint: String = "localhost".to_string();
         let svc_port: String = "8080".to_string();
         let log_level = tracing::Level::INFO;
+        let log_format = LogFormat::Text;
         let environment = Environment::Release;
         let dist_path = "./dist".to_string();
+        let rate_limit_per_minute = 60;
+        let rate_limit_burst = 10;
+        let max_concurrent_requests = 4;
+        let cors_allowed_origins = Vec::new();
+        let cors_allowed_methods = DEFAULT_CORS_METHODS.map(String::from).to_vec();
+        let api_keys = BTreeMap::new();
+        let admin_keys = BTreeMap::new();
+        let store_path = None;
+        let max_lattices_per_client = 4;
+        let max_sites_per_client = 131_072;
+        let max_sweeps_per_second = 40;
+        let simulation_idle_ttl = 3600;
+        let max_animation_sweeps = 2000;
+        let grpc_port = None;
 
         Self {
             svc_endpoint,
             svc_port,
             log_level,
+            log_format,
             environment,
             dist_path,
+            rate_limit_per_minute,
+            rate_limit_burst,
+            max_concurrent_requests,
+            cors_allowed_origins,
+            cors_allowed_methods,
+            api_keys,
+            admin_keys,
+            store_path,
+            max_lattices_per_client,
+            max_sites_per_client,
+            max_sweeps_per_second,
+            simulation_idle_ttl,
+            max_animation_sweeps,
+            grpc_port,
+        }
+    }
+}
+
+/// Config File
+/// TOML file with the keys of `Config`, read from the path of the `--config` flag or
+/// the `CONFIG_PATH` environment variable. Environment variables override its values.
+#[derive(PartialEq, Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub svc_endpoint: Option<String>,
+    pub svc_port: Option<u16>,
+    pub log_level: Option<String>,
+    pub log_format: Option<String>,
+    pub environment: Option<String>,
+    pub dist_path: Option<String>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub rate_limit_burst: Option<u32>,
+    pub max_concurrent_requests: Option<u32>,
+    pub cors_allowed_origins: Option<Vec<String>>,
+    pub cors_allowed_methods: Option<Vec<String>>,
+    pub api_keys: Option<BTreeMap<String, String>>,
+    pub api_keys_file: Option<String>,
+    pub admin_keys: Option<BTreeMap<String, String>>,
+    pub admin_keys_file: Option<String>,
+    pub store_path: Option<String>,
+    pub max_lattices_per_client: Option<u32>,
+    pub max_sites_per_client: Option<u32>,
+    pub max_sweeps_per_second: Option<u32>,
+    pub simulation_idle_ttl: Option<u32>,
+    pub max_animation_sweeps: Option<u32>,
+    pub grpc_port: Option<u16>,
+}
+
+/// Config Error
+/// Every missing or invalid value found while loading the config, in a readable list.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ConfigError {
+    pub errors: Vec<String>,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Invalid config. Double check your config:")?;
+        for error in &self.errors {
+            write!(f, "\n  - {error}")?;
         }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigFile {
+    /// Parse the TOML text of a config file
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+    /// Read the config file at path
+    pub fn read(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {path}. Error {e}"))?;
+        Self::parse(&text).map_err(|e| format!("Failed to parse config file {path}. Error {e}"))
     }
 }
 
+/// Value of a checked result, or else None with its error pushed to errors
+fn check<T>(errors: &mut Vec<String>, result: Result<T, String>) -> Option<T> {
+    result.map_err(|e| errors.push(e)).ok()
+}
+
 impl Config {
-    /// Setup config from environment variables
-    pub async fn from_envar() -> Self {
+    /// Setup config from the config file at path, or else `CONFIG_PATH`, if any, and
+    /// environment variables, with the values of flags by variable name overriding both
+    pub fn load(
+        path: Option<String>,
+        flags: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let file = match path.or_else(|| env::var("CONFIG_PATH").ok()) {
+            Some(path) => {
+                info!("Loading config file {path}");
+                ConfigFile::read(&path).map_err(|e| ConfigError { errors: vec![e] })?
+            }
+            None => ConfigFile::default(),
+        };
+        Self::from_sources(file, |name| flags(name).or_else(|| env::var(name).ok()))
+    }
+    /// Setup config from a config file with the variables of envar overriding its values
+    /// Every missing or invalid value is reported at once
+    pub fn from_sources(
+        file: ConfigFile,
+        envar: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
         // Required
-        let svc_endpoint: String = env::var("SVC_ENDPOINT")
-            .expect("Failed to load SVC_ENDPOINT environment variable. Double check your config");
-        let svc_port: String = env::var("SVC_PORT")
-            .expect("failed to load SVC_PORT environment variable. Double check your config");
-        let log_level = Self::parse_log_level();
-        let environment = Self::parse_environment();
-        let dist_path = Self::parse_dist_path();
+        let svc_endpoint = check(
+            &mut errors,
+            envar("SVC_ENDPOINT").or(file.svc_endpoint).ok_or_else(|| {
+                "SVC_ENDPOINT is missing. Set the environment variable, --bind, \
+                 or svc_endpoint in the config file"
+                    .to_string()
+            }),
+        );
+        let svc_port = check(
+            &mut errors,
+            Self::parse_port(envar("SVC_PORT"), file.svc_port),
+        );
+        let log_level = check(
+            &mut errors,
+            Self::parse_log_level(envar("LOG_LEVEL").or(file.log_level)),
+        );
+        let log_format = check(
+            &mut errors,
+            Self::parse_log_format(envar("LOG_FORMAT").or(file.log_format)),
+        );
+        let environment = check(
+            &mut errors,
+            Self::parse_environment(envar("ENVIRONMENT").or(file.environment)),
+        );
+        let dist_path = check(
+            &mut errors,
+            Self::parse_dist_path(envar("DIST_PATH").or(file.dist_path)),
+        );
+        let rate_limit_per_minute = check(
+            &mut errors,
+            Self::parse_limit(
+                "RATE_LIMIT_PER_MINUTE",
+                envar("RATE_LIMIT_PER_MINUTE"),
+                file.rate_limit_per_minute,
+                60,
+            ),
+        );
+        let rate_limit_burst = check(
+            &mut errors,
+            Self::parse_limit(
+                "RATE_LIMIT_BURST",
+                envar("RATE_LIMIT_BURST"),
+                file.rate_limit_burst,
+                10,
+            ),
+        );
+        let max_concurrent_requests = check(
+            &mut errors,
+            Self::parse_limit(
+                "MAX_CONCURRENT_REQUESTS",
+                envar("MAX_CONCURRENT_REQUESTS"),
+                file.max_concurrent_requests,
+                4,
+            ),
+        );
 
-        Self {
+        let cors_allowed_origins = check(
+            &mut errors,
+            Self::parse_origins(
+                envar("CORS_ALLOWED_ORIGINS").map(|val| Self::split_list(&val)),
+                file.cors_allowed_origins,
+            ),
+        );
+        let cors_allowed_methods = check(
+            &mut errors,
+            Self::parse_methods(
+                envar("CORS_ALLOWED_METHODS").map(|val| Self::split_list(&val)),
+                file.cors_allowed_methods,
+            ),
+        );
+        let api_keys = check(
+            &mut errors,
+            Self::parse_api_keys(
+                "API_KEYS_FILE",
+                file.api_keys.unwrap_or_default(),
+                envar("API_KEYS_FILE").or(file.api_keys_file),
+            ),
+        );
+        let admin_keys = check(
+            &mut errors,
+            Self::parse_api_keys(
+                "ADMIN_KEYS_FILE",
+                file.admin_keys.unwrap_or_default(),
+                envar("ADMIN_KEYS_FILE").or(file.admin_keys_file),
+            )
+            .and_then(|admin_keys| match &api_keys {
+                Some(api_keys)
+                    if admin_keys
+                        .values()
+                        .any(|key| api_keys.values().any(|api_key| api_key == key)) =>
+                {
+                    Err("Admin keys must differ from the API keys".to_string())
+                }
+                _ => Ok(admin_keys),
+            }),
+        );
+        let store_path = check(
+            &mut errors,
+            Self::parse_store_path(envar("STORE_PATH").or(file.store_path)),
+        );
+        let max_lattices_per_client = check(
+            &mut errors,
+            Self::parse_limit(
+                "MAX_LATTICES_PER_CLIENT",
+                envar("MAX_LATTICES_PER_CLIENT"),
+                file.max_lattices_per_client,
+                4,
+            ),
+        );
+        let max_sites_per_client = check(
+            &mut errors,
+            Self::parse_limit(
+                "MAX_SITES_PER_CLIENT",
+                envar("MAX_SITES_PER_CLIENT"),
+                file.max_sites_per_client,
+                131_072,
+            ),
+        );
+        let max_sweeps_per_second = check(
+            &mut errors,
+            Self::parse_limit(
+                "MAX_SWEEPS_PER_SECOND",
+                envar("MAX_SWEEPS_PER_SECOND"),
+                file.max_sweeps_per_second,
+                40,
+            ),
+        );
+        let simulation_idle_ttl = check(
+            &mut errors,
+            Self::parse_limit(
+                "SIMULATION_IDLE_TTL",
+                envar("SIMULATION_IDLE_TTL"),
+                file.simulation_idle_ttl,
+                3600,
+            ),
+        );
+        let max_animation_sweeps = check(
+            &mut errors,
+            Self::parse_limit(
+                "MAX_ANIMATION_SWEEPS",
+                envar("MAX_ANIMATION_SWEEPS"),
+                file.max_animation_sweeps,
+                2000,
+            ),
+        );
+        let grpc_port = check(
+            &mut errors,
+            Self::parse_grpc_port(envar("GRPC_PORT"), file.grpc_port),
+        );
+
+        let (
+            Some(svc_endpoint),
+            Some(svc_port),
+            Some(log_level),
+            Some(log_format),
+            Some(environment),
+            Some(dist_path),
+            Some(rate_limit_per_minute),
+            Some(rate_limit_burst),
+            Some(max_concurrent_requests),
+            Some(cors_allowed_origins),
+            Some(cors_allowed_methods),
+            Some(api_keys),
+            Some(admin_keys),
+            Some(store_path),
+            Some(max_lattices_per_client),
+            Some(max_sites_per_client),
+            Some(max_sweeps_per_second),
+            Some(simulation_idle_ttl),
+            Some(max_animation_sweeps),
+            Some(grpc_port),
+        ) = (
+            svc_endpoint,
+            svc_port,
+            log_level,
+            log_format,
+            environment,
+            dist_path,
+            rate_limit_per_minute,
+            rate_limit_burst,
+            max_concurrent_requests,
+            cors_allowed_origins,
+            cors_allowed_methods,
+            api_keys,
+            admin_keys,
+            store_path,
+            max_lattices_per_client,
+            max_sites_per_client,
+            max_sweeps_per_second,
+            simulation_idle_ttl,
+            max_animation_sweeps,
+            grpc_port,
+        )
+        else {
+            return Err(ConfigError { errors });
+        };
+        Ok(Self {
             svc_endpoint,
             svc_port,
             log_level,
+            log_format,
             environment,
             dist_path,
+            rate_limit_per_minute,
+            rate_limit_burst,
+            max_concurrent_requests,
+            cors_allowed_origins,
+            cors_allowed_methods,
+            api_keys,
+            admin_keys,
+            store_path,
+            max_lattices_per_client,
+            max_sites_per_client,
+            max_sweeps_per_second,
+            simulation_idle_ttl,
+            max_animation_sweeps,
+            grpc_port,
+        })
+    }
+    /// Parse Service Port
+    fn parse_port(value: Option<String>, file: Option<u16>) -> Result<String, String> {
+        match (value, file) {
+            (Some(val), _) => val
+                .parse::<u16>()
+                .map(|port| port.to_string())
+                .map_err(|e| format!("SVC_PORT '{val}' is not a port number. Error {e}")),
+            (None, Some(port)) => Ok(port.to_string()),
+            (None, None) => Err(
+                "SVC_PORT is missing. Set the environment variable, --port, \
+                 or svc_port in the config file"
+                    .to_string(),
+            ),
         }
     }
-    /// Parse Log Level
-    fn parse_environment() -> Environment {
-        match env::var("ENVIRONMENT") {
-            Err(e) => {
-                println!(
-                "Failed to load ENVIRONMENT environment variable. Set default to 'Release'. Error {e}"
-            );
-                Environment::Release
+    /// Parse gRPC Port
+    fn parse_grpc_port(value: Option<String>, file: Option<u16>) -> Result<Option<u16>, String> {
+        match value {
+            Some(val) => val
+                .parse::<u16>()
+                .map(Some)
+                .map_err(|e| format!("GRPC_PORT '{val}' is not a port number. Error {e}")),
+            None => Ok(file),
+        }
+    }
+    /// Parse Environment
+    fn parse_environment(value: Option<String>) -> Result<Environment, String> {
+        match value {
+            None => {
+                debug!("Failed to load ENVIRONMENT config. Set default to 'Release'");
+                Ok(Environment::Release)
             }
-            Ok(val) => match val.as_str() {
-                "release" | "Release" | "RELEASE" => Environment::Release,
-                "development" | "Development" | "DEVELOPMENT" => Environment::Development,
-                _ => Environment::Release,
+            Some(val) => match val.as_str() {
+                "release" | "Release" | "RELEASE" => Ok(Environment::Release),
+                "development" | "Development" | "DEVELOPMENT" => Ok(Environment::Development),
+                _ => Err(format!(
+                    "ENVIRONMENT '{val}' is neither 'Release' nor 'Development'"
+                )),
             },
         }
     }
     /// Parse Log Level
-    fn parse_log_level() -> tracing::Level {
-        match env::var("LOG_LEVEL") {
-            Err(e) => {
-                println!(
-                "Failed to load LOG_LEVEL environment variable. Set default to 'info'. Error {e}"
-            );
-                tracing::Level::INFO
+    fn parse_log_level(value: Option<String>) -> Result<tracing::Level, String> {
+        match value {
+            None => {
+                debug!("Failed to load LOG_LEVEL config. Set default to 'info'");
+                Ok(tracing::Level::INFO)
             }
-            Ok(val) => match val.as_str() {
-                "ERROR" => tracing::Level::ERROR,
-                "WARN" => tracing::Level::WARN,
-                "INFO" => tracing::Level::INFO,
-                "DEBUG" => tracing::Level::DEBUG,
-                "TRACE" => tracing::Level::TRACE,
-                _ => tracing::Level::INFO,
+            Some(val) => match val.as_str() {
+                "ERROR" => Ok(tracing::Level::ERROR),
+                "WARN" => Ok(tracing::Level::WARN),
+                "INFO" => Ok(tracing::Level::INFO),
+                "DEBUG" => Ok(tracing::Level::DEBUG),
+                "TRACE" => Ok(tracing::Level::TRACE),
+                _ => Err(format!(
+                    "LOG_LEVEL '{val}' is not one of ERROR, WARN, INFO, DEBUG, or TRACE"
+                )),
+            },
+        }
+    }
+    /// Parse Log Format
+    fn parse_log_format(value: Option<String>) -> Result<LogFormat, String> {
+        match value {
+            None => {
+                debug!("Failed to load LOG_FORMAT config. Set default to 'text'");
+                Ok(LogFormat::Text)
+            }
+            Some(val) => match val.to_lowercase().as_str() {
+                "text" => Ok(LogFormat::Text),
+                "json" => Ok(LogFormat::Json),
+                _ => Err(format!("LOG_FORMAT '{val}' is neither 'text' nor 'json'")),
             },
         }
     }
     /// Parse Dist Path
-    fn parse_dist_path() -> String {
-        match env::var("DIST_PATH") {
-            Err(e) => {
-                println!(
-                "Failed to load DIST_PATH environment variable. Set default to './dist'. Error {e}"
-            );
-                "./dist".to_string()
+    fn parse_dist_path(value: Option<String>) -> Result<String, String> {
+        let dist_path = value.unwrap_or_else(|| {
+            debug!("Failed to load DIST_PATH config. Set default to './dist'");
+            "./dist".to_string()
+        });
+        if Path::new(&dist_path).is_dir() {
+            Ok(dist_path)
+        } else {
+            Err(format!(
+                "DIST_PATH '{dist_path}' is not a directory. Build the GUI with `trunk build` first"
+            ))
+        }
+    }
+    /// Parse Store Path
+    fn parse_store_path(value: Option<String>) -> Result<Option<String>, String> {
+        match value {
+            None => {
+                debug!("Failed to load STORE_PATH config. Keeping simulations in memory only");
+                Ok(None)
+            }
+            Some(val) if Path::new(&val).exists() && !Path::new(&val).is_dir() => {
+                Err(format!("STORE_PATH '{val}' is not a directory"))
+            }
+            Some(val) => Ok(Some(val)),
+        }
+    }
+    /// Split a comma separated list, dropping blank items
+    fn split_list(value: &str) -> Vec<String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect()
+    }
+    /// Parse CORS Allowed Origins
+    fn parse_origins(
+        value: Option<Vec<String>>,
+        file: Option<Vec<String>>,
+    ) -> Result<Vec<String>, String> {
+        let origins = value.or(file).unwrap_or_default();
+        for origin in &origins {
+            let is_origin = origin.starts_with("http://") || origin.starts_with("https://");
+            if origin != "*" && (!is_origin || HeaderValue::from_str(origin).is_err()) {
+                return Err(format!(
+                    "CORS_ALLOWED_ORIGINS '{origin}' is neither '*' nor an origin like \
+                     https://example.org"
+                ));
+            }
+        }
+        if origins.len() > 1 && origins.iter().any(|origin| origin == "*") {
+            return Err("CORS_ALLOWED_ORIGINS '*' can't be listed with other origins".to_string());
+        }
+        Ok(origins)
+    }
+    /// Parse CORS Allowed Methods
+    fn parse_methods(
+        value: Option<Vec<String>>,
+        file: Option<Vec<String>>,
+    ) -> Result<Vec<String>, String> {
+        let methods = value
+            .or(file)
+            .unwrap_or_else(|| DEFAULT_CORS_METHODS.map(String::from).to_vec());
+        methods
+            .into_iter()
+            .map(|method| {
+                let upper = method.to_uppercase();
+                if CORS_METHODS.contains(&upper.as_str()) {
+                    Ok(upper)
+                } else {
+                    Err(format!(
+                        "CORS_ALLOWED_METHODS '{method}' is not one of {}",
+                        CORS_METHODS.join(", ")
+                    ))
+                }
+            })
+            .collect()
+    }
+    /// Parse API Keys, adding the `name:key` lines of the keys file at path to keys,
+    /// the file named by its environment variable in errors
+    /// Blank lines and lines starting with `#` are skipped
+    fn parse_api_keys(
+        variable: &str,
+        mut keys: BTreeMap<String, String>,
+        path: Option<String>,
+    ) -> Result<BTreeMap<String, String>, String> {
+        if let Some(path) = path {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| format!("{variable} '{path}' can't be read. Error {e}"))?;
+            for (number, line) in text
+                .lines()
+                .enumerate()
+                .map(|(i, line)| (i + 1, line.trim()))
+            {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (name, key) = line.split_once(':').ok_or_else(|| {
+                    format!("{variable} '{path}' line {number} is not like `name:key`")
+                })?;
+                keys.insert(name.trim().to_string(), key.trim().to_string());
             }
-            Ok(val) => val.to_string(),
         }
+        if let Some((name, _)) = keys
+            .iter()
+            .find(|(name, key)| name.is_empty() || key.is_empty())
+        {
+            return Err(format!("API key '{name}' needs both a name and a key"));
+        }
+        let unique: BTreeSet<&String> = keys.values().collect();
+        if unique.len() < keys.len() {
+            return Err("API keys must differ from each other".to_string());
+        }
+        Ok(keys)
+    }
+    /// Parse a positive limit, the environment variable name overriding the file value
+    fn parse_limit(
+        name: &str,
+        value: Option<String>,
+        file: Option<u32>,
+        default: u32,
+    ) -> Result<u32, String> {
+        let limit = match value {
+            Some(val) => val
+                .parse::<u32>()
+                .map_err(|e| format!("{name} '{val}' is not a number. Error {e}"))?,
+            None => file.unwrap_or_else(|| {
+                debug!("Failed to load {name} config. Set default to '{default}'");
+                default
+            }),
+        };
+        if limit == 0 {
+            return Err(format!("{name} must be at least 1"));
+        }
+        Ok(limit)
     }
 }
 
@@ -148,5 +716,125 @@ mod test {
         assert_eq!(result.log_level, log_level);
         assert_eq!(result.environment, environment);
         assert_eq!(result.dist_path, dist_path);
+        assert_eq!(result.rate_limit_per_minute, 60);
+        assert_eq!(result.rate_limit_burst, 10);
+        assert_eq!(result.max_concurrent_requests, 4);
+        assert!(result.api_keys.is_empty());
+        assert!(result.admin_keys.is_empty());
+        assert_eq!(result.store_path, None);
+        assert_eq!(result.max_lattices_per_client, 4);
+        assert_eq!(result.simulation_idle_ttl, 3600);
+        assert_eq!(result.grpc_port, None);
+        assert_eq!(result.log_format, LogFormat::Text);
+        assert!(result.cors_allowed_origins.is_empty());
+        assert_eq!(
+            result.cors_allowed_methods,
+            vec!["GET", "POST", "PATCH", "DELETE"]
+        );
+    }
+
+    #[test]
+    fn test_cors_lists_split_on_commas() {
+        let envar = |name: &str| match name {
+            "SVC_ENDPOINT" => Some("localhost".to_string()),
+            "SVC_PORT" => Some("8080".to_string()),
+            "DIST_PATH" => Some(env!("CARGO_MANIFEST_DIR").to_string()),
+            "CORS_ALLOWED_ORIGINS" => {
+                Some("https://example.org, http://localhost:8888".to_string())
+            }
+            "CORS_ALLOWED_METHODS" => Some("get,post".to_string()),
+            _ => None,
+        };
+        let file = ConfigFile::parse(r#"cors_allowed_origins = ["ftp://example.org"]"#).unwrap();
+
+        let result = Config::from_sources(ConfigFile::default(), envar).unwrap();
+        let invalid = Config::from_sources(file, |name| match name {
+            "CORS_ALLOWED_ORIGINS" => None,
+            _ => envar(name),
+        });
+
+        assert_eq!(
+            result.cors_allowed_origins,
+            vec!["https://example.org", "http://localhost:8888"]
+        );
+        assert_eq!(result.cors_allowed_methods, vec!["GET", "POST"]);
+        assert!(invalid.unwrap_err().errors[0].starts_with("CORS_ALLOWED_ORIGINS 'ftp:"));
+    }
+
+    #[test]
+    fn test_environment_overrides_the_config_file() {
+        let file = ConfigFile::parse(
+            r#"
+            svc_endpoint = "0.0.0.0"
+            svc_port = 3000
+            log_level = "DEBUG"
+            rate_limit_burst = 20
+            grpc_port = 50051
+            "#,
+        )
+        .unwrap();
+        let envar = |name: &str| match name {
+            "SVC_PORT" => Some("8080".to_string()),
+            "RATE_LIMIT_PER_MINUTE" => Some("120".to_string()),
+            "LOG_FORMAT" => Some("JSON".to_string()),
+            "DIST_PATH" => Some(env!("CARGO_MANIFEST_DIR").to_string()),
+            _ => None,
+        };
+
+        let result = Config::from_sources(file, envar).unwrap();
+
+        assert_eq!(result.svc_endpoint, "0.0.0.0");
+        assert_eq!(result.svc_port, "8080");
+        assert_eq!(result.log_level, tracing::Level::DEBUG);
+        assert_eq!(result.environment, Environment::Release);
+        assert_eq!(result.rate_limit_per_minute, 120);
+        assert_eq!(result.rate_limit_burst, 20);
+        assert_eq!(result.max_concurrent_requests, 4);
+        assert_eq!(result.grpc_port, Some(50051));
+        assert_eq!(result.log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn test_config_file_refuses_unknown_keys() {
+        assert!(ConfigFile::parse("svc_prot = 3000").is_err());
+    }
+
+    #[test]
+    fn test_every_invalid_value_is_reported() {
+        let envar = |name: &str| match name {
+            "SVC_PORT" => Some("http".to_string()),
+            "LOG_LEVEL" => Some("LOUD".to_string()),
+            "DIST_PATH" => Some("./missing-dist".to_string()),
+            "RATE_LIMIT_BURST" => Some("0".to_string()),
+            _ => None,
+        };
+
+        let result = Config::from_sources(ConfigFile::default(), envar).unwrap_err();
+
+        assert_eq!(result.errors.len(), 5);
+        assert!(result.errors[0].starts_with("SVC_ENDPOINT is missing"));
+        assert!(result.errors[1].starts_with("SVC_PORT 'http'"));
+        assert!(result.to_string().contains("\n  - LOG_LEVEL 'LOUD'"));
+    }
+
+    #[test]
+    fn test_api_keys_file_adds_to_the_config_file_keys() {
+        let path = std::env::temp_dir().join("r-ising-api-keys-test");
+        fs::write(&path, "# Course keys\nlab: k2\n\nworkshop:k3\n").unwrap();
+        let file = ConfigFile::parse(r#"api_keys = { teacher = "k1" }"#).unwrap();
+        let envar = |name: &str| match name {
+            "SVC_ENDPOINT" => Some("localhost".to_string()),
+            "SVC_PORT" => Some("8080".to_string()),
+            "DIST_PATH" => Some(env!("CARGO_MANIFEST_DIR").to_string()),
+            "API_KEYS_FILE" => Some(path.display().to_string()),
+            _ => None,
+        };
+
+        let result = Config::from_sources(file, envar).unwrap();
+
+        assert_eq!(result.api_keys.len(), 3);
+        assert_eq!(result.api_keys["teacher"], "k1");
+        assert_eq!(result.api_keys["lab"], "k2");
+        assert_eq!(result.api_keys["workshop"], "k3");
     }
 }
@@ -0,0 +1,113 @@
+use crate::config::Config;
+use crate::rate_limit::API_KEY_HEADER;
+use axum::http::{header, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// CORS layer letting the pages of the configured origins call the API from a browser,
+/// None when no origin is allowed
+pub fn layer(config: &Config) -> Option<CorsLayer> {
+    if config.cors_allowed_origins.is_empty() {
+        return None;
+    }
+    let origins = if config
+        .cors_allowed_origins
+        .iter()
+        .any(|origin| origin == "*")
+    {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .cors_allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    let methods: Vec<Method> = config
+        .cors_allowed_methods
+        .iter()
+        .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+        .collect();
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                header::HeaderName::from_static(API_KEY_HEADER),
+            ])
+            // Lets pages back off when rate limited
+            .expose_headers([header::RETRY_AFTER]),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_allowed_origin_gets_cors_headers() {
+        let config = Config {
+            cors_allowed_origins: vec!["https://example.org".to_string()],
+            ..Default::default()
+        };
+        let app = Router::new()
+            .route("/api", get(|| async { "ok" }))
+            .layer(layer(&config).unwrap());
+        let request = |origin| {
+            Request::get("/api")
+                .header(header::ORIGIN, origin)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let allowed = app
+            .clone()
+            .oneshot(request("https://example.org"))
+            .await
+            .unwrap();
+        let other = app.oneshot(request("https://elsewhere.org")).await.unwrap();
+
+        assert_eq!(
+            allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://example.org"
+        );
+        assert!(!other
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert!(layer(&Config::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_preflight_allows_the_auth_headers() {
+        let config = Config {
+            cors_allowed_origins: vec!["https://example.org".to_string()],
+            ..Default::default()
+        };
+        let app = Router::new()
+            .route("/api", get(|| async { "ok" }))
+            .layer(layer(&config).unwrap());
+        let preflight = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api")
+            .header(header::ORIGIN, "https://example.org")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .header(
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                format!("authorization,{API_KEY_HEADER}"),
+            )
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(preflight).await.unwrap();
+
+        let allowed = response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap();
+        assert!(allowed.contains("authorization"), "{allowed}");
+        assert!(allowed.contains(API_KEY_HEADER), "{allowed}");
+    }
+}
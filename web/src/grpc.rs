@@ -0,0 +1,675 @@
+use crate::auth::ApiKeys;
+use crate::manager::{Denial, Refusal, SimulationManager};
+use crate::rate_limit::{Permit, RateLimiter, API_KEY_HEADER};
+use crate::stream::{self, pack_bits, ParamsUpdate, Sample};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use futures_util::stream::{once, unfold, Stream, StreamExt};
+use internal::algorithm::Algorithm;
+use proto::simulations_server::{Simulations, SimulationsServer};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status};
+use tracing::warn;
+
+/// Messages of `proto/ising.proto` with the generated service and client
+pub mod proto {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Algorithm {
+        Metropolis = 0,
+        HeatBath = 1,
+        Wolff = 2,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SimulationParams {
+        #[prost(uint32, optional, tag = "1")]
+        pub size: Option<u32>,
+        #[prost(double, optional, tag = "2")]
+        pub temperature: Option<f64>,
+        #[prost(double, optional, tag = "3")]
+        pub interactivity: Option<f64>,
+        #[prost(double, optional, tag = "4")]
+        pub field: Option<f64>,
+        #[prost(enumeration = "Algorithm", optional, tag = "5")]
+        pub algorithm: Option<i32>,
+        #[prost(uint64, optional, tag = "6")]
+        pub seed: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CreateSimulationRequest {
+        #[prost(message, optional, tag = "1")]
+        pub params: Option<SimulationParams>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListSimulationsRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListSimulationsResponse {
+        #[prost(message, repeated, tag = "1")]
+        pub simulations: Vec<SimulationInfo>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SimulationRequest {
+        #[prost(string, tag = "1")]
+        pub id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct UpdateSimulationRequest {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(double, optional, tag = "2")]
+        pub temperature: Option<f64>,
+        #[prost(double, optional, tag = "3")]
+        pub interactivity: Option<f64>,
+        #[prost(double, optional, tag = "4")]
+        pub field: Option<f64>,
+        #[prost(enumeration = "Algorithm", optional, tag = "5")]
+        pub algorithm: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct DeleteSimulationResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SimulationInfo {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(message, optional, tag = "2")]
+        pub params: Option<SimulationParams>,
+        #[prost(uint64, tag = "3")]
+        pub sweep: u64,
+        #[prost(bool, tag = "4")]
+        pub managed: bool,
+        #[prost(uint32, tag = "5")]
+        pub clients: u32,
+    }
+
+    #[derive(Clone, Copy, PartialEq, prost::Message)]
+    pub struct Observables {
+        #[prost(double, tag = "1")]
+        pub magnetization: f64,
+        #[prost(double, tag = "2")]
+        pub energy: f64,
+        #[prost(double, tag = "3")]
+        pub acceptance: f64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SimulationState {
+        #[prost(message, optional, tag = "1")]
+        pub info: Option<SimulationInfo>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub spins: Vec<u8>,
+        #[prost(message, optional, tag = "3")]
+        pub observables: Option<Observables>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StreamFramesRequest {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(message, optional, tag = "2")]
+        pub params: Option<SimulationParams>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Frame {
+        #[prost(oneof = "frame::Kind", tags = "1, 2")]
+        pub kind: Option<frame::Kind>,
+    }
+
+    pub mod frame {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Kind {
+            #[prost(message, tag = "1")]
+            Full(super::FullFrame),
+            #[prost(message, tag = "2")]
+            Diff(super::DiffFrame),
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FullFrame {
+        #[prost(uint64, tag = "1")]
+        pub sweep: u64,
+        #[prost(uint32, tag = "2")]
+        pub size: u32,
+        #[prost(bytes = "vec", tag = "3")]
+        pub spins: Vec<u8>,
+        #[prost(message, optional, tag = "4")]
+        pub observables: Option<Observables>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct DiffFrame {
+        #[prost(uint64, tag = "1")]
+        pub sweep: u64,
+        #[prost(uint32, repeated, tag = "2")]
+        pub flips: Vec<u32>,
+        #[prost(message, optional, tag = "3")]
+        pub observables: Option<Observables>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StreamObservablesRequest {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(uint64, optional, tag = "2")]
+        pub every: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sample {
+        #[prost(uint64, tag = "1")]
+        pub sweep: u64,
+        #[prost(message, optional, tag = "2")]
+        pub observables: Option<Observables>,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/ising.v1.Simulations.rs"));
+}
+
+impl From<Algorithm> for proto::Algorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Metropolis => proto::Algorithm::Metropolis,
+            Algorithm::HeatBath => proto::Algorithm::HeatBath,
+            Algorithm::Wolff => proto::Algorithm::Wolff,
+        }
+    }
+}
+
+impl From<proto::Algorithm> for Algorithm {
+    fn from(algorithm: proto::Algorithm) -> Self {
+        match algorithm {
+            proto::Algorithm::Metropolis => Algorithm::Metropolis,
+            proto::Algorithm::HeatBath => Algorithm::HeatBath,
+            proto::Algorithm::Wolff => Algorithm::Wolff,
+        }
+    }
+}
+
+/// Algorithm of an enumeration value, InvalidArgument for an unknown one
+fn algorithm(value: i32) -> Result<Algorithm, Status> {
+    proto::Algorithm::try_from(value)
+        .map(Algorithm::from)
+        .map_err(|_| Status::invalid_argument(format!("Unknown algorithm {value}")))
+}
+
+impl From<stream::SimulationParams> for proto::SimulationParams {
+    fn from(params: stream::SimulationParams) -> Self {
+        Self {
+            size: Some(params.size as u32),
+            temperature: Some(params.temperature),
+            interactivity: Some(params.interactivity),
+            field: Some(params.field),
+            algorithm: Some(proto::Algorithm::from(params.algorithm).into()),
+            seed: params.seed,
+        }
+    }
+}
+
+impl TryFrom<proto::SimulationParams> for stream::SimulationParams {
+    type Error = Status;
+
+    /// Parameters with the defaults of the REST API in place of the absent ones
+    fn try_from(params: proto::SimulationParams) -> Result<Self, Status> {
+        let defaults = Self::default();
+        Ok(Self {
+            size: params.size.map_or(defaults.size, |size| size as usize),
+            temperature: params.temperature.unwrap_or(defaults.temperature),
+            interactivity: params.interactivity.unwrap_or(defaults.interactivity),
+            field: params.field.unwrap_or(defaults.field),
+            algorithm: params
+                .algorithm
+                .map(algorithm)
+                .transpose()?
+                .unwrap_or(defaults.algorithm),
+            seed: params.seed,
+        })
+    }
+}
+
+/// Parameters of a request, the defaults when absent
+fn params(params: Option<proto::SimulationParams>) -> Result<stream::SimulationParams, Status> {
+    params.map_or(Ok(Default::default()), TryFrom::try_from)
+}
+
+impl From<stream::SimulationInfo> for proto::SimulationInfo {
+    fn from(info: stream::SimulationInfo) -> Self {
+        Self {
+            id: info.id,
+            params: Some(info.params.into()),
+            sweep: info.sweep,
+            managed: info.managed,
+            clients: info.clients as u32,
+        }
+    }
+}
+
+impl From<stream::Observables> for proto::Observables {
+    fn from(observables: stream::Observables) -> Self {
+        Self {
+            magnetization: observables.magnetization,
+            energy: observables.energy,
+            acceptance: observables.acceptance,
+        }
+    }
+}
+
+impl From<&stream::Frame> for proto::Frame {
+    fn from(frame: &stream::Frame) -> Self {
+        let kind = match frame {
+            stream::Frame::Full {
+                sweep,
+                size,
+                spins,
+                observables,
+            } => proto::frame::Kind::Full(proto::FullFrame {
+                sweep: *sweep,
+                size: *size as u32,
+                spins: pack_bits(spins.chars().map(|spin| spin == '+')),
+                observables: Some((*observables).into()),
+            }),
+            stream::Frame::Diff {
+                sweep,
+                flips,
+                observables,
+            } => proto::frame::Kind::Diff(proto::DiffFrame {
+                sweep: *sweep,
+                flips: flips.iter().map(|&index| index as u32).collect(),
+                observables: Some((*observables).into()),
+            }),
+        };
+        Self { kind: Some(kind) }
+    }
+}
+
+/// Status of a refused simulation, like the REST API's 503 and 429
+fn refused(refusal: Refusal) -> Status {
+    match refusal {
+        Refusal::Full => Status::unavailable(refusal.to_string()),
+        refusal => Status::resource_exhausted(refusal.to_string()),
+    }
+}
+
+fn not_found(id: &str) -> Status {
+    Status::not_found(format!("No simulation {id}"))
+}
+
+fn denied(id: &str, denial: Denial) -> Status {
+    match denial {
+        Denial::Missing => not_found(id),
+        denial => Status::permission_denied(denial.to_string()),
+    }
+}
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// gRPC service of the server-side simulations, sharing the manager, API keys, and rate limits
+/// of the REST API
+#[derive(Clone)]
+pub struct SimulationService {
+    manager: SimulationManager,
+    api_keys: ApiKeys,
+    limiter: RateLimiter,
+}
+
+impl SimulationService {
+    pub fn new(manager: SimulationManager, api_keys: ApiKeys, limiter: RateLimiter) -> Self {
+        Self {
+            manager,
+            api_keys,
+            limiter,
+        }
+    }
+
+    /// Owner of the simulations a request starts, like `web::manager::Owner`
+    /// Unauthenticated without a known API key once keys are configured
+    fn owner<T>(&self, request: &Request<T>) -> Result<String, Status> {
+        if self.api_keys.is_open() {
+            return Ok(match request.remote_addr() {
+                Some(address) => format!("ip:{}", address.ip()),
+                None => "unknown".to_string(),
+            });
+        }
+        let metadata = request.metadata();
+        let key = match metadata.get(API_KEY_HEADER) {
+            Some(key) => key.to_str().ok(),
+            None => metadata
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(str::trim),
+        };
+        match key.and_then(|key| self.api_keys.authenticate(key)) {
+            Some(name) => Ok(format!("key:{name}")),
+            None => {
+                warn!("Refusing a gRPC call without a valid API key");
+                Err(Status::unauthenticated("Missing or unknown API key"))
+            }
+        }
+    }
+
+    /// Owner of a compute request within its rate limit, with the permit to hold until
+    /// the call and its stream end, ResourceExhausted past it
+    /// Owners key the limits like the HTTP API, sharing the buckets of both
+    fn limit<T>(&self, request: &Request<T>) -> Result<(String, Permit), Status> {
+        let owner = self.owner(request)?;
+        match self.limiter.acquire(&owner, Instant::now()) {
+            Ok(permit) => Ok((owner, permit)),
+            Err(retry_after) => {
+                warn!("Rate limiting {owner} for {}ms", retry_after.as_millis());
+                Err(Status::resource_exhausted(format!(
+                    "Rate limited, retry in {}ms",
+                    retry_after.as_millis()
+                )))
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Simulations for SimulationService {
+    type StreamFramesStream = ResponseStream<proto::Frame>;
+    type StreamObservablesStream = ResponseStream<proto::Sample>;
+
+    async fn create_simulation(
+        &self,
+        request: Request<proto::CreateSimulationRequest>,
+    ) -> Result<Response<proto::SimulationInfo>, Status> {
+        let (owner, _permit) = self.limit(&request)?;
+        let params = params(request.into_inner().params)?;
+        let info = self.manager.create(&owner, params).map_err(refused)?;
+        Ok(Response::new(info.into()))
+    }
+
+    async fn list_simulations(
+        &self,
+        _request: Request<proto::ListSimulationsRequest>,
+    ) -> Result<Response<proto::ListSimulationsResponse>, Status> {
+        let simulations = self.manager.list().into_iter().map(From::from).collect();
+        Ok(Response::new(proto::ListSimulationsResponse {
+            simulations,
+        }))
+    }
+
+    async fn get_simulation(
+        &self,
+        request: Request<proto::SimulationRequest>,
+    ) -> Result<Response<proto::SimulationInfo>, Status> {
+        let id = request.into_inner().id;
+        match self.manager.get(&id) {
+            Some(info) => Ok(Response::new(info.into())),
+            None => Err(not_found(&id)),
+        }
+    }
+
+    async fn get_state(
+        &self,
+        request: Request<proto::SimulationRequest>,
+    ) -> Result<Response<proto::SimulationState>, Status> {
+        let id = request.into_inner().id;
+        let Some(state) = self.manager.state(&id) else {
+            return Err(not_found(&id));
+        };
+        let spins = BASE64_STANDARD
+            .decode(&state.spins)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(proto::SimulationState {
+            info: Some(state.info.into()),
+            spins,
+            observables: Some(state.observables.into()),
+        }))
+    }
+
+    async fn update_simulation(
+        &self,
+        request: Request<proto::UpdateSimulationRequest>,
+    ) -> Result<Response<proto::SimulationInfo>, Status> {
+        let (owner, _permit) = self.limit(&request)?;
+        let request = request.into_inner();
+        self.manager
+            .authorize(&request.id, &owner, false)
+            .map_err(|denial| denied(&request.id, denial))?;
+        let update = ParamsUpdate {
+            temperature: request.temperature,
+            interactivity: request.interactivity,
+            field: request.field,
+            algorithm: request.algorithm.map(algorithm).transpose()?,
+        };
+        match self.manager.update(&request.id, update) {
+            Some(info) => Ok(Response::new(info.into())),
+            None => Err(not_found(&request.id)),
+        }
+    }
+
+    async fn delete_simulation(
+        &self,
+        request: Request<proto::SimulationRequest>,
+    ) -> Result<Response<proto::DeleteSimulationResponse>, Status> {
+        let owner = self.owner(&request)?;
+        let id = request.into_inner().id;
+        self.manager
+            .authorize(&id, &owner, false)
+            .map_err(|denial| denied(&id, denial))?;
+        if self.manager.remove(&id) {
+            Ok(Response::new(proto::DeleteSimulationResponse {}))
+        } else {
+            Err(not_found(&id))
+        }
+    }
+
+    async fn stream_frames(
+        &self,
+        request: Request<proto::StreamFramesRequest>,
+    ) -> Result<Response<Self::StreamFramesStream>, Status> {
+        let (owner, permit) = self.limit(&request)?;
+        let request = request.into_inner();
+        let params = params(request.params)?;
+        let id = request.id;
+        let (full, frames) = self
+            .manager
+            .subscribe(&id, &owner, params)
+            .map_err(refused)?;
+        let manager = self.manager.clone();
+        // The permit is held in the state of the stream, counting it in flight until it ends
+        let diffs = unfold((frames, permit), move |(mut frames, permit)| {
+            let (manager, id, owner) = (manager.clone(), id.clone(), owner.clone());
+            async move {
+                match frames.recv().await {
+                    Ok(frame) => Some((Ok(frame.as_ref().into()), (frames, permit))),
+                    // A client too slow for the diffs starts over from a full frame
+                    Err(RecvError::Lagged(_)) => {
+                        let (full, frames) = manager.subscribe(&id, &owner, params).ok()?;
+                        Some((Ok((&full).into()), (frames, permit)))
+                    }
+                    Err(RecvError::Closed) => None,
+                }
+            }
+        });
+        let frames = once(async move { Ok((&full).into()) }).chain(diffs);
+        Ok(Response::new(Box::pin(frames)))
+    }
+
+    async fn stream_observables(
+        &self,
+        request: Request<proto::StreamObservablesRequest>,
+    ) -> Result<Response<Self::StreamObservablesStream>, Status> {
+        let request = request.into_inner();
+        let every = request.every.unwrap_or(1);
+        let Some(frames) = self.manager.observe(&request.id) else {
+            return Err(not_found(&request.id));
+        };
+        let samples = unfold(frames, move |mut frames| async move {
+            loop {
+                match frames.recv().await {
+                    Ok(frame) => {
+                        if let Some(sample) = Sample::of(&frame, every) {
+                            let sample = proto::Sample {
+                                sweep: sample.sweep,
+                                observables: Some(sample.observables.into()),
+                            };
+                            return Some((Ok(sample), frames));
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Observables stream lagged, skipping {skipped} frames");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(samples)))
+    }
+}
+
+/// Serve the gRPC API on listener until shutdown completes
+pub async fn serve(
+    listener: TcpListener,
+    service: SimulationService,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(SimulationsServer::new(service))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use proto::simulations_client::SimulationsClient;
+    use std::collections::BTreeMap;
+
+    fn with_key<T>(mut request: Request<T>, key: &str) -> Request<T> {
+        request
+            .metadata_mut()
+            .insert(API_KEY_HEADER, key.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_simulations_are_created_and_streamed() {
+        let config = Config {
+            api_keys: BTreeMap::from([("lab".to_string(), "k1".to_string())]),
+            ..Default::default()
+        };
+        let service = SimulationService::new(
+            SimulationManager::default(),
+            ApiKeys::new(&config),
+            RateLimiter::new(&config),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, service, std::future::pending()));
+        let mut client = SimulationsClient::connect(format!("http://{address}"))
+            .await
+            .unwrap();
+        let create = || {
+            Request::new(proto::CreateSimulationRequest {
+                params: Some(proto::SimulationParams {
+                    size: Some(8),
+                    algorithm: Some(proto::Algorithm::Wolff.into()),
+                    ..Default::default()
+                }),
+            })
+        };
+
+        let refused = client.create_simulation(create()).await.unwrap_err();
+        let info = client
+            .create_simulation(with_key(create(), "k1"))
+            .await
+            .unwrap()
+            .into_inner();
+        let params = info.params.clone().unwrap();
+        let mut frames = client
+            .stream_frames(with_key(
+                Request::new(proto::StreamFramesRequest {
+                    id: info.id.clone(),
+                    params: None,
+                }),
+                "k1",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let full = frames.next().await.unwrap().unwrap();
+        let diff = frames.next().await.unwrap().unwrap();
+        let missing = client
+            .get_state(Request::new(proto::SimulationRequest {
+                id: "missing".to_string(),
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(refused.code(), tonic::Code::Unauthenticated);
+        assert_eq!(params.size, Some(8));
+        assert_eq!(params.temperature, Some(2269.0));
+        assert_eq!(params.algorithm, Some(proto::Algorithm::Wolff.into()));
+        let Some(proto::frame::Kind::Full(full)) = full.kind else {
+            panic!("Expected a full frame first, got {full:?}");
+        };
+        assert_eq!((full.size, full.spins.len()), (8, 8));
+        assert!(matches!(diff.kind, Some(proto::frame::Kind::Diff(_))));
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_streams_are_in_flight_until_they_end() {
+        let config = Config {
+            max_concurrent_requests: 1,
+            ..Default::default()
+        };
+        let service = SimulationService::new(
+            SimulationManager::default(),
+            ApiKeys::default(),
+            RateLimiter::new(&config),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, service, std::future::pending()));
+        let mut client = SimulationsClient::connect(format!("http://{address}"))
+            .await
+            .unwrap();
+        let update = |id: &str| {
+            Request::new(proto::UpdateSimulationRequest {
+                id: id.to_string(),
+                temperature: Some(1000.0),
+                ..Default::default()
+            })
+        };
+
+        let info = client
+            .create_simulation(Request::new(proto::CreateSimulationRequest {
+                params: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let updated = client.update_simulation(update(&info.id)).await;
+        let mut frames = client
+            .stream_frames(Request::new(proto::StreamFramesRequest {
+                id: info.id.clone(),
+                params: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        frames.next().await.unwrap().unwrap();
+        let limited = client.update_simulation(update(&info.id)).await;
+
+        assert!(updated.is_ok());
+        assert_eq!(limited.unwrap_err().code(), tonic::Code::ResourceExhausted);
+    }
+}
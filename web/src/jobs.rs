@@ -0,0 +1,406 @@
+use crate::results::{Measurement, Results};
+use crate::stream::MAX_SIZE;
+use internal::{
+    algorithm::Algorithm,
+    scan::{self, ScanPoint},
+    Lattice, KB,
+};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// Number of scan points measured at once, across every job
+pub const WORKERS: usize = 4;
+
+/// Number of jobs kept, finished ones are dropped oldest first to make room
+pub const MAX_JOBS: usize = 64;
+
+/// Largest number of temperatures of a sweep
+pub const MAX_STEPS: usize = 200;
+
+/// Largest number of lattice sizes of a sweep
+pub const MAX_SIZES: usize = 8;
+
+/// Largest number of sweeps run at every scan point, equilibration included
+pub const MAX_SWEEPS: usize = 100_000;
+
+/// Interaction strength of the scanned lattices, any value works in reduced units
+pub(crate) const INTERACTIVITY: f64 = 1000.0 * KB;
+
+/// Temperature sweep of a job, in reduced units k_B T / |J| like the GUI scan
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SweepRequest {
+    /// lattice sides, every one scanned over the whole temperature range
+    pub sizes: Vec<usize>,
+    /// first reduced temperature
+    pub start: f64,
+    /// last reduced temperature
+    pub end: f64,
+    /// number of temperatures between start and end, both included
+    pub steps: usize,
+    /// sweeps run before measuring at every temperature
+    pub equilibration: usize,
+    /// sweeps averaged at every temperature
+    pub sweeps: usize,
+    /// Monte Carlo update run every sweep
+    pub algorithm: Algorithm,
+    /// seed of the random spins and updates of every point, random when absent
+    pub seed: Option<u64>,
+}
+
+impl Default for SweepRequest {
+    fn default() -> Self {
+        Self {
+            sizes: vec![16],
+            start: 1.5,
+            end: 3.5,
+            steps: 11,
+            equilibration: 200,
+            sweeps: 500,
+            algorithm: Algorithm::default(),
+            seed: None,
+        }
+    }
+}
+
+impl SweepRequest {
+    /// Reason the sweep can't run, None when it can
+    pub fn invalid(&self) -> Option<String> {
+        if self.sizes.is_empty() || self.sizes.len() > MAX_SIZES {
+            Some(format!("Expected 1 to {MAX_SIZES} sizes"))
+        } else if let Some(size) = self
+            .sizes
+            .iter()
+            .find(|size| !(5..=MAX_SIZE).contains(*size))
+        {
+            Some(format!("Size {size} is outside of 5 to {MAX_SIZE}"))
+        } else if !(1..=MAX_STEPS).contains(&self.steps) {
+            Some(format!("Expected 1 to {MAX_STEPS} steps"))
+        } else if !self.start.is_finite() || !self.end.is_finite() {
+            Some("Expected finite temperatures".to_string())
+        } else if self.start.min(self.end) <= 0.0 {
+            Some("Expected positive temperatures".to_string())
+        } else if self.sweeps == 0 || self.equilibration + self.sweeps > MAX_SWEEPS {
+            Some(format!(
+                "Expected 1 to {MAX_SWEEPS} sweeps, equilibration included"
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Size and reduced temperature of every point, size by size
+    fn points(&self) -> Vec<(usize, f64)> {
+        let temperatures = scan::temperatures(self.start, self.end, self.steps);
+        self.sizes
+            .iter()
+            .flat_map(|size| temperatures.iter().map(move |t| (*size, *t)))
+            .collect()
+    }
+}
+
+/// Stage of a job
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// waiting for a worker
+    Queued,
+    /// measuring its points
+    Running,
+    /// every point measured, results ready
+    Done,
+    /// a point could not be measured
+    Failed,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed)
+    }
+}
+
+/// Job as described by the API
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub status: JobStatus,
+    /// number of points measured so far
+    pub completed: usize,
+    /// number of points of the sweep
+    pub total: usize,
+    /// sweep run by the job, its seed included
+    pub request: SweepRequest,
+    /// why the job failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Temperature sweep queued or run by the worker pool
+struct Job {
+    request: SweepRequest,
+    status: JobStatus,
+    /// measured points by index in SweepRequest::points
+    points: Vec<Option<(usize, ScanPoint)>>,
+    error: Option<String>,
+    /// creation order, to drop the oldest finished jobs first
+    order: u64,
+}
+
+impl Job {
+    fn info(&self, id: &str) -> JobInfo {
+        JobInfo {
+            id: id.to_string(),
+            status: self.status,
+            completed: self.points.iter().flatten().count(),
+            total: self.points.len(),
+            request: self.request.clone(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// Jobs by id
+#[derive(Default)]
+struct Registry {
+    jobs: HashMap<String, Arc<Mutex<Job>>>,
+    created: u64,
+}
+
+/// Temperature sweep jobs sharing a pool of WORKERS workers, served first come first served
+/// The points of the finished jobs are recorded to the results
+#[derive(Clone)]
+pub struct Jobs {
+    registry: Arc<Mutex<Registry>>,
+    workers: Arc<Semaphore>,
+    results: Results,
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self::new(Results::default())
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Jobs {
+    /// Jobs recording their points to results
+    pub fn new(results: Results) -> Self {
+        Self {
+            registry: Arc::default(),
+            workers: Arc::new(Semaphore::new(WORKERS)),
+            results,
+        }
+    }
+
+    /// Queue a valid sweep under a new random id
+    /// None when MAX_JOBS are queued or running
+    pub fn submit(&self, mut request: SweepRequest) -> Option<JobInfo> {
+        let mut registry = lock(&self.registry);
+        if registry.jobs.len() >= MAX_JOBS {
+            let oldest = registry
+                .jobs
+                .iter()
+                .filter(|(_, job)| lock(job).status.is_finished())
+                .min_by_key(|(_, job)| lock(job).order)
+                .map(|(id, _)| id.clone())?;
+            registry.jobs.remove(&oldest);
+        }
+        let id = loop {
+            let id = format!("{:016x}", rand::random::<u64>());
+            if !registry.jobs.contains_key(&id) {
+                break id;
+            }
+        };
+        request.seed = Some(request.seed.unwrap_or_else(rand::random));
+        let job = Arc::new(Mutex::new(Job {
+            points: vec![None; request.points().len()],
+            request,
+            status: JobStatus::Queued,
+            error: None,
+            order: registry.created,
+        }));
+        registry.created += 1;
+        registry.jobs.insert(id.clone(), job.clone());
+        let info = lock(&job).info(&id);
+        info!("Queueing sweep job {id} with {:?}", info.request);
+        tokio::spawn(run(id, job, self.workers.clone(), self.results.clone()));
+        Some(info)
+    }
+
+    /// Status and progress of the job id, None when there is none
+    pub fn get(&self, id: &str) -> Option<JobInfo> {
+        let job = lock(&self.registry).jobs.get(id)?.clone();
+        let info = lock(&job).info(id);
+        Some(info)
+    }
+
+    /// Results of the job id as CSV, Err with its status while they are not ready
+    /// None when there is no such job
+    pub fn results(&self, id: &str) -> Option<Result<Vec<u8>, JobStatus>> {
+        let job = lock(&self.registry).jobs.get(id)?.clone();
+        let job = lock(&job);
+        if job.status != JobStatus::Done {
+            return Some(Err(job.status));
+        }
+        let points: Vec<(usize, ScanPoint)> = job.points.iter().flatten().copied().collect();
+        let mut out = Vec::new();
+        Some(write_csv(&points, &mut out).map(|_| out).map_err(|e| {
+            warn!("Failed to write the results of job {id}. Error {e}");
+            JobStatus::Failed
+        }))
+    }
+}
+
+/// Measure every point of a job on the worker pool, then mark it done or failed
+/// The points of a done job are recorded to results
+async fn run(id: String, job: Arc<Mutex<Job>>, workers: Arc<Semaphore>, results: Results) {
+    let (request, points) = {
+        let job = lock(&job);
+        (job.request.clone(), job.request.points())
+    };
+    let seed = request.seed.unwrap_or_default();
+    let mut tasks = JoinSet::new();
+    for (index, (size, reduced_temperature)) in points.into_iter().enumerate() {
+        let (job, workers) = (job.clone(), workers.clone());
+        let algorithm = request.algorithm;
+        let (equilibration, sweeps) = (request.equilibration, request.sweeps);
+        // Points wait for a worker in submission order, the semaphore being fair
+        tasks.spawn(async move {
+            let _worker = workers.acquire_owned().await.expect("Worker pool closed");
+            {
+                let mut job = lock(&job);
+                if job.status == JobStatus::Queued {
+                    job.status = JobStatus::Running;
+                }
+            }
+            let point = tokio::task::spawn_blocking(move || {
+                let mut lattice = Lattice::with_seed(size, INTERACTIVITY, 0.0, seed);
+                lattice.set_reduced_temperature(reduced_temperature);
+                scan::measure(&mut lattice, algorithm, equilibration, sweeps)
+            })
+            .await?;
+            lock(&job).points[index] = Some((size, point));
+            Ok::<_, tokio::task::JoinError>(())
+        });
+    }
+
+    let mut error = None;
+    while let Some(result) = tasks.join_next().await {
+        if let Err(e) = result.and_then(|measured| measured) {
+            error.get_or_insert(e.to_string());
+        }
+    }
+    let mut job = lock(&job);
+    match error {
+        Some(e) => {
+            warn!("Failed to run sweep job {id}. Error {e}");
+            job.status = JobStatus::Failed;
+            job.error = Some(e);
+        }
+        None => {
+            info!("Finished sweep job {id}");
+            job.status = JobStatus::Done;
+            let source = format!("job:{id}");
+            results.record(
+                job.points
+                    .iter()
+                    .flatten()
+                    .map(|(size, point)| Measurement {
+                        algorithm: request.algorithm,
+                        equilibration: request.equilibration,
+                        sweeps: request.sweeps,
+                        seed,
+                        ..Measurement::new(*size, *point, &source)
+                    }),
+            );
+        }
+    }
+}
+
+/// Write the points of a sweep as CSV, one row per size and temperature
+pub fn write_csv(points: &[(usize, ScanPoint)], out: &mut impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "size,temperature,magnetization,susceptibility,heat_capacity"
+    )?;
+    for (size, point) in points {
+        writeln!(
+            out,
+            "{size},{},{},{},{}",
+            point.reduced_temperature,
+            point.magnetization,
+            point.susceptibility,
+            point.heat_capacity
+        )?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::results::ResultsQuery;
+    use std::time::Duration;
+
+    #[test]
+    fn test_invalid_sweeps_are_refused() {
+        let request = |sizes: Vec<usize>, steps| SweepRequest {
+            sizes,
+            steps,
+            ..Default::default()
+        };
+
+        assert_eq!(request(vec![16, 32], 11).invalid(), None);
+        assert!(request(Vec::new(), 11).invalid().is_some());
+        assert!(request(vec![4], 11).invalid().is_some());
+        assert!(request(vec![16], 0).invalid().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_job_measures_every_size_and_temperature() {
+        let results = Results::default();
+        let jobs = Jobs::new(results.clone());
+        let request = SweepRequest {
+            sizes: vec![5, 6],
+            start: 1.0,
+            end: 3.0,
+            steps: 3,
+            equilibration: 2,
+            sweeps: 4,
+            ..Default::default()
+        };
+
+        let queued = jobs.submit(request).unwrap();
+        let mut info = queued.clone();
+        for _ in 0..100 {
+            info = jobs.get(&queued.id).unwrap();
+            if info.status.is_finished() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let csv = String::from_utf8(jobs.results(&queued.id).unwrap().unwrap()).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(queued.total, 6);
+        assert!(queued.request.seed.is_some());
+        assert_eq!(info.status, JobStatus::Done);
+        assert_eq!(info.completed, 6);
+        assert_eq!(rows.len(), 7);
+        assert!(rows[1].starts_with("5,1,"));
+        assert!(rows[6].starts_with("6,3,"));
+        let recorded = results.query(&ResultsQuery::default()).unwrap();
+        assert_eq!(recorded.total, 6);
+        assert!(recorded
+            .results
+            .iter()
+            .all(|result| result.source == format!("job:{}", queued.id) && result.sweeps == 4));
+    }
+}
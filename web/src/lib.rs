@@ -1 +1,17 @@
+pub mod auth;
+pub mod caching;
 pub mod config;
+pub mod cors;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod jobs;
+pub mod manager;
+pub mod permalink;
+pub mod phase;
+pub mod rate_limit;
+pub mod render;
+pub mod results;
+pub mod rooms;
+pub mod store;
+pub mod stream;
+pub mod wire;
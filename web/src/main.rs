@@ -1,98 +1,379 @@
-use axum::extract::{MatchedPath, State};
-use axum::http::{Request, StatusCode};
-use axum::response::Html;
-use axum::routing::{get, get_service};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{MatchedPath, Path, Query, State};
+use axum::handler::Handler;
+use axum::http::{header, HeaderValue, Request, StatusCode};
+use axum::middleware;
+use axum::response::sse::{KeepAlive, Sse};
+use axum::response::{Html, IntoResponse};
+use axum::routing::{delete, get, get_service, post};
 use axum::Router;
 use axum::{body::Bytes, http::HeaderMap, response::Response};
+use axum::{Extension, Json};
+use clap::Parser;
 use std::fs;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal;
 use tower::ServiceBuilder;
 use tower_http::compression::CompressionLayer;
+use tower_http::request_id::{
+    MakeRequestId, PropagateRequestIdLayer, RequestId, SetRequestIdLayer,
+};
 use tower_http::services::{ServeDir, ServeFile};
 use tower_http::timeout::TimeoutLayer;
 use tower_http::{classify::ServerErrorsFailureClass, trace::TraceLayer};
-use tracing::{info, info_span, Span};
+use tracing::{info, info_span, warn, Span};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use web::config::Config;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+use web::auth::{self, Admin, ApiKeys};
+use web::caching;
+use web::config::{Config, LogFormat};
+use web::cors;
+#[cfg(feature = "grpc")]
+use web::grpc::{self, SimulationService};
+use web::jobs::{JobStatus, Jobs, SweepRequest};
+use web::manager::{Denial, Limits, Owner, Refusal, SimulationManager};
+use web::permalink::Permalink;
+use web::phase::{PhaseCache, PhaseQuery};
+use web::rate_limit::{self, Permit, RateLimiter};
+use web::render::{self, AnimationParams, SnapshotParams};
+use web::results::{Results, ResultsQuery};
+use web::rooms::{self, Participant, Rooms};
+use web::store::Store;
+use web::stream::{self, ParamsUpdate, SampleParams, SimulationParams, WindowParams};
+use web::wire::{self, Encoding, FrameEncoder};
 
 // Application State
 #[derive(Clone)]
 struct AppState {
     config: Config,
+    simulations: SimulationManager,
+    rooms: Rooms,
+    phase_diagrams: PhaseCache,
+    jobs: Jobs,
+    results: Results,
+    api_keys: ApiKeys,
+    rate_limiter: RateLimiter,
+}
+
+/// Random request IDs, kept when the client or a proxy already set one
+#[derive(Clone, Copy, Default)]
+struct RandomRequestId;
+
+impl MakeRequestId for RandomRequestId {
+    fn make_request_id<B>(&mut self, _request: &Request<B>) -> Option<RequestId> {
+        let id = format!("{:016x}", rand::random::<u64>());
+        HeaderValue::from_str(&id).ok().map(RequestId::new)
+    }
+}
+
+/// Web server of the Ising model GUI and server-side simulations
+///
+/// Flags override environment variables, which override the config file
+#[derive(Parser, Debug, Clone)]
+struct Args {
+    /// Port to listen on, like SVC_PORT
+    #[arg(long)]
+    port: Option<u16>,
+    /// Address to listen on, like SVC_ENDPOINT, e.g. 127.0.0.1 or 0.0.0.0
+    #[arg(long)]
+    bind: Option<String>,
+    /// Directory of the files built by `trunk`, like DIST_PATH
+    #[arg(long)]
+    dist_path: Option<String>,
+    /// ERROR, WARN, INFO, DEBUG, or TRACE, like LOG_LEVEL
+    #[arg(long)]
+    log_level: Option<String>,
+    /// text or json, like LOG_FORMAT
+    #[arg(long)]
+    log_format: Option<String>,
+    /// Directory keeping managed simulations across restarts, like STORE_PATH
+    #[arg(long)]
+    store_path: Option<String>,
+    /// Path to a TOML config file, like CONFIG_PATH
+    #[arg(long)]
+    config: Option<String>,
+}
+
+impl Args {
+    /// Value of the flag standing for an environment variable
+    fn flag(&self, name: &str) -> Option<String> {
+        match name {
+            "SVC_PORT" => self.port.map(|port| port.to_string()),
+            "SVC_ENDPOINT" => self.bind.clone(),
+            "DIST_PATH" => self.dist_path.clone(),
+            "LOG_LEVEL" => self.log_level.clone(),
+            "LOG_FORMAT" => self.log_format.clone(),
+            "STORE_PATH" => self.store_path.clone(),
+            _ => None,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
-    app().await;
+    app(Args::parse()).await;
     Ok(())
 }
 
 /// Run the axum web application
-async fn app() {
-    // Setup Config
-    let config = Config::from_envar().await;
+async fn app(args: Args) {
+    // Setup Config, logging the defaults it falls back to before the configured logs exist
+    let startup_logs = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .finish();
+    let config = match tracing::subscriber::with_default(startup_logs, || {
+        Config::load(args.config.clone(), |name| args.flag(name))
+    }) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
     let endpoint = format!("{}:{}", &config.svc_endpoint, &config.svc_port);
 
-    // Initialize tracing
+    // Initialize tracing, the filter being replaced when the config is reloaded
+    let json = config.log_format == LogFormat::Json;
+    let (filter, filter_handle) = reload::Layer::new(log_filter(config.log_level));
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-                // axum logs rejections from built-in extractors with the `axum::rejection`
-                // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
-                format!(
-                    "{}=debug,tower_http=debug,axum::rejection=trace",
-                    env!("CARGO_CRATE_NAME")
-                )
-                .into()
-            }),
-        )
-        .with(tracing_subscriber::fmt::layer())
+        .with(filter)
+        // JSON lines carry the fields of the request span, its request ID included
+        .with(json.then(|| {
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(false)
+        }))
+        .with((!json).then(tracing_subscriber::fmt::layer))
         .init();
 
+    // Managed simulations are checkpointed to the store, when there is one
+    let store = config
+        .store_path
+        .as_ref()
+        .map(|path| match Store::open(path) {
+            Ok(store) => {
+                info!("Storing simulations in {path}");
+                store
+            }
+            Err(e) => {
+                eprintln!("Failed to open the simulation store {path}. Error {e}");
+                std::process::exit(1);
+            }
+        });
+    // Measurements of the jobs and the phase diagram build up the results database
+    let results = Results::new(store.clone());
+    let phase_diagrams = PhaseCache::new(store.clone(), results.clone());
+    let simulations = SimulationManager::new(Limits::new(&config), store);
+    let api_keys = ApiKeys::new(&config);
+    let rate_limiter = RateLimiter::new(&config);
+
+    // SIGHUP reloads the config, applying what can change without a restart
+    #[cfg(unix)]
+    tokio::spawn(reload_on_hangup(
+        args.clone(),
+        filter_handle,
+        rate_limiter.clone(),
+        simulations.clone(),
+    ));
+    #[cfg(not(unix))]
+    drop(filter_handle);
+
+    // The gRPC API runs beside the HTTP server on its own port, when enabled
+    #[cfg(feature = "grpc")]
+    let grpc_server = config.grpc_port.map(|port| {
+        let endpoint = format!("{}:{port}", &config.svc_endpoint);
+        let service =
+            SimulationService::new(simulations.clone(), api_keys.clone(), rate_limiter.clone());
+        tokio::spawn(async move {
+            info!("Starting gRPC Server at http://{}", endpoint);
+            let listener = tokio::net::TcpListener::bind(endpoint).await.unwrap();
+            if let Err(e) = grpc::serve(listener, service, shutdown_signal()).await {
+                warn!("Failed to serve the gRPC API. Error {e}");
+            }
+        })
+    });
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc_port.is_some() {
+        warn!("Ignoring GRPC_PORT, the server was built without the grpc feature");
+    }
+
     // Init app state
     info!("Starting HTTP Server at http://{}", endpoint);
-    let app = main_route(AppState { config });
+    let app = main_route(AppState {
+        api_keys,
+        rate_limiter,
+        config,
+        simulations: simulations.clone(),
+        rooms: Rooms::default(),
+        phase_diagrams,
+        jobs: Jobs::new(results.clone()),
+        results,
+    });
 
     // Start Axum Application
     let listener = tokio::net::TcpListener::bind(endpoint).await.unwrap();
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+    // Client addresses key the rate limits
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap();
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_server) = grpc_server {
+        let _ = grpc_server.await;
+    }
+    simulations.checkpoint_all();
+}
+
+/// Filter of the logs at level, or else of RUST_LOG when it is set
+fn log_filter(level: tracing::Level) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        // axum logs rejections from built-in extractors with the `axum::rejection`
+        // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
+        format!(
+            "{crate_name}={level},tower_http={level},axum::rejection=trace",
+            crate_name = env!("CARGO_CRATE_NAME"),
+        )
+        .into()
+    })
+}
+
+/// Reload the config on every SIGHUP, applying its log level, rate limits, and simulation
+/// limits from the next request on, connected clients staying connected
+/// Other values apply after a restart, an invalid config keeps the current one
+#[cfg(unix)]
+async fn reload_on_hangup(
+    args: Args,
+    filter: reload::Handle<EnvFilter, Registry>,
+    rate_limiter: RateLimiter,
+    simulations: SimulationManager,
+) {
+    let mut hangups = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            warn!("Failed to install the SIGHUP handler, the config won't be reloaded. Error {e}");
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        info!("Reloading the config");
+        let config = match Config::load(args.config.clone(), |name| args.flag(name)) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Keeping the current config. {e}");
+                continue;
+            }
+        };
+        if let Err(e) = filter.reload(log_filter(config.log_level)) {
+            warn!("Failed to change the log level. Error {e}");
+        }
+        rate_limiter.reconfigure(&config);
+        simulations.set_limits(Limits::new(&config));
+        info!("Reloaded the log level, rate limits, and simulation limits");
+    }
 }
 
 /// Build Axum router
 fn main_route(app_state: AppState) -> Router {
     let dist_path = app_state.config.dist_path.clone();
+    // Compute endpoints are rate limited by client, answering 429 with Retry-After
+    let limit = middleware::from_fn_with_state(app_state.rate_limiter.clone(), rate_limit::limit);
+    // Simulation and job endpoints need an API key once keys are configured
+    let auth = middleware::from_fn_with_state(app_state.api_keys.clone(), auth::require_key);
+    // Admin endpoints need an admin key, and are not found when none is configured
+    let admin = middleware::from_fn_with_state(app_state.api_keys.clone(), auth::require_admin);
+    let cors = cors::layer(&app_state.config);
+    // Static files are cached by browsers and revalidated with their ETag
+    let static_cache = middleware::from_fn(caching::cache);
     Router::new()
         .route("/", get(get_index))
+        .route("/embed", get(get_embed))
         .nest_service(
             "/assets",
-            get_service(ServeDir::new(format!("{dist_path}/assets"))),
+            get_service(ServeDir::new(format!("{dist_path}/assets"))).layer(static_cache.clone()),
         )
         .nest_service(
             "/favicon.ico",
-            get_service(ServeFile::new(format!("{dist_path}/favicon.ico"))),
+            get_service(ServeFile::new(format!("{dist_path}/favicon.ico")))
+                .layer(static_cache.clone()),
         )
         .nest_service(
             "/gui_bg.wasm",
-            get_service(ServeFile::new(format!("{dist_path}/gui_bg.wasm"))),
+            get_service(ServeFile::new(format!("{dist_path}/gui_bg.wasm")))
+                .layer(static_cache.clone()),
         )
         .nest_service(
             "/gui.js",
-            get_service(ServeFile::new(format!("{dist_path}/gui.js"))),
+            get_service(ServeFile::new(format!("{dist_path}/gui.js"))).layer(static_cache.clone()),
         )
         .nest_service(
             "/index.css",
-            get_service(ServeFile::new(format!("{dist_path}/index.css"))),
+            get_service(ServeFile::new(format!("{dist_path}/index.css")))
+                .layer(static_cache.clone()),
         )
         .nest_service(
             "/sw.js",
-            get_service(ServeFile::new(format!("{dist_path}/sw.js"))),
+            get_service(ServeFile::new(format!("{dist_path}/sw.js"))).layer(static_cache.clone()),
+        )
+        .route(
+            "/api/simulations",
+            get(get_simulations).post(post_simulation.layer(limit.clone()).layer(auth.clone())),
+        )
+        .route(
+            "/api/simulations/{id}",
+            get(get_simulation)
+                .patch(patch_simulation.layer(limit.clone()).layer(auth.clone()))
+                .delete(delete_simulation.layer(auth.clone())),
+        )
+        .route("/api/simulations/{id}/state", get(get_simulation_state))
+        .route(
+            "/api/simulations/{id}/observables",
+            get(get_simulation_observables.layer(limit.clone())),
         )
+        .route(
+            "/api/simulations/{id}/snapshot.png",
+            get(get_simulation_snapshot),
+        )
+        .route(
+            "/api/simulations/{id}/animation.gif",
+            get(get_simulation_animation
+                .layer(limit.clone())
+                .layer(auth.clone())),
+        )
+        .route(
+            "/api/jobs/sweep",
+            post(post_sweep_job.layer(limit.clone()).layer(auth.clone())),
+        )
+        .route("/api/jobs/{id}", get(get_job.layer(auth.clone())))
+        .route(
+            "/api/jobs/{id}/results.csv",
+            get(get_job_results.layer(auth.clone())),
+        )
+        .route(
+            "/api/phase-diagram",
+            get(get_phase_diagram.layer(limit.clone())),
+        )
+        .route(
+            "/api/admin/simulations",
+            get(get_admin_simulations.layer(admin.clone())),
+        )
+        .route(
+            "/api/admin/simulations/{id}",
+            delete(delete_admin_simulation.layer(admin)),
+        )
+        .route("/api/results", get(get_results))
+        .route("/metrics", get(get_metrics))
         .layer((
+            // Every request gets an ID in its logs, sent back in the x-request-id header
+            SetRequestIdLayer::x_request_id(RandomRequestId),
+            PropagateRequestIdLayer::x_request_id(),
             ServiceBuilder::new().layer(CompressionLayer::new()),
             // TODO: explore more about TraceLayer
             TraceLayer::new_for_http()
@@ -104,10 +385,16 @@ fn main_route(app_state: AppState) -> Router {
                         .get::<MatchedPath>()
                         .map(MatchedPath::as_str);
 
+                    let request_id = request
+                        .extensions()
+                        .get::<RequestId>()
+                        .and_then(|id| id.header_value().to_str().ok());
+
                     info_span!(
                         "http_request",
                         method = ?request.method(),
                         matched_path,
+                        request_id,
                         some_other_field = tracing::field::Empty,
                     )
                 })
@@ -139,14 +426,403 @@ fn main_route(app_state: AppState) -> Router {
             // requests don't hang forever.
             TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, Duration::from_secs(10)),
         ))
+        // Streams last as long as their clients, so they skip the layers above
+        .route(
+            "/ws/simulations/{id}",
+            get(get_simulation_stream
+                .layer(limit.clone())
+                .layer(auth.clone())),
+        )
+        .route(
+            "/ws/rooms/{room}",
+            get(get_room_stream.layer(limit).layer(auth)),
+        )
+        .route(
+            "/api/simulations/{id}/observables/stream",
+            get(get_observables_stream),
+        )
+        // Pages of other origins may call any route above, streams included
+        .layer(ServiceBuilder::new().option_layer(cors))
         .with_state(app_state)
         .fallback_service(get(get_not_found))
 }
 
-async fn get_index(State(app_state): State<AppState>) -> Html<String> {
+/// Serve the GUI, starting from the parameters of a permalink like `/?size=100&t=2.27&seed=42`
+/// A malformed query is ignored rather than failing the page
+async fn get_index(
+    State(app_state): State<AppState>,
+    permalink: Result<Query<Permalink>, QueryRejection>,
+) -> Html<String> {
+    let dist_path = format!("{}/index.html", app_state.config.dist_path);
+    let index = fs::read_to_string(&dist_path).unwrap_or("404 - Not Found".to_string());
+    let permalink = permalink
+        .map(|Query(permalink)| permalink)
+        .unwrap_or_default();
+    Html(permalink.inject(&index))
+}
+
+/// Serve the lattice alone with a play/pause button, for iframes like `/embed?size=100&t=2.27`
+/// A malformed query is ignored rather than failing the page
+async fn get_embed(
+    State(app_state): State<AppState>,
+    permalink: Result<Query<Permalink>, QueryRejection>,
+) -> Html<String> {
     let dist_path = format!("{}/index.html", app_state.config.dist_path);
     let index = fs::read_to_string(&dist_path).unwrap_or("404 - Not Found".to_string());
-    Html(index)
+    let permalink = permalink
+        .map(|Query(permalink)| permalink)
+        .unwrap_or_default();
+    Html(permalink.embed(&index))
+}
+
+/// Stream a server-side simulation to a WebSocket client, starting it for its first client
+/// Frames are binary for clients asking for the `ising.frame.v1` subprotocol, JSON otherwise
+async fn get_simulation_stream(
+    ws: WebSocketUpgrade,
+    Path(id): Path<String>,
+    Query(params): Query<SimulationParams>,
+    State(app_state): State<AppState>,
+    Owner(owner): Owner,
+    Extension(permit): Extension<Arc<Permit>>,
+) -> Response {
+    let (ws, encoding) = wire::negotiate_protocol(ws);
+    // The permit counts the stream in flight until the socket closes
+    ws.on_upgrade(move |socket| async move {
+        stream::serve_client(socket, app_state.simulations, id, owner, params, encoding).await;
+        drop(permit);
+    })
+}
+
+/// Join a room sharing a server-side simulation like `/ws/rooms/physics-101?name=Ada`,
+/// its first participant starting the simulation with the parameters of the query
+async fn get_room_stream(
+    ws: WebSocketUpgrade,
+    Path(room): Path<String>,
+    Query(params): Query<SimulationParams>,
+    Query(participant): Query<Participant>,
+    State(app_state): State<AppState>,
+    Owner(owner): Owner,
+    Extension(permit): Extension<Arc<Permit>>,
+) -> Response {
+    if !rooms::is_room_name(&room) {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "Expected a room name of 1 to {} letters, digits, '-', or '_'",
+                rooms::MAX_ROOM_NAME
+            ),
+        )
+            .into_response();
+    }
+    let name = participant.display_name();
+    let (ws, encoding) = wire::negotiate_protocol(ws);
+    ws.on_upgrade(move |socket| async move {
+        rooms::serve_participant(
+            socket,
+            app_state.simulations,
+            app_state.rooms,
+            room,
+            name,
+            owner,
+            params,
+            encoding,
+        )
+        .await;
+        drop(permit);
+    })
+}
+
+/// Stream the observables of a running simulation as Server-Sent Events every few sweeps
+async fn get_observables_stream(
+    Path(id): Path<String>,
+    Query(params): Query<SampleParams>,
+    State(app_state): State<AppState>,
+) -> Response {
+    match app_state.simulations.observe(&id) {
+        Some(frames) => Sse::new(stream::samples(frames, params.every))
+            .keep_alive(KeepAlive::default())
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// List the running server-side simulations
+async fn get_simulations(State(app_state): State<AppState>) -> Response {
+    Json(app_state.simulations.list()).into_response()
+}
+
+/// Start a managed server-side simulation, running until deleted or idle
+/// Clients over their limits get 429 Too Many Requests with the limit they hit
+async fn post_simulation(
+    State(app_state): State<AppState>,
+    Owner(owner): Owner,
+    Json(params): Json<SimulationParams>,
+) -> Response {
+    match app_state.simulations.create(&owner, params) {
+        Ok(info) => (StatusCode::CREATED, Json(info)).into_response(),
+        Err(Refusal::Full) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+        Err(refusal) => (StatusCode::TOO_MANY_REQUESTS, refusal.to_string()).into_response(),
+    }
+}
+
+/// Describe a running server-side simulation
+async fn get_simulation(Path(id): Path<String>, State(app_state): State<AppState>) -> Response {
+    match app_state.simulations.get(&id) {
+        Some(info) => Json(info).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Snapshot the spins, parameters, and sweep of a running simulation
+/// Clients accepting `application/vnd.ising.frame` get the spins and sweep as a binary full frame
+async fn get_simulation_state(
+    Path(id): Path<String>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let vary = [(header::VARY, "accept")];
+    if Encoding::negotiate(&headers) == Encoding::Binary {
+        return match app_state.simulations.frame(&id) {
+            Some(frame) => (
+                vary,
+                [(header::CONTENT_TYPE, wire::MEDIA_TYPE)],
+                FrameEncoder::default().encode(&frame),
+            )
+                .into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        };
+    }
+    match app_state.simulations.state(&id) {
+        Some(state) => (vary, Json(state)).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Energy, magnetization, susceptibility, heat capacity, acceptance, and correlation length
+/// of a running simulation, the fluctuations taken over its latest sweeps like `?window=1000`
+async fn get_simulation_observables(
+    Path(id): Path<String>,
+    Query(params): Query<WindowParams>,
+    State(app_state): State<AppState>,
+) -> Response {
+    if let Some(reason) = params.invalid() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, reason).into_response();
+    }
+    let simulations = app_state.simulations;
+    match tokio::task::spawn_blocking(move || simulations.observables(&id, params.window)).await {
+        Ok(Some(observables)) => Json(observables).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            warn!("Failed to compute the observables. Error {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Render the current spins of a running simulation as a PNG image like `?scale=4`
+async fn get_simulation_snapshot(
+    Path(id): Path<String>,
+    Query(params): Query<SnapshotParams>,
+    State(app_state): State<AppState>,
+) -> Response {
+    let Some((_, lattice)) = app_state.simulations.lattice(&id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let png = tokio::task::spawn_blocking(move || render::png(&lattice, params))
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|png| png);
+    match png {
+        Ok(png) => (
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                // The lattice changes every sweep
+                (header::CACHE_CONTROL, "no-store"),
+            ],
+            png,
+        )
+            .into_response(),
+        Err(e) => {
+            warn!("Failed to render simulation {id}. Error {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Render the evolution of a copy of a running simulation as an animated GIF
+/// like `?sweeps=500&stride=5`, the simulation itself being left as is
+async fn get_simulation_animation(
+    Path(id): Path<String>,
+    Query(params): Query<AnimationParams>,
+    State(app_state): State<AppState>,
+) -> Response {
+    if let Some(reason) = params.invalid(app_state.config.max_animation_sweeps as usize) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, reason).into_response();
+    }
+    let Some((simulation, lattice)) = app_state.simulations.lattice(&id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let gif =
+        tokio::task::spawn_blocking(move || render::gif(lattice, simulation.algorithm, params))
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|gif| gif);
+    match gif {
+        Ok(gif) => ([(header::CONTENT_TYPE, "image/gif")], gif).into_response(),
+        Err(e) => {
+            warn!("Failed to animate simulation {id}. Error {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Change the temperature, interactivity, field, or algorithm of a running simulation
+/// Clients may change only their own simulations, administrators any
+async fn patch_simulation(
+    Path(id): Path<String>,
+    State(app_state): State<AppState>,
+    owner: Owner,
+    admin: Option<Extension<Admin>>,
+    Json(update): Json<ParamsUpdate>,
+) -> Response {
+    if let Err(denial) = app_state
+        .simulations
+        .authorize(&id, &owner.0, admin.is_some())
+    {
+        return denied(denial);
+    }
+    match app_state.simulations.update(&id, update) {
+        Some(info) => Json(info).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Stop a running simulation and disconnect its clients
+/// Clients may stop only their own simulations, administrators any
+async fn delete_simulation(
+    Path(id): Path<String>,
+    State(app_state): State<AppState>,
+    owner: Owner,
+    admin: Option<Extension<Admin>>,
+) -> Response {
+    if let Err(denial) = app_state
+        .simulations
+        .authorize(&id, &owner.0, admin.is_some())
+    {
+        return denied(denial);
+    }
+    if app_state.simulations.remove(&id) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+/// 404 Not Found for a missing simulation, 403 Forbidden with the reason otherwise
+fn denied(denial: Denial) -> Response {
+    match denial {
+        Denial::Missing => StatusCode::NOT_FOUND.into_response(),
+        _ => (StatusCode::FORBIDDEN, denial.to_string()).into_response(),
+    }
+}
+
+/// List the running simulations with their owner, memory estimate, and last activity
+async fn get_admin_simulations(State(app_state): State<AppState>) -> Response {
+    Json(app_state.simulations.active()).into_response()
+}
+
+/// Force-stop any running simulation, WebSocket and room ones included,
+/// disconnecting its clients
+async fn delete_admin_simulation(
+    Path(id): Path<String>,
+    State(app_state): State<AppState>,
+    Extension(Admin(admin)): Extension<Admin>,
+) -> StatusCode {
+    if app_state.simulations.remove(&id) {
+        warn!("Simulation {id} force-stopped by administrator {admin}");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Queue a temperature sweep on the worker pool
+async fn post_sweep_job(
+    State(app_state): State<AppState>,
+    Json(request): Json<SweepRequest>,
+) -> Response {
+    if let Some(reason) = request.invalid() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, reason).into_response();
+    }
+    match app_state.jobs.submit(request) {
+        Some(info) => (StatusCode::ACCEPTED, Json(info)).into_response(),
+        None => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    }
+}
+
+/// Status and progress of a sweep job
+async fn get_job(Path(id): Path<String>, State(app_state): State<AppState>) -> Response {
+    match app_state.jobs.get(&id) {
+        Some(info) => Json(info).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Download the results of a finished sweep job as CSV
+async fn get_job_results(Path(id): Path<String>, State(app_state): State<AppState>) -> Response {
+    match app_state.jobs.results(&id) {
+        Some(Ok(csv)) => ([(header::CONTENT_TYPE, "text/csv")], csv).into_response(),
+        Some(Err(JobStatus::Failed)) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        Some(Err(_)) => StatusCode::CONFLICT.into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Observables of a lattice size over reduced temperatures like `?size=32&start=1.5&end=3.5`,
+/// measured once on a fixed grid and served from the cache after
+/// Points still being measured are counted as pending, for clients to fetch again
+async fn get_phase_diagram(
+    Query(query): Query<PhaseQuery>,
+    State(app_state): State<AppState>,
+) -> Response {
+    if let Some(reason) = query.invalid() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, reason).into_response();
+    }
+    let diagram = app_state.phase_diagrams.get(query);
+    // Complete diagrams never change
+    let cache_control = if diagram.pending == 0 {
+        "public, max-age=86400"
+    } else {
+        "no-store"
+    };
+    ([(header::CACHE_CONTROL, cache_control)], Json(diagram)).into_response()
+}
+
+/// Measurements of the finished jobs and the phase diagram, page by page, like
+/// `?model=ising2d&size=64&t_min=2&t_max=2.5&sort=temperature&order=asc&offset=0&limit=100`
+async fn get_results(
+    Query(query): Query<ResultsQuery>,
+    State(app_state): State<AppState>,
+) -> Response {
+    if let Some(reason) = query.invalid() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, reason).into_response();
+    }
+    match app_state.results.query(&query) {
+        Ok(page) => Json(page).into_response(),
+        Err(e) => {
+            warn!("Failed to query the results. Error {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Usage of every API key in the Prometheus text format
+async fn get_metrics(State(app_state): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        app_state.api_keys.metrics(),
+    )
+        .into_response()
 }
 
 async fn get_not_found() -> Html<String> {
@@ -0,0 +1,995 @@
+use crate::auth::KeyName;
+use crate::config::Config;
+use crate::rooms::is_room_simulation;
+use crate::store::{Store, StoredSimulation, CHECKPOINT_EVERY};
+use crate::stream::{
+    net_flips, pack_spins, Frame, Observables, ParamsUpdate, SimulationInfo, SimulationParams,
+    SimulationState, WindowedObservables, MAX_SIZE, RECORDED_SWEEPS,
+};
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use internal::correlation::correlation_length;
+use internal::recorder::{Recorder, Sample};
+use internal::Lattice;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, Receiver, Sender};
+use tracing::{info, warn};
+
+/// Shortest time between two sweeps of a simulation
+pub const SWEEP_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Number of simulations running or stored at once, further ones are refused
+pub const MAX_SIMULATIONS: usize = 16;
+
+/// Number of frames buffered for a slow client before it is resynced with a full frame
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Limits of every client of the manager, see `Owner`
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct Limits {
+    /// simulations a client may run or keep stored
+    pub max_lattices: usize,
+    /// spins summed over the simulations of a client
+    pub max_sites: usize,
+    /// sweeps per second shared by the running simulations of a client
+    pub max_sweeps_per_second: u32,
+    /// seconds a managed simulation may go without clients or requests before it is evicted
+    pub idle_ttl_secs: u64,
+}
+
+impl Limits {
+    /// Limits of a config
+    pub fn new(config: &Config) -> Self {
+        Self {
+            max_lattices: config.max_lattices_per_client as usize,
+            max_sites: config.max_sites_per_client as usize,
+            max_sweeps_per_second: config.max_sweeps_per_second.max(1),
+            idle_ttl_secs: u64::from(config.simulation_idle_ttl),
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new(&Config::default())
+    }
+}
+
+/// Client owning the simulations it starts, by API key name once authenticated,
+/// or else by IP address
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Owner(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for Owner {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(KeyName(name)) = parts.extensions.get::<KeyName>() {
+            return Ok(Owner(format!("key:{name}")));
+        }
+        match parts.extensions.get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(address)) => Ok(Owner(format!("ip:{}", address.ip()))),
+            None => Ok(Owner("unknown".to_string())),
+        }
+    }
+}
+
+/// Why a simulation was not started
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    /// MAX_SIMULATIONS are already running or stored
+    Full,
+    /// the client already has its maximum number of simulations
+    Lattices(usize),
+    /// the lattice would take the client over its maximum number of spins
+    Sites(usize),
+}
+
+impl std::fmt::Display for Refusal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Refusal::Full => write!(f, "{MAX_SIMULATIONS} simulations are already running"),
+            Refusal::Lattices(max) => write!(f, "Clients may have at most {max} simulations"),
+            Refusal::Sites(max) => write!(
+                f,
+                "Clients may have at most {max} spins across their simulations"
+            ),
+        }
+    }
+}
+
+/// Why a client may not change or stop a simulation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Denial {
+    /// there is no such simulation
+    Missing,
+    /// the simulation was started by another client
+    NotOwner,
+    /// the simulation is of a room, changed through the room so every participant hears of it
+    Room,
+}
+
+impl std::fmt::Display for Denial {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Denial::Missing => write!(f, "No such simulation"),
+            Denial::NotOwner => write!(
+                f,
+                "Only the client who started the simulation may change it"
+            ),
+            Denial::Room => write!(f, "Room simulations are changed through their room"),
+        }
+    }
+}
+
+/// Simulations of a client and the resources they take
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct ClientUsage {
+    pub client: String,
+    /// simulations running or stored
+    pub lattices: usize,
+    /// spins summed over those simulations
+    pub sites: usize,
+    /// sweeps per second of the running ones
+    pub sweeps_per_second: f64,
+}
+
+/// Limits and usage of the manager
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct ManagerStatus {
+    pub limits: Limits,
+    /// simulations running
+    pub running: usize,
+    /// simulations stored, running or waiting to be resumed
+    pub stored: usize,
+    /// usage of every client with a simulation, sorted by client
+    pub clients: Vec<ClientUsage>,
+}
+
+/// Running simulation as seen by the administrators
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct ActiveSimulation {
+    #[serde(flatten)]
+    pub info: SimulationInfo,
+    /// client who started the simulation
+    pub owner: String,
+    /// estimated bytes taken by the lattice and the recorded observables of the simulation
+    pub memory_bytes: usize,
+    /// Unix time in seconds of the latest request about the simulation
+    pub last_activity: u64,
+}
+
+/// Lattice of a running simulation with its latest sweep
+struct State {
+    lattice: Lattice,
+    params: SimulationParams,
+    sweep: u64,
+    observables: Observables,
+    /// observables of the latest RECORDED_SWEEPS sweeps, since the simulation started or resumed
+    recorder: Recorder,
+}
+
+/// Simulation shared by the task running it and its clients
+struct Shared {
+    state: Mutex<State>,
+    frames: Sender<Arc<Frame>>,
+    /// created through the API, kept running without clients until deleted or evicted
+    managed: bool,
+    /// client who started the simulation
+    owner: String,
+    /// time of the latest request about the simulation
+    accessed: Mutex<Instant>,
+}
+
+impl Shared {
+    /// New simulation of a random lattice
+    fn new(params: SimulationParams, managed: bool, owner: &str) -> Arc<Self> {
+        let lattice = params.lattice();
+        let params = SimulationParams {
+            size: lattice.size,
+            seed: Some(lattice.seed),
+            ..params
+        };
+        Self::with_lattice(lattice, params, 0, managed, owner)
+    }
+
+    /// Simulation of a lattice already run for sweep sweeps
+    fn with_lattice(
+        lattice: Lattice,
+        params: SimulationParams,
+        sweep: u64,
+        managed: bool,
+        owner: &str,
+    ) -> Arc<Self> {
+        let observables = Observables {
+            magnetization: lattice.magnetization(),
+            energy: lattice.energy_per_spin(),
+            acceptance: 0.0,
+        };
+        let (frames, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Arc::new(Self {
+            state: Mutex::new(State {
+                lattice,
+                params,
+                sweep,
+                observables,
+                recorder: Recorder::new(RECORDED_SWEEPS),
+            }),
+            frames,
+            managed,
+            owner: owner.to_string(),
+            accessed: Mutex::new(Instant::now()),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn accessed(&self) -> MutexGuard<'_, Instant> {
+        self.accessed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the simulation went without clients or requests for ttl
+    fn is_idle(&self, ttl: Duration) -> bool {
+        self.frames.receiver_count() == 0 && self.accessed().elapsed() >= ttl
+    }
+
+    /// Description of the simulation for the API
+    fn info(&self, id: &str) -> SimulationInfo {
+        self.describe(id, &self.state())
+    }
+
+    /// Description of the simulation in state, taken under its lock
+    fn describe(&self, id: &str, state: &State) -> SimulationInfo {
+        SimulationInfo {
+            id: id.to_string(),
+            params: state.params,
+            sweep: state.sweep,
+            managed: self.managed,
+            clients: self.frames.receiver_count(),
+        }
+    }
+
+    /// Description of the simulation for the administrators
+    fn activity(&self, id: &str) -> ActiveSimulation {
+        let state = self.state();
+        let lattice = &state.lattice;
+        let memory_bytes = std::mem::size_of::<Lattice>()
+            + lattice
+                .value
+                .iter()
+                .map(|row| {
+                    std::mem::size_of_val(row) + row.value.capacity() * std::mem::size_of::<i32>()
+                })
+                .sum::<usize>()
+            + state.recorder.samples.capacity() * std::mem::size_of::<Sample>();
+        let last_activity = SystemTime::now()
+            .checked_sub(self.accessed().elapsed())
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        ActiveSimulation {
+            info: self.describe(id, &state),
+            owner: self.owner.clone(),
+            memory_bytes,
+            last_activity,
+        }
+    }
+
+    /// Spins and observables of the simulation after its latest sweep
+    fn snapshot(&self, id: &str) -> SimulationState {
+        let state = self.state();
+        SimulationState {
+            info: self.describe(id, &state),
+            spins: pack_spins(&state.lattice),
+            observables: state.observables,
+        }
+    }
+}
+
+/// Running simulations by id
+type Running = HashMap<String, Arc<Shared>>;
+
+/// Owner of every server-side simulation, enforcing the limits of their clients
+/// Simulations started by a WebSocket client stop after their last client leaves,
+/// the ones created through the API run until deleted or idle for the TTL of the limits
+/// With a store, managed simulations are checkpointed every CHECKPOINT_EVERY sweeps
+/// and on eviction, then resumed on their first access
+#[derive(Clone, Default)]
+pub struct SimulationManager {
+    running: Arc<Mutex<Running>>,
+    /// limits of every client, changed when the config is reloaded
+    limits: Arc<Mutex<Limits>>,
+    /// checkpoints of the managed simulations, None to keep them in memory only
+    store: Option<Store>,
+}
+
+impl SimulationManager {
+    /// Manager of simulations limited by limits, checkpointed to store when there is one
+    pub fn new(limits: Limits, store: Option<Store>) -> Self {
+        Self {
+            running: Arc::default(),
+            limits: Arc::new(Mutex::new(limits)),
+            store,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Running> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Current limits of every client
+    pub fn limits(&self) -> Limits {
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Hold clients to new limits from now on, their running simulations being kept
+    pub fn set_limits(&self, limits: Limits) {
+        info!("Limiting clients to {limits:?}");
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+    }
+
+    /// Stored simulations, running or waiting to be resumed, sorted by id
+    fn stored(&self) -> Vec<(String, StoredSimulation)> {
+        let Some(store) = &self.store else {
+            return Vec::new();
+        };
+        store.list().unwrap_or_else(|e| {
+            warn!("Failed to list stored simulations. Error {e}");
+            Vec::new()
+        })
+    }
+
+    /// Number of running simulations and of stored ones waiting to be resumed
+    fn count(&self, simulations: &Running) -> usize {
+        let dormant = self
+            .stored()
+            .iter()
+            .filter(|(id, _)| !simulations.contains_key(id))
+            .count();
+        simulations.len() + dormant
+    }
+
+    /// Time between two sweeps of the simulations of owner,
+    /// sharing its sweeps per second once they would go over
+    fn period(&self, simulations: &Running, owner: &str) -> Duration {
+        let running = simulations
+            .values()
+            .filter(|shared| shared.owner == owner)
+            .count();
+        let rate = f64::from(self.limits().max_sweeps_per_second);
+        SWEEP_INTERVAL.max(Duration::from_secs_f64(running as f64 / rate))
+    }
+
+    /// Usage of every client with a running or stored simulation
+    fn usage(&self, simulations: &Running) -> BTreeMap<String, ClientUsage> {
+        let mut usage: BTreeMap<String, ClientUsage> = BTreeMap::new();
+        let mut add = |owner: &str, size: usize, running: bool| {
+            let client = usage
+                .entry(owner.to_string())
+                .or_insert_with(|| ClientUsage {
+                    client: owner.to_string(),
+                    ..Default::default()
+                });
+            client.lattices += 1;
+            client.sites += size * size;
+            if running {
+                client.sweeps_per_second += 1.0 / self.period(simulations, owner).as_secs_f64();
+            }
+        };
+        for shared in simulations.values() {
+            add(&shared.owner, shared.state().params.size, true);
+        }
+        for (id, stored) in self.stored() {
+            if !simulations.contains_key(&id) {
+                add(&stored.owner, stored.params.size, false);
+            }
+        }
+        usage
+    }
+
+    /// Check that owner may start a simulation of params
+    fn admit(
+        &self,
+        simulations: &Running,
+        owner: &str,
+        params: &SimulationParams,
+    ) -> Result<(), Refusal> {
+        if self.count(simulations) >= MAX_SIMULATIONS {
+            return Err(Refusal::Full);
+        }
+        let usage = self.usage(simulations).remove(owner).unwrap_or_default();
+        let size = params.size.clamp(5, MAX_SIZE);
+        let limits = self.limits();
+        if usage.lattices >= limits.max_lattices {
+            Err(Refusal::Lattices(limits.max_lattices))
+        } else if usage.sites + size * size > limits.max_sites {
+            Err(Refusal::Sites(limits.max_sites))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Limits of the manager with the usage of every client
+    pub fn status(&self) -> ManagerStatus {
+        let simulations = self.lock();
+        ManagerStatus {
+            limits: self.limits(),
+            running: simulations.len(),
+            stored: self.stored().len(),
+            clients: self.usage(&simulations).into_values().collect(),
+        }
+    }
+
+    /// Running simulation id, resumed from its latest checkpoint when it is only stored
+    /// Counts as a request keeping the simulation from being evicted
+    fn find(&self, simulations: &mut Running, id: &str) -> Option<Arc<Shared>> {
+        if let Some(shared) = simulations.get(id) {
+            *shared.accessed() = Instant::now();
+            return Some(shared.clone());
+        }
+        let (stored, mut lattice) = match self.store.as_ref()?.load(id) {
+            Ok(checkpoint) => checkpoint?,
+            Err(e) => {
+                warn!("Failed to resume simulation {id}. Error {e}");
+                return None;
+            }
+        };
+        stored.params.apply(&mut lattice);
+        info!("Resuming simulation {id} from sweep {}", stored.sweep);
+        let shared =
+            Shared::with_lattice(lattice, stored.params, stored.sweep, true, &stored.owner);
+        self.spawn(simulations, id.to_string(), shared.clone());
+        Some(shared)
+    }
+
+    /// Save the spins, parameters, and sweep of the managed simulation id to the store
+    /// Taking the registry keeps a deleted simulation from being saved again
+    fn checkpoint(&self, simulations: &Running, id: &str, shared: &Arc<Shared>) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let registered = simulations
+            .get(id)
+            .is_some_and(|registered| Arc::ptr_eq(registered, shared));
+        if !shared.managed || !registered {
+            return;
+        }
+        let (stored, lattice) = {
+            let state = shared.state();
+            let stored = StoredSimulation {
+                params: state.params,
+                sweep: state.sweep,
+                owner: shared.owner.clone(),
+            };
+            (stored, state.lattice.clone())
+        };
+        if let Err(e) = store.save(id, &stored, &lattice) {
+            warn!("Failed to checkpoint simulation {id}. Error {e}");
+        }
+    }
+
+    /// Checkpoint every running managed simulation and write the store to disk,
+    /// e.g. before shutting down
+    pub fn checkpoint_all(&self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let simulations = self.lock();
+        for (id, shared) in simulations.iter() {
+            self.checkpoint(&simulations, id, shared);
+        }
+        info!("Checkpointed {} simulations", simulations.len());
+        if let Err(e) = store.flush() {
+            warn!("Failed to write the simulation store. Error {e}");
+        }
+    }
+
+    /// Start a simulation of owner kept running without clients under a new random id
+    pub fn create(&self, owner: &str, params: SimulationParams) -> Result<SimulationInfo, Refusal> {
+        let mut simulations = self.lock();
+        self.admit(&simulations, owner, &params)?;
+        let stored = self.stored();
+        let id = loop {
+            let id = format!("{:016x}", rand::random::<u64>());
+            if !simulations.contains_key(&id) && !stored.iter().any(|(other, _)| *other == id) {
+                break id;
+            }
+        };
+        let shared = Shared::new(params, true, owner);
+        let info = shared.info(&id);
+        self.spawn(&mut simulations, id.clone(), shared.clone());
+        self.checkpoint(&simulations, &id, &shared);
+        Ok(info)
+    }
+
+    /// Every running or stored simulation, sorted by id
+    pub fn list(&self) -> Vec<SimulationInfo> {
+        let simulations = self.lock();
+        let dormant: Vec<SimulationInfo> = self
+            .stored()
+            .into_iter()
+            .filter(|(id, _)| !simulations.contains_key(id))
+            .map(|(id, stored)| SimulationInfo {
+                id,
+                params: stored.params,
+                sweep: stored.sweep,
+                managed: true,
+                clients: 0,
+            })
+            .collect();
+        let mut simulations: Vec<SimulationInfo> = simulations
+            .iter()
+            .map(|(id, shared)| shared.info(id))
+            .chain(dormant)
+            .collect();
+        simulations.sort_by(|a, b| a.id.cmp(&b.id));
+        simulations
+    }
+
+    /// Every running simulation with its owner and resources, sorted by id
+    /// Stored simulations waiting to be resumed are left out
+    pub fn active(&self) -> Vec<ActiveSimulation> {
+        let mut simulations: Vec<ActiveSimulation> = self
+            .lock()
+            .iter()
+            .map(|(id, shared)| shared.activity(id))
+            .collect();
+        simulations.sort_by(|a, b| a.info.id.cmp(&b.info.id));
+        simulations
+    }
+
+    /// Running simulation id, None when there is none
+    pub fn get(&self, id: &str) -> Option<SimulationInfo> {
+        let shared = self.find(&mut self.lock(), id)?;
+        Some(shared.info(id))
+    }
+
+    /// Current spins, parameters, and sweep of the simulation id, None when there is none
+    pub fn state(&self, id: &str) -> Option<SimulationState> {
+        let shared = self.find(&mut self.lock(), id)?;
+        Some(shared.snapshot(id))
+    }
+
+    /// Full frame of the simulation id after its latest sweep, None when there is none
+    pub fn frame(&self, id: &str) -> Option<Frame> {
+        let shared = self.find(&mut self.lock(), id)?;
+        let state = shared.state();
+        Some(Frame::full(&state.lattice, state.sweep, state.observables))
+    }
+
+    /// Observables of the simulation id with their fluctuations over its latest window sweeps,
+    /// None when there is none
+    pub fn observables(&self, id: &str, window: usize) -> Option<WindowedObservables> {
+        let shared = self.find(&mut self.lock(), id)?;
+        let (mut observables, lattice) = {
+            let state = shared.state();
+            let recorder = &state.recorder;
+            let observables = WindowedObservables {
+                sweep: state.sweep,
+                window: recorder.samples.len().min(window),
+                energy: state.observables.energy,
+                magnetization: state.observables.magnetization,
+                susceptibility: recorder.susceptibility(&state.lattice, window),
+                heat_capacity: recorder.heat_capacity(&state.lattice, window),
+                acceptance: recorder.rolling_acceptance(window),
+                correlation_length: None,
+            };
+            (observables, state.lattice.clone())
+        };
+        // Fitted on a copy so the simulation keeps running meanwhile
+        let correlation = lattice.correlation_function(lattice.size / 2);
+        observables.correlation_length = correlation_length(&correlation);
+        Some(observables)
+    }
+
+    /// Parameters and copy of the lattice of the simulation id after its latest sweep,
+    /// None when there is none
+    pub fn lattice(&self, id: &str) -> Option<(SimulationParams, Lattice)> {
+        let shared = self.find(&mut self.lock(), id)?;
+        let state = shared.state();
+        Some((state.params, state.lattice.clone()))
+    }
+
+    /// Change the temperature, interactivity, field, or algorithm of the simulation id
+    /// from its next sweep on, None when there is no such simulation
+    pub fn update(&self, id: &str, update: ParamsUpdate) -> Option<SimulationInfo> {
+        let shared = self.find(&mut self.lock(), id)?;
+        {
+            let mut state = shared.state();
+            let params = &mut state.params;
+            params.temperature = update.temperature.unwrap_or(params.temperature);
+            params.interactivity = update.interactivity.unwrap_or(params.interactivity);
+            params.field = update.field.unwrap_or(params.field);
+            params.algorithm = update.algorithm.unwrap_or(params.algorithm);
+            let params = state.params;
+            params.apply(&mut state.lattice);
+            info!("Updating simulation {id} to {params:?}");
+        }
+        self.checkpoint(&self.lock(), id, &shared);
+        Some(shared.info(id))
+    }
+
+    /// Check that owner may change or stop the simulation id, running or stored
+    /// Administrators may change any simulation, other clients their own but those of rooms
+    pub fn authorize(&self, id: &str, owner: &str, admin: bool) -> Result<(), Denial> {
+        let running = self.lock().get(id).map(|shared| shared.owner.clone());
+        let started_by = match (running, &self.store) {
+            (Some(started_by), _) => started_by,
+            (None, Some(store)) => match store.get(id) {
+                Ok(Some(stored)) => stored.owner,
+                Ok(None) => return Err(Denial::Missing),
+                Err(e) => {
+                    warn!("Failed to read simulation {id} from the store. Error {e}");
+                    return Err(Denial::Missing);
+                }
+            },
+            (None, None) => return Err(Denial::Missing),
+        };
+        if admin {
+            Ok(())
+        } else if is_room_simulation(id) {
+            Err(Denial::Room)
+        } else if started_by != owner {
+            Err(Denial::NotOwner)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Stop the simulation id and disconnect its clients, return whether it was running
+    pub fn remove(&self, id: &str) -> bool {
+        let mut simulations = self.lock();
+        let mut removed = simulations.remove(id).is_some();
+        if let Some(store) = &self.store {
+            removed |= store.contains(id).unwrap_or_default();
+            if let Err(e) = store.remove(id) {
+                warn!("Failed to remove simulation {id} from the store. Error {e}");
+            }
+        }
+        if removed {
+            info!("Stopping simulation {id}, deleted");
+        }
+        removed
+    }
+
+    /// Receiver of the frames of the running simulation id, None when there is none
+    pub fn observe(&self, id: &str) -> Option<Receiver<Arc<Frame>>> {
+        let shared = self.find(&mut self.lock(), id)?;
+        Some(shared.frames.subscribe())
+    }
+
+    /// Full frame of the simulation id and a receiver of the diffs following it
+    /// An unknown id starts a simulation of owner with params, stopped after its last client
+    pub fn subscribe(
+        &self,
+        id: &str,
+        owner: &str,
+        params: SimulationParams,
+    ) -> Result<(Frame, Receiver<Arc<Frame>>), Refusal> {
+        // Subscribing under the registry lock keeps the task from stopping in between
+        let mut simulations = self.lock();
+        let shared = match self.find(&mut simulations, id) {
+            Some(shared) => shared,
+            None => {
+                self.admit(&simulations, owner, &params)?;
+                let shared = Shared::new(params, false, owner);
+                self.spawn(&mut simulations, id.to_string(), shared.clone());
+                shared
+            }
+        };
+        let state = shared.state();
+        let frame = Frame::full(&state.lattice, state.sweep, state.observables);
+        Ok((frame, shared.frames.subscribe()))
+    }
+
+    /// Register a simulation under id and run it on a new tokio task
+    fn spawn(&self, simulations: &mut Running, id: String, shared: Arc<Shared>) {
+        info!("Starting simulation {id} with {:?}", shared.state().params);
+        simulations.insert(id.clone(), shared.clone());
+        tokio::spawn(run(self.clone(), id, shared));
+    }
+
+    /// Time until the next sweep of the simulation id, None when its task stops
+    /// It stops once the simulation is deleted, unmanaged and without clients, or idle
+    /// for the TTL of the limits
+    fn keep_running(&self, id: &str, shared: &Arc<Shared>) -> Option<Duration> {
+        let mut simulations = self.lock();
+        if !simulations
+            .get(id)
+            .is_some_and(|registered| Arc::ptr_eq(registered, shared))
+        {
+            return None;
+        }
+        if !shared.managed && shared.frames.receiver_count() == 0 {
+            info!("Stopping simulation {id}, no client left");
+            simulations.remove(id);
+            return None;
+        }
+        let ttl = Duration::from_secs(self.limits().idle_ttl_secs);
+        if shared.managed && shared.is_idle(ttl) {
+            info!("Evicting simulation {id}, idle for {}s", ttl.as_secs());
+            self.checkpoint(&simulations, id, shared);
+            simulations.remove(id);
+            return None;
+        }
+        Some(self.period(&simulations, &shared.owner))
+    }
+}
+
+/// Run a sweep at the pace of its owner and broadcast its diff while the simulation is kept
+async fn run(manager: SimulationManager, id: String, shared: Arc<Shared>) {
+    let mut next = tokio::time::Instant::now();
+    loop {
+        tokio::time::sleep_until(next).await;
+        let Some(period) = manager.keep_running(&id, &shared) else {
+            return;
+        };
+        next = (next + period).max(tokio::time::Instant::now());
+        let mut state = shared.state();
+        let mut flips = Vec::new();
+        let algorithm = state.params.algorithm;
+        let flipped = state
+            .lattice
+            .sweep_with(algorithm, |x, y| flips.push((x, y)));
+        state.sweep += 1;
+        let State {
+            lattice, recorder, ..
+        } = &mut *state;
+        recorder.record(lattice, flipped);
+        let sample = recorder.last().copied().unwrap_or_default();
+        state.observables = Observables {
+            magnetization: sample.magnetization,
+            energy: sample.energy,
+            acceptance: sample.acceptance,
+        };
+        // Sent under the state lock so a new client gets every diff after its full frame
+        let _ = shared.frames.send(Arc::new(Frame::Diff {
+            sweep: state.sweep,
+            flips: net_flips(state.lattice.size, &flips),
+            observables: state.observables,
+        }));
+        let sweep = state.sweep;
+        drop(state);
+        if shared.managed && sweep.is_multiple_of(CHECKPOINT_EVERY) {
+            manager.checkpoint(&manager.lock(), &id, &shared);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use internal::algorithm::Algorithm;
+
+    #[tokio::test]
+    async fn test_clients_share_a_simulation_until_the_last_leaves() {
+        let manager = SimulationManager::default();
+        let params = SimulationParams {
+            size: 8,
+            ..Default::default()
+        };
+
+        let (first, mut frames) = manager.subscribe("demo", "ip:a", params).unwrap();
+        let (second, other) = manager.subscribe("demo", "ip:b", params).unwrap();
+        let diff = frames.recv().await.unwrap();
+
+        assert!(matches!(first, Frame::Full { size: 8, .. }));
+        assert!(matches!(second, Frame::Full { size: 8, .. }));
+        assert!(matches!(*diff, Frame::Diff { sweep: 1, .. }));
+        drop((frames, other));
+        tokio::time::sleep(3 * SWEEP_INTERVAL).await;
+        assert!(manager.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_active_simulations_show_their_owner_and_resources() {
+        let manager = SimulationManager::default();
+        let params = SimulationParams {
+            size: 8,
+            ..Default::default()
+        };
+        let created = manager.create("ip:a", params).unwrap();
+        let (_frame, _frames) = manager.subscribe("demo", "ip:b", params).unwrap();
+
+        let active = manager.active();
+
+        let find = |id: &str| active.iter().find(|simulation| simulation.info.id == id);
+        assert_eq!(active.len(), 2);
+        let demo = find("demo").unwrap();
+        assert_eq!((demo.owner.as_str(), demo.info.clients), ("ip:b", 1));
+        assert!(find(&created.id).unwrap().info.managed);
+        assert!(active
+            .iter()
+            .all(|simulation| simulation.memory_bytes > 64 * 4));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(active
+            .iter()
+            .all(|simulation| simulation.last_activity + 1 >= now));
+        assert!(manager.remove("demo"));
+        assert_eq!(manager.active().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_managed_simulations_run_until_deleted() {
+        let manager = SimulationManager::default();
+        let params = SimulationParams {
+            size: 8,
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let created = manager.create("ip:a", params).unwrap();
+        tokio::time::sleep(3 * SWEEP_INTERVAL).await;
+        let update = ParamsUpdate {
+            temperature: Some(1000.0),
+            algorithm: Some(Algorithm::Wolff),
+            ..Default::default()
+        };
+        let updated = manager.update(&created.id, update).unwrap();
+
+        assert_eq!(created.params, params);
+        assert!(created.managed);
+        assert!(updated.sweep > 0);
+        assert_eq!(updated.params.temperature, 1000.0);
+        assert_eq!(updated.params.algorithm, Algorithm::Wolff);
+        assert_eq!(updated.params.interactivity, params.interactivity);
+        assert!(manager.remove(&created.id));
+        assert!(!manager.remove(&created.id));
+        assert!(manager.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_only_owners_and_admins_change_simulations() {
+        let manager = SimulationManager::default();
+        let params = SimulationParams {
+            size: 8,
+            ..Default::default()
+        };
+        let created = manager.create("ip:a", params).unwrap();
+        let room = crate::rooms::simulation_id("lab");
+        let (_frame, _frames) = manager.subscribe(&room, "ip:a", params).unwrap();
+
+        assert_eq!(manager.authorize(&created.id, "ip:a", false), Ok(()));
+        assert_eq!(
+            manager.authorize(&created.id, "ip:b", false),
+            Err(Denial::NotOwner)
+        );
+        assert_eq!(manager.authorize(&created.id, "ip:b", true), Ok(()));
+        assert_eq!(manager.authorize(&room, "ip:a", false), Err(Denial::Room));
+        assert_eq!(manager.authorize(&room, "ip:b", true), Ok(()));
+        assert_eq!(
+            manager.authorize("missing", "ip:a", true),
+            Err(Denial::Missing)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_observables_are_computed_over_the_window() {
+        let manager = SimulationManager::default();
+        let params = SimulationParams {
+            size: 8,
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let created = manager.create("ip:a", params).unwrap();
+        tokio::time::sleep(4 * SWEEP_INTERVAL).await;
+        let observables = manager.observables(&created.id, 2).unwrap();
+
+        assert_eq!(observables.window, 2);
+        assert!(observables.sweep >= 2);
+        assert!((0.0..=1.0).contains(&observables.acceptance));
+        assert!(observables.susceptibility >= 0.0 && observables.heat_capacity >= 0.0);
+        assert!(observables.magnetization.abs() <= 1.0);
+        assert!(manager.observables("unknown", 2).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stored_simulations_resume_on_first_access() {
+        let store = Store::temporary().unwrap();
+        let manager = SimulationManager::new(Limits::default(), Some(store.clone()));
+        let params = SimulationParams {
+            size: 8,
+            ..Default::default()
+        };
+
+        let created = manager.create("ip:a", params).unwrap();
+        tokio::time::sleep(3 * SWEEP_INTERVAL).await;
+        let update = ParamsUpdate {
+            temperature: Some(1000.0),
+            ..Default::default()
+        };
+        manager.update(&created.id, update);
+        // A restart keeps the store and loses the running simulations
+        manager.lock().clear();
+        let checkpoint = store.get(&created.id).unwrap().unwrap();
+        let restarted = SimulationManager::new(Limits::default(), Some(store));
+        let listed = restarted.list();
+        let resumed = restarted.get(&created.id).unwrap();
+
+        assert!(checkpoint.sweep > 0);
+        assert_eq!(checkpoint.owner, "ip:a");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].sweep, checkpoint.sweep);
+        assert_eq!(listed[0].clients, 0);
+        assert_eq!(resumed.params, checkpoint.params);
+        assert_eq!(resumed.params.temperature, 1000.0);
+        assert!(resumed.sweep >= checkpoint.sweep);
+        assert!(restarted.remove(&created.id));
+        assert!(restarted.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_held_to_their_limits() {
+        let limits = Limits {
+            max_lattices: 2,
+            max_sites: 200,
+            max_sweeps_per_second: 10,
+            idle_ttl_secs: 3600,
+        };
+        let manager = SimulationManager::new(limits, None);
+        let params = |size| SimulationParams {
+            size,
+            ..Default::default()
+        };
+
+        let first = manager.create("ip:a", params(10));
+        let too_large = manager.create("ip:a", params(11));
+        let second = manager.create("ip:a", params(5));
+        let third = manager.create("ip:a", params(5));
+        let other = manager.create("ip:b", params(10));
+        let status = manager.status();
+
+        assert!(first.is_ok() && second.is_ok() && other.is_ok());
+        assert_eq!(too_large, Err(Refusal::Sites(200)));
+        assert_eq!(third, Err(Refusal::Lattices(2)));
+        assert_eq!(status.running, 3);
+        assert_eq!(status.clients[0].client, "ip:a");
+        assert_eq!(status.clients[0].lattices, 2);
+        assert_eq!(status.clients[0].sites, 125);
+        // Two simulations share the 10 sweeps per second of their client
+        assert!((status.clients[0].sweeps_per_second - 10.0).abs() < 1e-9);
+        assert!((status.clients[1].sweeps_per_second - 10.0).abs() < 1e-9);
+
+        // Raised limits admit the refused simulation, the running ones being kept
+        manager.set_limits(Limits {
+            max_lattices: 3,
+            ..limits
+        });
+        assert!(manager.create("ip:a", params(5)).is_ok());
+        assert_eq!(manager.status().running, 4);
+    }
+
+    #[tokio::test]
+    async fn test_idle_simulations_are_evicted_to_the_store() {
+        let limits = Limits {
+            idle_ttl_secs: 0,
+            ..Default::default()
+        };
+        let store = Store::temporary().unwrap();
+        let manager = SimulationManager::new(limits, Some(store));
+        let params = SimulationParams {
+            size: 8,
+            ..Default::default()
+        };
+
+        let created = manager.create("ip:a", params).unwrap();
+        tokio::time::sleep(3 * SWEEP_INTERVAL).await;
+        let evicted = manager.lock().is_empty();
+        let listed = manager.list();
+
+        assert!(evicted);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, created.id);
+        assert!(manager.get(&created.id).is_some());
+    }
+}
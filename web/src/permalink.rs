@@ -0,0 +1,137 @@
+/// Name of the meta tag carrying the permalink parameters to the GUI
+pub const META_NAME: &str = "ising-permalink";
+
+/// Name of the meta tag telling the GUI it is embedded in another site
+pub const EMBED_META_NAME: &str = "ising-embed";
+
+/// Simulation parameters of a shared link like `/?size=100&t=2.27&j=1&seed=42`,
+/// in the reduced units of the GUI presets
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct Permalink {
+    /// lattice side
+    pub size: Option<usize>,
+    /// reduced temperature k_B T / |J|
+    pub t: Option<f64>,
+    /// interaction strength in units of the presets' J, negative for an antiferromagnet
+    pub j: Option<f64>,
+    /// reduced external field h / |J|
+    pub h: Option<f64>,
+    /// seed of the random spins and updates
+    pub seed: Option<u64>,
+}
+
+impl Permalink {
+    /// Query string of the given parameters, non-finite numbers left out
+    pub fn query(&self) -> String {
+        let finite = |value: Option<f64>| value.filter(|value| value.is_finite());
+        [
+            ("size", self.size.map(|size| size.to_string())),
+            ("t", finite(self.t).map(|t| t.to_string())),
+            ("j", finite(self.j).map(|j| j.to_string())),
+            ("h", finite(self.h).map(|h| h.to_string())),
+            ("seed", self.seed.map(|seed| seed.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some(format!("{name}={}", value?)))
+        .collect::<Vec<_>>()
+        .join("&")
+    }
+
+    /// Page with the parameters in a meta tag closing its head, for the GUI to start from
+    /// The page is left as is without parameters
+    pub fn inject(&self, html: &str) -> String {
+        close_head(html, &self.meta())
+    }
+
+    /// Page embeddable in other sites, showing the lattice alone from the parameters
+    pub fn embed(&self, html: &str) -> String {
+        let meta = format!(
+            "{}<meta name=\"{EMBED_META_NAME}\" content=\"1\">\n",
+            self.meta()
+        );
+        close_head(html, &meta)
+    }
+
+    /// Meta tag of the parameters, empty without any
+    fn meta(&self) -> String {
+        let query = self.query();
+        if query.is_empty() {
+            return String::new();
+        }
+        // Only digits, letters, dots, and signs besides the escaped separators
+        format!(
+            "<meta name=\"{META_NAME}\" content=\"{}\">\n",
+            query.replace('&', "&amp;")
+        )
+    }
+}
+
+/// Page with the tags inserted right before the end of its head, as is without a head
+fn close_head(html: &str, tags: &str) -> String {
+    match html.find("</head>") {
+        Some(head) => format!("{}{tags}{}", &html[..head], &html[head..]),
+        None => html.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_query_keeps_the_given_finite_parameters() {
+        let permalink = Permalink {
+            size: Some(100),
+            t: Some(2.27),
+            j: Some(f64::NAN),
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let result = permalink.query();
+
+        assert_eq!(result, "size=100&t=2.27&seed=42");
+    }
+
+    #[test]
+    fn test_inject_closes_the_head_with_a_meta_tag() {
+        let permalink = Permalink {
+            t: Some(2.27),
+            j: Some(1.0),
+            ..Default::default()
+        };
+        let html = "<html><head><title>R-Ising</title></head><body></body></html>";
+
+        let result = permalink.inject(html);
+
+        assert_eq!(
+            result,
+            "<html><head><title>R-Ising</title>\
+             <meta name=\"ising-permalink\" content=\"t=2.27&amp;j=1\">\n\
+             </head><body></body></html>"
+        );
+        assert_eq!(Permalink::default().inject(html), html);
+    }
+
+    #[test]
+    fn test_embed_marks_the_page_embedded() {
+        let permalink = Permalink {
+            size: Some(50),
+            ..Default::default()
+        };
+        let html = "<html><head></head><body></body></html>";
+
+        let result = permalink.embed(html);
+
+        assert_eq!(
+            result,
+            "<html><head>\
+             <meta name=\"ising-permalink\" content=\"size=50\">\n\
+             <meta name=\"ising-embed\" content=\"1\">\n\
+             </head><body></body></html>"
+        );
+        assert!(Permalink::default()
+            .embed(html)
+            .contains("<meta name=\"ising-embed\""));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::jobs::INTERACTIVITY;
+use crate::results::{Measurement, Results};
+use crate::store::Store;
+use internal::{
+    algorithm::Algorithm,
+    scan::{self, ScanPoint},
+    Lattice,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+/// Lattice sides of the phase diagram
+pub const SIZES: [usize; 4] = [8, 16, 32, 64];
+
+/// Lowest reduced temperature of the phase diagram
+pub const MIN_TEMPERATURE: f64 = 0.5;
+
+/// Reduced temperature between two points of the phase diagram
+pub const TEMPERATURE_STEP: f64 = 0.05;
+
+/// Number of temperatures of the phase diagram, from 0.5 to 5.0
+pub const TEMPERATURES: usize = 91;
+
+/// Sweeps run before measuring a point
+pub const EQUILIBRATION: usize = 1000;
+
+/// Sweeps averaged at every point
+pub const SWEEPS: usize = 2000;
+
+/// Number of points measured at once
+const WORKERS: usize = 2;
+
+/// Update of the measured lattices, fastest to decorrelate near the critical temperature
+const ALGORITHM: Algorithm = Algorithm::Wolff;
+
+/// Seed of every measured lattice, so points are reproducible
+const SEED: u64 = 42;
+
+/// Reduced temperature of the point at index
+pub fn temperature(index: usize) -> f64 {
+    ((MIN_TEMPERATURE + index as f64 * TEMPERATURE_STEP) * 100.0).round() / 100.0
+}
+
+/// Query of the phase diagram of a lattice size over a range of reduced temperatures
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct PhaseQuery {
+    /// lattice side, one of SIZES
+    pub size: usize,
+    /// lowest reduced temperature k_B T / |J|
+    pub start: f64,
+    /// highest reduced temperature
+    pub end: f64,
+}
+
+impl Default for PhaseQuery {
+    fn default() -> Self {
+        Self {
+            size: 32,
+            start: 1.5,
+            end: 3.5,
+        }
+    }
+}
+
+impl PhaseQuery {
+    /// Reason the query can't be answered, None when it can
+    pub fn invalid(&self) -> Option<String> {
+        if !SIZES.contains(&self.size) {
+            Some(format!("Expected a size among {SIZES:?}"))
+        } else if !self.start.is_finite() || !self.end.is_finite() || self.start > self.end {
+            Some("Expected finite temperatures with start up to end".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Indices of the temperatures of the grid from start to end, both included
+    fn indices(&self) -> std::ops::Range<usize> {
+        // Rounding to the grid first keeps temperatures like 2.05 on their point
+        let index = |t: f64| ((t - MIN_TEMPERATURE) / TEMPERATURE_STEP * 1e6).round() / 1e6;
+        let first = index(self.start).ceil().max(0.0) as usize;
+        let last = index(self.end).floor().min((TEMPERATURES - 1) as f64);
+        if last < 0.0 {
+            return 0..0;
+        }
+        first..last as usize + 1
+    }
+}
+
+/// Observables of a lattice size over a range of reduced temperatures, as served by the API
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct PhaseDiagram {
+    pub size: usize,
+    /// points measured so far, by increasing temperature
+    pub points: Vec<ScanPoint>,
+    /// points of the range still being measured, fetch again once they are done
+    pub pending: usize,
+}
+
+/// Points measured and being measured, by size and temperature index
+#[derive(Default)]
+struct Points {
+    measured: BTreeMap<(usize, usize), ScanPoint>,
+    pending: BTreeSet<(usize, usize)>,
+}
+
+/// Phase diagram points measured on demand and kept, in the store when there is one
+/// New points are recorded to the results
+#[derive(Clone)]
+pub struct PhaseCache {
+    points: Arc<Mutex<Points>>,
+    workers: Arc<Semaphore>,
+    store: Option<Store>,
+    results: Results,
+}
+
+impl Default for PhaseCache {
+    fn default() -> Self {
+        Self::new(None, Results::default())
+    }
+}
+
+impl PhaseCache {
+    /// Cache starting from the points of the store, when there is one, recording to results
+    pub fn new(store: Option<Store>, results: Results) -> Self {
+        let mut points = Points::default();
+        if let Some(store) = &store {
+            match store.phase_points() {
+                Ok(stored) => {
+                    info!("Loaded {} phase diagram points", stored.len());
+                    points.measured = stored
+                        .into_iter()
+                        .map(|(size, index, point)| ((size, index), point))
+                        .collect();
+                }
+                Err(e) => warn!("Failed to load the phase diagram points. Error {e}"),
+            }
+        }
+        Self {
+            points: Arc::new(Mutex::new(points)),
+            workers: Arc::new(Semaphore::new(WORKERS)),
+            store,
+            results,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Points> {
+        self.points.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Points of a valid query measured so far, queueing the missing ones
+    pub fn get(&self, query: PhaseQuery) -> PhaseDiagram {
+        let mut points = self.lock();
+        let mut measured = Vec::new();
+        let mut pending = 0;
+        for index in query.indices() {
+            let key = (query.size, index);
+            if let Some(point) = points.measured.get(&key) {
+                measured.push(*point);
+                continue;
+            }
+            pending += 1;
+            if points.pending.insert(key) {
+                tokio::spawn(self.clone().measure(query.size, index));
+            }
+        }
+        PhaseDiagram {
+            size: query.size,
+            points: measured,
+            pending,
+        }
+    }
+
+    /// Measure the point of size at the temperature index on a worker, then keep it
+    async fn measure(self, size: usize, index: usize) {
+        let _worker = self.workers.acquire().await.expect("Worker pool closed");
+        let point = tokio::task::spawn_blocking(move || {
+            let mut lattice = Lattice::with_seed(size, INTERACTIVITY, 0.0, SEED);
+            lattice.set_reduced_temperature(temperature(index));
+            ScanPoint {
+                reduced_temperature: temperature(index),
+                ..scan::measure(&mut lattice, ALGORITHM, EQUILIBRATION, SWEEPS)
+            }
+        })
+        .await;
+        let mut points = self.lock();
+        points.pending.remove(&(size, index));
+        let point = match point {
+            Ok(point) => point,
+            Err(e) => {
+                warn!("Failed to measure the phase diagram of size {size} at {index}. Error {e}");
+                return;
+            }
+        };
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_phase_point(size, index, &point) {
+                warn!("Failed to store the phase diagram point of size {size}. Error {e}");
+            }
+        }
+        points.measured.insert((size, index), point);
+        self.results.record([Measurement {
+            algorithm: ALGORITHM,
+            equilibration: EQUILIBRATION,
+            sweeps: SWEEPS,
+            seed: SEED,
+            ..Measurement::new(size, point, "phase-diagram")
+        }]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::results::ResultsQuery;
+    use std::time::Duration;
+
+    #[test]
+    fn test_queries_cover_the_grid_between_their_temperatures() {
+        let query = |start, end| PhaseQuery {
+            size: 8,
+            start,
+            end,
+        };
+
+        assert_eq!(query(2.0, 2.1).indices(), 30..33);
+        assert_eq!(query(0.0, 0.52).indices(), 0..1);
+        assert_eq!(query(4.99, 9.0).indices(), 90..91);
+        assert!(query(0.1, 0.2).indices().is_empty());
+        assert_eq!(temperature(31), 2.05);
+        assert!(query(2.0, 2.1).invalid().is_none());
+        assert!(PhaseQuery {
+            size: 10,
+            ..query(2.0, 2.1)
+        }
+        .invalid()
+        .is_some());
+        assert!(query(3.0, 2.0).invalid().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_points_are_measured_once_and_stored() {
+        let store = Store::temporary().unwrap();
+        let results = Results::default();
+        let cache = PhaseCache::new(Some(store.clone()), results.clone());
+        let query = PhaseQuery {
+            size: 8,
+            start: 2.0,
+            end: 2.1,
+        };
+
+        let first = cache.get(query);
+        let mut diagram = first.clone();
+        for _ in 0..500 {
+            diagram = cache.get(query);
+            if diagram.pending == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let reloaded = PhaseCache::new(Some(store), Results::default()).get(query);
+
+        assert_eq!((first.points.len(), first.pending), (0, 3));
+        assert_eq!(diagram.pending, 0);
+        let temperatures: Vec<f64> = diagram
+            .points
+            .iter()
+            .map(|point| point.reduced_temperature)
+            .collect();
+        assert_eq!(temperatures, vec![2.0, 2.05, 2.1]);
+        assert_eq!(reloaded, diagram);
+        let recorded = results.query(&ResultsQuery::default()).unwrap();
+        assert_eq!(recorded.total, 3);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::auth::KeyName;
+use crate::config::Config;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Header carrying the API key of a request
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Number of clients tracked before the least recently seen is forgotten
+const MAX_CLIENTS: usize = 10_000;
+
+/// Requests left to a client and the ones it has in flight
+#[derive(Clone, Copy, Debug)]
+struct Client {
+    /// requests the client may still send at once, refilled over time up to the burst
+    tokens: f64,
+    /// time tokens were last refilled
+    refilled: Instant,
+    /// requests of the client being handled
+    in_flight: u32,
+    /// number of the latest request of the client, see `Clients`
+    seen: u64,
+}
+
+/// Clients by key, up to MAX_CLIENTS
+#[derive(Debug, Default)]
+struct Clients {
+    by_key: HashMap<String, Client>,
+    /// keys by the number of the latest request of their client, the least recent first
+    by_seen: BTreeMap<u64, String>,
+    /// requests seen
+    seen: u64,
+}
+
+impl Clients {
+    /// Client of a key seeing a request, forgetting the least recently seen client
+    /// to make room for a new one
+    fn see(&mut self, key: &str, new: impl FnOnce() -> Client) -> &mut Client {
+        self.seen += 1;
+        match self.by_key.get(key) {
+            Some(client) => {
+                self.by_seen.remove(&client.seen);
+            }
+            None if self.by_key.len() >= MAX_CLIENTS => {
+                if let Some((_, oldest)) = self.by_seen.pop_first() {
+                    self.by_key.remove(&oldest);
+                }
+            }
+            None => {}
+        }
+        self.by_seen.insert(self.seen, key.to_string());
+        let client = self.by_key.entry(key.to_string()).or_insert_with(new);
+        client.seen = self.seen;
+        client
+    }
+}
+
+/// Limits of every client
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Settings {
+    /// tokens refilled per second
+    rate: f64,
+    burst: u32,
+    max_concurrent: u32,
+}
+
+impl Settings {
+    fn new(config: &Config) -> Self {
+        Self {
+            rate: f64::from(config.rate_limit_per_minute.max(1)) / 60.0,
+            burst: config.rate_limit_burst.max(1),
+            max_concurrent: config.max_concurrent_requests.max(1),
+        }
+    }
+}
+
+/// Token bucket rate limit and concurrency cap of every client,
+/// by authenticated API key name or IP address
+#[derive(Clone)]
+pub struct RateLimiter {
+    /// limits, changed when the config is reloaded
+    settings: Arc<Mutex<Settings>>,
+    clients: Arc<Mutex<Clients>>,
+}
+
+/// Request counted in flight until dropped
+pub struct Permit {
+    limiter: RateLimiter,
+    key: String,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(client) = self.limiter.lock().by_key.get_mut(&self.key) {
+            client.in_flight = client.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Limiter of the compute endpoints with the limits of a config
+    pub fn new(config: &Config) -> Self {
+        Self {
+            settings: Arc::new(Mutex::new(Settings::new(config))),
+            clients: Arc::default(),
+        }
+    }
+
+    /// Apply the limits of a reloaded config from now on, keeping the buckets of the clients
+    pub fn reconfigure(&self, config: &Config) {
+        *self.settings.lock().unwrap_or_else(|e| e.into_inner()) = Settings::new(config);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Clients> {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take a token of the client key at now and count its request in flight
+    /// Err with the time to wait before retrying when it is limited
+    pub fn acquire(&self, key: &str, now: Instant) -> Result<Permit, Duration> {
+        let settings = *self.settings.lock().unwrap_or_else(|e| e.into_inner());
+        let mut clients = self.lock();
+        let client = clients.see(key, || Client {
+            tokens: f64::from(settings.burst),
+            refilled: now,
+            in_flight: 0,
+            seen: 0,
+        });
+        let elapsed = now.saturating_duration_since(client.refilled).as_secs_f64();
+        client.tokens = (client.tokens + elapsed * settings.rate).min(f64::from(settings.burst));
+        client.refilled = now;
+        if client.in_flight >= settings.max_concurrent {
+            return Err(Duration::from_secs(1));
+        }
+        if client.tokens < 1.0 {
+            return Err(Duration::from_secs_f64(
+                (1.0 - client.tokens) / settings.rate,
+            ));
+        }
+        client.tokens -= 1.0;
+        client.in_flight += 1;
+        Ok(Permit {
+            limiter: self.clone(),
+            key: key.to_string(),
+        })
+    }
+}
+
+/// Name of the API key the request was authenticated with, or else the IP address it
+/// comes from, unverified keys being ignored so changing them doesn't reset the limits
+fn client_key(request: &Request) -> String {
+    if let Some(KeyName(name)) = request.extensions().get::<KeyName>() {
+        return format!("key:{name}");
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(address)) => format!("ip:{}", address.ip()),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Middleware answering 429 Too Many Requests with a Retry-After header to limited clients
+/// Layered inside `auth::require_key` to limit clients by the name of their API key
+pub async fn limit(
+    State(limiter): State<RateLimiter>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let key = client_key(&request);
+    match limiter.acquire(&key, Instant::now()) {
+        Ok(permit) => {
+            // Shared with the handler, so an upgraded WebSocket stays in flight until it closes
+            let permit = Arc::new(permit);
+            request.extensions_mut().insert(permit.clone());
+            let response = next.run(request).await;
+            drop(permit);
+            response
+        }
+        Err(retry_after) => {
+            warn!("Rate limiting {key} for {}ms", retry_after.as_millis());
+            // Whole seconds, rounded up so retrying right on time succeeds
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, HeaderValue::from(seconds))],
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::auth::{self, ApiKeys};
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn limiter(per_minute: u32, burst: u32, max_concurrent: u32) -> RateLimiter {
+        RateLimiter::new(&Config {
+            rate_limit_per_minute: per_minute,
+            rate_limit_burst: burst,
+            max_concurrent_requests: max_concurrent,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_bucket_refills_at_the_rate() {
+        let limiter = limiter(60, 2, 10);
+        let now = Instant::now();
+
+        let first = limiter.acquire("a", now);
+        let second = limiter.acquire("a", now);
+        let limited = limiter.acquire("a", now);
+        let other = limiter.acquire("b", now);
+        let refilled = limiter.acquire("a", now + Duration::from_secs(1));
+
+        assert!(first.is_ok() && second.is_ok() && other.is_ok() && refilled.is_ok());
+        assert_eq!(limited.err(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_concurrent_requests_are_capped() {
+        let limiter = limiter(60, 10, 1);
+        let now = Instant::now();
+
+        let permit = limiter.acquire("a", now).unwrap();
+        let limited = limiter.acquire("a", now);
+        drop(permit);
+        let released = limiter.acquire("a", now);
+
+        assert!(limited.is_err());
+        assert!(released.is_ok());
+    }
+
+    #[test]
+    fn test_reloaded_limits_apply_to_the_next_requests() {
+        let limiter = limiter(60, 1, 10);
+        let now = Instant::now();
+
+        let first = limiter.acquire("a", now);
+        let limited = limiter.acquire("a", now);
+        limiter.reconfigure(&Config {
+            rate_limit_per_minute: 120,
+            ..Default::default()
+        });
+        let retry_after = limiter.acquire("a", now).err();
+
+        assert!(first.is_ok() && limited.is_err());
+        assert_eq!(retry_after, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_least_recently_seen_clients_are_forgotten() {
+        let limiter = limiter(60, 1, 10);
+        let now = Instant::now();
+
+        let _ = limiter.acquire("a", now);
+        for client in 1..MAX_CLIENTS {
+            let _ = limiter.acquire(&client.to_string(), now);
+        }
+        let limited = limiter.acquire("a", now);
+        let _ = limiter.acquire("new", now);
+        let still_limited = limiter.acquire("a", now);
+        let forgotten = limiter.acquire("1", now);
+
+        assert!(limited.is_err() && still_limited.is_err());
+        assert!(forgotten.is_ok());
+        assert_eq!(limiter.lock().by_key.len(), MAX_CLIENTS);
+    }
+
+    #[tokio::test]
+    async fn test_unverified_api_keys_share_the_bucket_of_their_address() {
+        let limiter = limiter(60, 2, 10);
+        let app = Router::new()
+            .route("/api/phase-diagram", get(|| async { "diagram" }))
+            .layer(middleware::from_fn_with_state(limiter, limit))
+            .layer(middleware::from_fn_with_state(
+                ApiKeys::default(),
+                auth::require_key,
+            ));
+        let request = |key: &str| {
+            let mut request = Request::get("/api/phase-diagram")
+                .header(API_KEY_HEADER, key)
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+            request
+        };
+
+        let mut statuses = Vec::new();
+        for key in ["k1", "k2", "k3"] {
+            statuses.push(app.clone().oneshot(request(key)).await.unwrap().status());
+        }
+
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS
+            ]
+        );
+    }
+    #[tokio::test]
+    async fn test_handlers_holding_the_permit_stay_in_flight() {
+        let limiter = limiter(600, 10, 1);
+        let held = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route(
+                "/ws/simulations/a",
+                get({
+                    let held = held.clone();
+                    move |axum::Extension(permit): axum::Extension<Arc<Permit>>| async move {
+                        // Like a WebSocket moving its permit into the upgraded connection
+                        held.lock().unwrap().push(permit);
+                        "upgraded"
+                    }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(limiter, limit));
+        let request = || {
+            let mut request = Request::get("/ws/simulations/a")
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+            request
+        };
+
+        let open = app.clone().oneshot(request()).await.unwrap().status();
+        let while_open = app.clone().oneshot(request()).await.unwrap().status();
+        held.lock().unwrap().clear();
+        let after_close = app.oneshot(request()).await.unwrap().status();
+
+        assert_eq!(open, StatusCode::OK);
+        assert_eq!(while_open, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(after_close, StatusCode::OK);
+    }
+}
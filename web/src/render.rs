@@ -0,0 +1,135 @@
+use internal::{algorithm::Algorithm, animation::Animation, export, Lattice};
+use std::io;
+
+/// Largest number of pixels per spin side of a rendered image
+pub const MAX_SCALE: usize = 16;
+
+/// Frames shown per second in a rendered animation
+pub const FRAME_RATE: f64 = 20.0;
+
+/// Upper bound on the memory taken by the frames of a rendered animation
+pub const MAX_ANIMATION_BYTES: usize = 32 * 1024 * 1024;
+
+/// Query of the PNG snapshot of a simulation
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct SnapshotParams {
+    /// pixels per spin side, clamped to 1..=MAX_SCALE
+    pub scale: usize,
+}
+
+impl Default for SnapshotParams {
+    fn default() -> Self {
+        Self { scale: 4 }
+    }
+}
+
+/// Query of the animated GIF of a simulation
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct AnimationParams {
+    /// sweeps run from the current spins
+    pub sweeps: usize,
+    /// sweeps between two frames
+    pub stride: usize,
+    /// pixels per spin side, clamped to 1..=MAX_SCALE
+    pub scale: usize,
+}
+
+impl Default for AnimationParams {
+    fn default() -> Self {
+        Self {
+            sweeps: 500,
+            stride: 5,
+            scale: 4,
+        }
+    }
+}
+
+impl AnimationParams {
+    /// Reason the animation can't be rendered within max_sweeps, None when it can
+    pub fn invalid(&self, max_sweeps: usize) -> Option<String> {
+        if !(1..=max_sweeps).contains(&self.sweeps) {
+            Some(format!("Expected 1 to {max_sweeps} sweeps"))
+        } else if self.stride == 0 {
+            Some("Expected a stride of at least 1 sweep".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// Render a lattice as a PNG image with the core exporter
+/// The image embeds a snapshot of the lattice, so the GUI can load it back
+pub fn png(lattice: &Lattice, params: SnapshotParams) -> io::Result<Vec<u8>> {
+    export::to_png(lattice, params.scale.clamp(1, MAX_SCALE))
+}
+
+/// Render the evolution of a lattice over the sweeps of params as an animated GIF,
+/// a frame every stride sweeps from the current spins
+/// Frames past MAX_ANIMATION_BYTES are left out
+pub fn gif(
+    mut lattice: Lattice,
+    algorithm: Algorithm,
+    params: AnimationParams,
+) -> io::Result<Vec<u8>> {
+    let scale = params.scale.clamp(1, MAX_SCALE);
+    let mut animation = Animation::new(lattice.size, scale, FRAME_RATE, MAX_ANIMATION_BYTES);
+    animation.push(&lattice);
+    for sweep in 1..=params.sweeps {
+        lattice.sweep_with(algorithm, |_, _| {});
+        if sweep.is_multiple_of(params.stride.max(1)) && !animation.push(&lattice) {
+            break;
+        }
+    }
+    animation.to_gif()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_png_scale_is_clamped() {
+        let lattice = Lattice::with_seed(5, 1.0, 1.0, 42);
+        // Width in the header chunk following the 8 bytes signature
+        let width = |png: &[u8]| u32::from_be_bytes(png[16..20].try_into().unwrap());
+
+        let scaled = png(&lattice, SnapshotParams { scale: 3 }).unwrap();
+        let clamped = png(&lattice, SnapshotParams { scale: 1000 }).unwrap();
+        let zero = png(&lattice, SnapshotParams { scale: 0 }).unwrap();
+
+        assert!(scaled.starts_with(b"\x89PNG"));
+        assert_eq!(width(&scaled), 15);
+        assert_eq!(width(&clamped), 5 * MAX_SCALE as u32);
+        assert_eq!(width(&zero), 5);
+    }
+
+    #[test]
+    fn test_gif_runs_the_requested_sweeps() {
+        let lattice = Lattice::with_seed(5, 1.0, 1.0, 42);
+        let params = AnimationParams {
+            sweeps: 10,
+            stride: 5,
+            scale: 2,
+        };
+        let invalid = |sweeps, stride| {
+            AnimationParams {
+                sweeps,
+                stride,
+                ..params
+            }
+            .invalid(100)
+        };
+
+        let animated = gif(lattice, Algorithm::Metropolis, params).unwrap();
+
+        assert!(animated.starts_with(b"GIF89a"));
+        // Logical screen width following the 6 bytes signature
+        assert_eq!(u16::from_le_bytes([animated[6], animated[7]]), 10);
+        assert_eq!(invalid(10, 5), None);
+        assert!(invalid(0, 5).is_some());
+        assert!(invalid(101, 5).is_some());
+        assert!(invalid(10, 0).is_some());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::store::Store;
+use internal::{algorithm::Algorithm, scan::ScanPoint};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Model of every measurement, the square lattice Ising model
+pub const MODEL: &str = "ising2d";
+
+/// Measurements kept without a store, the oldest dropped first to make room
+pub const MAX_MEMORY_RESULTS: usize = 10_000;
+
+/// Largest page of a results query
+pub const MAX_LIMIT: usize = 1000;
+
+/// Observables measured at a lattice size and temperature, with how they were measured
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Measurement {
+    /// number of the measurement, increasing in the order they were recorded
+    pub id: u64,
+    pub model: String,
+    /// lattice side
+    pub size: usize,
+    #[serde(flatten)]
+    pub point: ScanPoint,
+    /// Monte Carlo update of the measured lattice
+    pub algorithm: Algorithm,
+    /// sweeps run before measuring
+    pub equilibration: usize,
+    /// sweeps averaged
+    pub sweeps: usize,
+    /// seed of the measured lattice
+    pub seed: u64,
+    /// what measured it, like `job:<id>` or `phase-diagram`
+    pub source: String,
+    /// Unix time in seconds of the measurement
+    pub measured_at: u64,
+}
+
+impl Measurement {
+    /// Measurement of a lattice of size made now by source, numbered once recorded
+    pub fn new(size: usize, point: ScanPoint, source: &str) -> Self {
+        let measured_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        Self {
+            id: 0,
+            model: MODEL.to_string(),
+            size,
+            point,
+            algorithm: Algorithm::default(),
+            equilibration: 0,
+            sweeps: 0,
+            seed: 0,
+            source: source.to_string(),
+            measured_at,
+        }
+    }
+}
+
+/// Field results are sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// reduced temperature, then size
+    #[default]
+    Temperature,
+    /// size, then reduced temperature
+    Size,
+    /// time of the measurement
+    MeasuredAt,
+}
+
+/// Direction of the sort
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query of the results like `?model=ising2d&size=64&t_min=2&t_max=2.5&offset=100&limit=100`
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct ResultsQuery {
+    pub model: String,
+    /// lattice side, any when absent
+    pub size: Option<usize>,
+    /// lowest reduced temperature, included
+    pub t_min: Option<f64>,
+    /// highest reduced temperature, included
+    pub t_max: Option<f64>,
+    pub sort: SortKey,
+    pub order: Order,
+    /// number of matching results skipped
+    pub offset: usize,
+    /// number of results of the page, up to MAX_LIMIT
+    pub limit: usize,
+}
+
+impl Default for ResultsQuery {
+    fn default() -> Self {
+        Self {
+            model: MODEL.to_string(),
+            size: None,
+            t_min: None,
+            t_max: None,
+            sort: SortKey::default(),
+            order: Order::default(),
+            offset: 0,
+            limit: 100,
+        }
+    }
+}
+
+impl ResultsQuery {
+    /// Reason the query can't be answered, None when it can
+    pub fn invalid(&self) -> Option<String> {
+        let t_min = self.t_min.unwrap_or(f64::NEG_INFINITY);
+        let t_max = self.t_max.unwrap_or(f64::INFINITY);
+        if !(1..=MAX_LIMIT).contains(&self.limit) {
+            Some(format!("Expected a limit of 1 to {MAX_LIMIT}"))
+        } else if t_min.is_nan() || t_max.is_nan() || t_min > t_max {
+            Some("Expected temperatures with t_min up to t_max".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, measurement: &Measurement) -> bool {
+        let t = measurement.point.reduced_temperature;
+        measurement.model == self.model
+            && self.size.is_none_or(|size| size == measurement.size)
+            && self.t_min.is_none_or(|t_min| t >= t_min)
+            && self.t_max.is_none_or(|t_max| t <= t_max)
+    }
+
+    fn compare(&self, a: &Measurement, b: &Measurement) -> Ordering {
+        let (ta, tb) = (a.point.reduced_temperature, b.point.reduced_temperature);
+        let ordering = match self.sort {
+            SortKey::Temperature => ta.total_cmp(&tb).then(a.size.cmp(&b.size)),
+            SortKey::Size => a.size.cmp(&b.size).then(ta.total_cmp(&tb)),
+            SortKey::MeasuredAt => a.measured_at.cmp(&b.measured_at),
+        }
+        .then(a.id.cmp(&b.id));
+        match self.order {
+            Order::Asc => ordering,
+            Order::Desc => ordering.reverse(),
+        }
+    }
+}
+
+/// Page of the results matching a query
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct ResultsPage {
+    /// number of matching results, over every page
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub results: Vec<Measurement>,
+}
+
+/// Measurements in memory, when there is no store
+#[derive(Default)]
+struct Memory {
+    measurements: VecDeque<Measurement>,
+    recorded: u64,
+}
+
+/// Every measurement of the finished jobs and the phase diagram, building up a results
+/// database in the store, or in memory until a restart without one
+#[derive(Clone, Default)]
+pub struct Results {
+    memory: Arc<Mutex<Memory>>,
+    store: Option<Store>,
+}
+
+impl Results {
+    /// Results kept in the store, when there is one
+    pub fn new(store: Option<Store>) -> Self {
+        Self {
+            memory: Arc::default(),
+            store,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Memory> {
+        self.memory.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Keep measurements, numbering them in order
+    pub fn record(&self, measurements: impl IntoIterator<Item = Measurement>) {
+        if let Some(store) = &self.store {
+            for measurement in measurements {
+                if let Err(e) = store.save_result(measurement) {
+                    warn!("Failed to store a measurement. Error {e}");
+                }
+            }
+            return;
+        }
+        let mut memory = self.lock();
+        for mut measurement in measurements {
+            memory.recorded += 1;
+            measurement.id = memory.recorded;
+            if memory.measurements.len() >= MAX_MEMORY_RESULTS {
+                memory.measurements.pop_front();
+            }
+            memory.measurements.push_back(measurement);
+        }
+    }
+
+    /// Page of the measurements matching a valid query
+    pub fn query(&self, query: &ResultsQuery) -> io::Result<ResultsPage> {
+        let mut matching: Vec<Measurement> = match &self.store {
+            Some(store) => store
+                .results()?
+                .into_iter()
+                .filter(|measurement| query.matches(measurement))
+                .collect(),
+            None => self
+                .lock()
+                .measurements
+                .iter()
+                .filter(|measurement| query.matches(measurement))
+                .cloned()
+                .collect(),
+        };
+        matching.sort_by(|a, b| query.compare(a, b));
+        Ok(ResultsPage {
+            total: matching.len(),
+            offset: query.offset,
+            limit: query.limit,
+            results: matching
+                .into_iter()
+                .skip(query.offset)
+                .take(query.limit)
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn measurement(size: usize, reduced_temperature: f64) -> Measurement {
+        let point = ScanPoint {
+            reduced_temperature,
+            ..Default::default()
+        };
+        Measurement::new(size, point, "test")
+    }
+
+    fn temperatures(page: &ResultsPage) -> Vec<(usize, f64)> {
+        page.results
+            .iter()
+            .map(|result| (result.size, result.point.reduced_temperature))
+            .collect()
+    }
+
+    #[test]
+    fn test_queries_filter_sort_and_page_the_results() {
+        let results = Results::default();
+        results.record([
+            measurement(64, 2.3),
+            measurement(64, 1.5),
+            measurement(32, 2.3),
+            measurement(64, 2.0),
+            measurement(64, 2.5),
+        ]);
+        let query = ResultsQuery {
+            size: Some(64),
+            t_min: Some(2.0),
+            t_max: Some(2.5),
+            ..Default::default()
+        };
+
+        let ascending = results.query(&query).unwrap();
+        let descending = results
+            .query(&ResultsQuery {
+                order: Order::Desc,
+                offset: 1,
+                limit: 1,
+                ..query.clone()
+            })
+            .unwrap();
+        let by_size = results
+            .query(&ResultsQuery {
+                sort: SortKey::Size,
+                t_min: Some(2.3),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(ascending.total, 3);
+        assert_eq!(
+            temperatures(&ascending),
+            vec![(64, 2.0), (64, 2.3), (64, 2.5)]
+        );
+        assert_eq!(descending.total, 3);
+        assert_eq!(temperatures(&descending), vec![(64, 2.3)]);
+        assert_eq!(
+            temperatures(&by_size),
+            vec![(32, 2.3), (64, 2.3), (64, 2.5)]
+        );
+        let other = ResultsQuery {
+            model: "potts".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(results.query(&other).unwrap().total, 0);
+    }
+
+    #[test]
+    fn test_stored_results_are_numbered_in_order() {
+        let store = Store::temporary().unwrap();
+        Results::new(Some(store.clone())).record([measurement(8, 2.0), measurement(8, 1.0)]);
+
+        let page = Results::new(Some(store))
+            .query(&ResultsQuery {
+                sort: SortKey::MeasuredAt,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(temperatures(&page), vec![(8, 2.0), (8, 1.0)]);
+        assert!(page.results[0].id < page.results[1].id);
+    }
+
+    #[test]
+    fn test_invalid_queries_are_refused() {
+        let query = |t_min, t_max, limit| ResultsQuery {
+            t_min,
+            t_max,
+            limit,
+            ..Default::default()
+        };
+
+        assert_eq!(query(Some(2.0), Some(2.5), 100).invalid(), None);
+        assert!(query(Some(2.5), Some(2.0), 100).invalid().is_some());
+        assert!(query(Some(f64::NAN), None, 100).invalid().is_some());
+        assert!(query(None, None, 0).invalid().is_some());
+        assert!(query(None, None, MAX_LIMIT + 1).invalid().is_some());
+    }
+}
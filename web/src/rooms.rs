@@ -0,0 +1,391 @@
+use crate::manager::SimulationManager;
+use crate::stream::{self, ParamsUpdate, SimulationParams};
+use crate::wire::{Encoding, FrameEncoder};
+use axum::extract::ws::{Message, WebSocket};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+use tracing::{info, warn};
+
+/// Longest room name, made of ASCII letters, digits, '-', and '_'
+pub const MAX_ROOM_NAME: usize = 64;
+
+/// Longest participant name shown to the others, longer ones are cut
+pub const MAX_PARTICIPANT_NAME: usize = 32;
+
+/// Number of room events buffered for a slow participant
+const EVENT_CAPACITY: usize = 16;
+
+/// Query of a participant joining a room
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct Participant {
+    /// name shown to the other participants, "guest" when absent
+    pub name: Option<String>,
+}
+
+impl Participant {
+    /// Trimmed name of the participant, cut to MAX_PARTICIPANT_NAME characters
+    pub fn display_name(&self) -> String {
+        let name = self.name.as_deref().map(str::trim).unwrap_or_default();
+        match name {
+            "" => "guest".to_string(),
+            name => name.chars().take(MAX_PARTICIPANT_NAME).collect(),
+        }
+    }
+}
+
+/// Message of a participant, as JSON text
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RoomMessage {
+    /// change of the parameters of the room's simulation, absent fields keep their value
+    Update(ParamsUpdate),
+}
+
+/// Event sent to every participant of a room, as JSON text between the frames
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RoomEvent {
+    /// names of the participants in the order they joined, after someone joined or left
+    Participants { participants: Vec<String> },
+    /// parameters of the simulation after the latest change, the last writer winning
+    Params {
+        /// number of changes made in the room, 0 before the first one
+        revision: u64,
+        /// participant who made the latest change, empty before the first one
+        by: String,
+        params: SimulationParams,
+    },
+}
+
+/// Participants of a room and the latest change of its parameters
+struct Room {
+    /// participant names by join number
+    participants: BTreeMap<u64, String>,
+    joined: u64,
+    revision: u64,
+    by: String,
+    events: Sender<RoomEvent>,
+}
+
+impl Room {
+    fn participants(&self) -> RoomEvent {
+        RoomEvent::Participants {
+            participants: self.participants.values().cloned().collect(),
+        }
+    }
+}
+
+/// Named rooms whose participants share a server-side simulation and its controls
+#[derive(Clone, Default)]
+pub struct Rooms {
+    rooms: Arc<Mutex<HashMap<String, Room>>>,
+}
+
+/// Participant of a room, leaving it when dropped
+pub struct Seat {
+    rooms: Rooms,
+    room: String,
+    number: u64,
+}
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        self.rooms.leave(&self.room, self.number);
+    }
+}
+
+/// Whether name is a valid room name
+pub fn is_room_name(name: &str) -> bool {
+    (1..=MAX_ROOM_NAME).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Id of the simulation of a room
+pub fn simulation_id(room: &str) -> String {
+    format!("room-{room}")
+}
+
+/// Whether id is the id of the simulation of a room
+pub fn is_room_simulation(id: &str) -> bool {
+    id.strip_prefix("room-").is_some_and(is_room_name)
+}
+
+impl Rooms {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Room>> {
+        self.rooms.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Seat name in room, created when empty, with a receiver of the room's events
+    /// The participants are told who is in the room
+    pub fn join(&self, room: &str, name: &str) -> (Seat, Receiver<RoomEvent>) {
+        let mut rooms = self.lock();
+        let entry = rooms.entry(room.to_string()).or_insert_with(|| Room {
+            participants: BTreeMap::new(),
+            joined: 0,
+            revision: 0,
+            by: String::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        });
+        entry.joined += 1;
+        let number = entry.joined;
+        entry.participants.insert(number, name.to_string());
+        let events = entry.events.subscribe();
+        let _ = entry.events.send(entry.participants());
+        info!("{name} joined room {room}");
+        let seat = Seat {
+            rooms: self.clone(),
+            room: room.to_string(),
+            number,
+        };
+        (seat, events)
+    }
+
+    /// Remove the participant number from room, dropping the room once empty
+    fn leave(&self, room: &str, number: u64) {
+        let mut rooms = self.lock();
+        let Some(entry) = rooms.get_mut(room) else {
+            return;
+        };
+        if let Some(name) = entry.participants.remove(&number) {
+            info!("{name} left room {room}");
+        }
+        if entry.participants.is_empty() {
+            rooms.remove(room);
+        } else {
+            let _ = entry.events.send(entry.participants());
+        }
+    }
+
+    /// Names of the participants of room in the order they joined, empty for an unknown room
+    pub fn participants(&self, room: &str) -> Vec<String> {
+        self.lock()
+            .get(room)
+            .map(|entry| entry.participants.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Latest change of the parameters of room, with params the simulation runs with
+    pub fn params(&self, room: &str, params: SimulationParams) -> RoomEvent {
+        let rooms = self.lock();
+        let (revision, by) = rooms
+            .get(room)
+            .map(|entry| (entry.revision, entry.by.clone()))
+            .unwrap_or_default();
+        RoomEvent::Params {
+            revision,
+            by,
+            params,
+        }
+    }
+
+    /// Apply the change of name to the simulation of room and tell every participant
+    /// Changes are applied in the order they arrive, so the last one wins
+    /// Return the new revision, None when the simulation stopped
+    pub fn update(
+        &self,
+        manager: &SimulationManager,
+        room: &str,
+        name: &str,
+        update: ParamsUpdate,
+    ) -> Option<u64> {
+        // Updating under the rooms lock keeps revisions in the order of the changes
+        let mut rooms = self.lock();
+        let entry = rooms.get_mut(room)?;
+        let info = manager.update(&simulation_id(room), update)?;
+        entry.revision += 1;
+        entry.by = name.to_string();
+        let _ = entry.events.send(RoomEvent::Params {
+            revision: entry.revision,
+            by: name.to_string(),
+            params: info.params,
+        });
+        Some(entry.revision)
+    }
+}
+
+/// Stream the frames of the simulation of room to a participant with the events of the room,
+/// applying the changes it sends, until it disconnects
+/// Events stay JSON text whatever the encoding of the frames
+/// The first participant starts the simulation with params, stopped after the last one leaves
+#[allow(clippy::too_many_arguments)]
+pub async fn serve_participant(
+    mut socket: WebSocket,
+    manager: SimulationManager,
+    rooms: Rooms,
+    room: String,
+    name: String,
+    owner: String,
+    params: SimulationParams,
+    encoding: Encoding,
+) {
+    let id = simulation_id(&room);
+    let (full, mut frames) = match manager.subscribe(&id, &owner, params) {
+        Ok(subscription) => subscription,
+        Err(refusal) => {
+            warn!("Refusing room {room} to {owner}. {refusal}");
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    };
+    let (_seat, mut events) = rooms.join(&room, &name);
+    let Some(info) = manager.get(&id) else {
+        return;
+    };
+    let current = rooms.params(&room, info.params);
+    let mut encoder = FrameEncoder::default();
+    if stream::send_frame(&mut socket, &full, encoding, &mut encoder)
+        .await
+        .is_err()
+        || stream::send(&mut socket, &current).await.is_err()
+    {
+        return;
+    }
+    loop {
+        tokio::select! {
+            frame = frames.recv() => {
+                let sent = match frame {
+                    Ok(frame) => {
+                        stream::send_frame(&mut socket, &frame, encoding, &mut encoder).await
+                    }
+                    // A participant too slow for the diffs starts over from a full frame
+                    Err(RecvError::Lagged(_)) => {
+                        let Ok((full, receiver)) = manager.subscribe(&id, &owner, params) else {
+                            return;
+                        };
+                        frames = receiver;
+                        stream::send_frame(&mut socket, &full, encoding, &mut encoder).await
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if sent.is_err() {
+                    return;
+                }
+            }
+            event = events.recv() => {
+                let sent = match event {
+                    Ok(event) => stream::send(&mut socket, &event).await,
+                    Err(RecvError::Lagged(_)) => {
+                        let Some(info) = manager.get(&id) else {
+                            return;
+                        };
+                        let participants = RoomEvent::Participants {
+                            participants: rooms.participants(&room),
+                        };
+                        let current = rooms.params(&room, info.params);
+                        match stream::send(&mut socket, &participants).await {
+                            Ok(()) => stream::send(&mut socket, &current).await,
+                            error => error,
+                        }
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if sent.is_err() {
+                    return;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<RoomMessage>(&text) {
+                        Ok(RoomMessage::Update(update)) => {
+                            rooms.update(&manager, &room, &name, update);
+                        }
+                        Err(e) => warn!("Ignoring a message of {name} in room {room}. Error {e}"),
+                    }
+                }
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rooms_share_changes_and_participants() {
+        let manager = SimulationManager::default();
+        let rooms = Rooms::default();
+        let params = SimulationParams {
+            size: 8,
+            ..Default::default()
+        };
+        let (_frame, _frames) = manager
+            .subscribe(&simulation_id("lab"), "ip:127.0.0.1", params)
+            .unwrap();
+
+        let (ada, mut events) = rooms.join("lab", "ada");
+        let (_bob, _) = rooms.join("lab", "bob");
+        let first = rooms.update(
+            &manager,
+            "lab",
+            "bob",
+            ParamsUpdate {
+                temperature: Some(1000.0),
+                ..Default::default()
+            },
+        );
+        let second = rooms.update(
+            &manager,
+            "lab",
+            "ada",
+            ParamsUpdate {
+                temperature: Some(3000.0),
+                ..Default::default()
+            },
+        );
+        drop(ada);
+        let received: Vec<RoomEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+
+        assert_eq!((first, second), (Some(1), Some(2)));
+        assert_eq!(
+            manager
+                .get(&simulation_id("lab"))
+                .unwrap()
+                .params
+                .temperature,
+            3000.0
+        );
+        assert_eq!(received.len(), 5);
+        assert_eq!(
+            received[1],
+            RoomEvent::Participants {
+                participants: vec!["ada".to_string(), "bob".to_string()]
+            }
+        );
+        assert!(matches!(
+            &received[3],
+            RoomEvent::Params { revision: 2, by, .. } if by == "ada"
+        ));
+        assert_eq!(
+            received[4],
+            RoomEvent::Participants {
+                participants: vec!["bob".to_string()]
+            }
+        );
+        assert_eq!(rooms.participants("lab"), vec!["bob".to_string()]);
+        assert_eq!(
+            rooms.update(&manager, "empty", "ada", ParamsUpdate::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_room_and_participant_names() {
+        let long = Participant {
+            name: Some(format!("  {}  ", "a".repeat(40))),
+        };
+
+        assert!(is_room_name("physics-101_a"));
+        assert!(!is_room_name(""));
+        assert!(!is_room_name("two words"));
+        assert!(!is_room_name(&"a".repeat(MAX_ROOM_NAME + 1)));
+        assert_eq!(Participant::default().display_name(), "guest");
+        assert_eq!(long.display_name(), "a".repeat(MAX_PARTICIPANT_NAME));
+    }
+}
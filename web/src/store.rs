@@ -0,0 +1,223 @@
+use crate::results::Measurement;
+use crate::stream::SimulationParams;
+use internal::{scan::ScanPoint, snapshot::Snapshot, Lattice};
+use sled::transaction::{TransactionError, Transactional};
+use std::io;
+use std::path::Path;
+
+/// Number of sweeps between two checkpoints of a managed simulation, 10s at SWEEP_INTERVAL
+pub const CHECKPOINT_EVERY: u64 = 200;
+
+/// Parameters and progress of a stored simulation, kept apart from its spins
+/// so simulations are listed without reading their lattices
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StoredSimulation {
+    pub params: SimulationParams,
+    /// sweeps run when the simulation was checkpointed
+    pub sweep: u64,
+    /// client who started the simulation, see `web::manager::Owner`
+    #[serde(default)]
+    pub owner: String,
+}
+
+/// Managed simulations saved on disk, resumed after a restart from their latest checkpoint,
+/// the phase diagram points measured so far, see `web::phase`,
+/// and the measurements of the results database, see `web::results`
+#[derive(Clone)]
+pub struct Store {
+    db: sled::Db,
+    /// StoredSimulation JSON by id
+    simulations: sled::Tree,
+    /// Snapshot JSON of the lattices by id
+    lattices: sled::Tree,
+    /// ScanPoint JSON by lattice size and temperature index, both big endian u32
+    phase_points: sled::Tree,
+    /// Measurement JSON by id, a big endian u64
+    results: sled::Tree,
+}
+
+fn to_io(e: TransactionError<()>) -> io::Error {
+    match e {
+        TransactionError::Storage(e) => e.into(),
+        TransactionError::Abort(()) => io::Error::other("Store transaction aborted"),
+    }
+}
+
+impl Store {
+    /// Open the store in the directory at path, created when missing
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::with_db(sled::open(path)?)
+    }
+
+    /// Store dropped with the process, for tests
+    pub fn temporary() -> io::Result<Self> {
+        Self::with_db(sled::Config::new().temporary(true).open()?)
+    }
+
+    fn with_db(db: sled::Db) -> io::Result<Self> {
+        Ok(Self {
+            simulations: db.open_tree("simulations")?,
+            lattices: db.open_tree("lattices")?,
+            phase_points: db.open_tree("phase_points")?,
+            results: db.open_tree("results")?,
+            db,
+        })
+    }
+
+    /// Save the checkpoint of the simulation id, replacing the previous one
+    pub fn save(
+        &self,
+        id: &str,
+        simulation: &StoredSimulation,
+        lattice: &Lattice,
+    ) -> io::Result<()> {
+        let simulation = serde_json::to_vec(simulation)?;
+        let lattice = Snapshot::new(lattice).to_bytes()?;
+        (&self.simulations, &self.lattices)
+            .transaction(|(simulations, lattices)| {
+                simulations.insert(id.as_bytes(), simulation.as_slice())?;
+                lattices.insert(id.as_bytes(), lattice.as_slice())?;
+                Ok(())
+            })
+            .map_err(to_io)
+    }
+
+    /// Parameters and progress of the stored simulation id, None when there is none
+    pub fn get(&self, id: &str) -> io::Result<Option<StoredSimulation>> {
+        match self.simulations.get(id)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Checkpoint of the simulation id with its lattice, None when there is none
+    pub fn load(&self, id: &str) -> io::Result<Option<(StoredSimulation, Lattice)>> {
+        let Some(simulation) = self.get(id)? else {
+            return Ok(None);
+        };
+        let bytes = self.lattices.get(id)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Lattice of {id} is missing"),
+            )
+        })?;
+        Ok(Some((
+            simulation,
+            Snapshot::from_bytes(&bytes)?.into_lattice(),
+        )))
+    }
+
+    /// Every stored simulation by id, sorted by id
+    pub fn list(&self) -> io::Result<Vec<(String, StoredSimulation)>> {
+        self.simulations
+            .iter()
+            .map(|entry| {
+                let (id, bytes) = entry?;
+                let id = String::from_utf8_lossy(&id).into_owned();
+                Ok((id, serde_json::from_slice(&bytes)?))
+            })
+            .collect()
+    }
+
+    /// Whether the simulation id is stored
+    pub fn contains(&self, id: &str) -> io::Result<bool> {
+        Ok(self.simulations.contains_key(id)?)
+    }
+
+    /// Forget the simulation id
+    pub fn remove(&self, id: &str) -> io::Result<()> {
+        (&self.simulations, &self.lattices)
+            .transaction(|(simulations, lattices)| {
+                simulations.remove(id.as_bytes())?;
+                lattices.remove(id.as_bytes())?;
+                Ok(())
+            })
+            .map_err(to_io)
+    }
+
+    /// Save the phase diagram point of a lattice size at a temperature index
+    pub fn save_phase_point(&self, size: usize, index: usize, point: &ScanPoint) -> io::Result<()> {
+        let mut key = (size as u32).to_be_bytes().to_vec();
+        key.extend((index as u32).to_be_bytes());
+        self.phase_points.insert(key, serde_json::to_vec(point)?)?;
+        Ok(())
+    }
+
+    /// Every stored phase diagram point with its lattice size and temperature index
+    pub fn phase_points(&self) -> io::Result<Vec<(usize, usize, ScanPoint)>> {
+        self.phase_points
+            .iter()
+            .map(|entry| {
+                let (key, bytes) = entry?;
+                let number = |bytes: &[u8]| {
+                    let bytes = bytes.try_into().map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidData, "Invalid phase point key")
+                    })?;
+                    Ok::<_, io::Error>(u32::from_be_bytes(bytes) as usize)
+                };
+                let (size, index) = key.split_at(key.len().min(4));
+                Ok((
+                    number(size)?,
+                    number(index)?,
+                    serde_json::from_slice(&bytes)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Save a measurement under a new id, greater than those of the saved ones
+    pub fn save_result(&self, mut measurement: Measurement) -> io::Result<()> {
+        measurement.id = self.db.generate_id()?;
+        self.results.insert(
+            measurement.id.to_be_bytes(),
+            serde_json::to_vec(&measurement)?,
+        )?;
+        Ok(())
+    }
+
+    /// Every saved measurement, sorted by id
+    pub fn results(&self) -> io::Result<Vec<Measurement>> {
+        self.results
+            .iter()
+            .map(|entry| Ok(serde_json::from_slice(&entry?.1)?))
+            .collect()
+    }
+
+    /// Write every pending change to disk
+    pub fn flush(&self) -> io::Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stream::pack_spins;
+
+    #[test]
+    fn test_checkpoints_round_trip() {
+        let store = Store::temporary().unwrap();
+        let simulation = StoredSimulation {
+            params: SimulationParams {
+                size: 8,
+                seed: Some(42),
+                ..Default::default()
+            },
+            sweep: 400,
+            owner: "ip:127.0.0.1".to_string(),
+        };
+        let mut lattice = Lattice::with_seed(8, 1.0, 1.0, 42);
+        lattice.sweep();
+
+        store.save("a", &simulation, &lattice).unwrap();
+        let (loaded, resumed) = store.load("a").unwrap().unwrap();
+        let listed = store.list().unwrap();
+        store.remove("a").unwrap();
+
+        assert_eq!(loaded, simulation);
+        assert_eq!(pack_spins(&resumed), pack_spins(&lattice));
+        assert_eq!(listed, vec![("a".to_string(), simulation.clone())]);
+        assert!(store.load("a").unwrap().is_none());
+    }
+}
@@ -0,0 +1,446 @@
+use crate::manager::SimulationManager;
+use crate::wire::{Encoding, FrameEncoder};
+use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
+use axum::response::sse::Event;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use futures_util::stream::{self, Stream};
+use internal::{algorithm::Algorithm, Lattice, KB};
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::warn;
+
+/// Largest lattice side a client may ask for
+pub const MAX_SIZE: usize = 256;
+
+/// Sweeps of the observables kept by every server-side simulation, see `WindowParams`
+pub const RECORDED_SWEEPS: usize = 10_000;
+
+/// Parameters of a simulation, energies in Kelvin, i.e. divided by k_B, like the GUI sliders
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SimulationParams {
+    /// lattice side, clamped to 5..=MAX_SIZE
+    pub size: usize,
+    /// temperature T in Kelvin
+    pub temperature: f64,
+    /// interaction strength J / k_B in Kelvin
+    pub interactivity: f64,
+    /// external field h / k_B in Kelvin
+    pub field: f64,
+    /// Monte Carlo update run every sweep
+    pub algorithm: Algorithm,
+    /// seed of the random spins and updates, random when absent
+    pub seed: Option<u64>,
+}
+
+impl Default for SimulationParams {
+    /// Near the critical temperature, k_B T / J = 2.269
+    fn default() -> Self {
+        Self {
+            size: 64,
+            temperature: 2269.0,
+            interactivity: 1000.0,
+            field: 0.0,
+            algorithm: Algorithm::default(),
+            seed: None,
+        }
+    }
+}
+
+impl SimulationParams {
+    /// Random lattice of these parameters
+    pub fn lattice(&self) -> Lattice {
+        let size = self.size.clamp(5, MAX_SIZE);
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut lattice = Lattice::with_seed(size, 0.0, 0.0, seed);
+        self.apply(&mut lattice);
+        lattice
+    }
+
+    /// Set the temperature, interactivity, and field of a lattice
+    pub fn apply(&self, lattice: &mut Lattice) {
+        lattice.temperature = self.temperature.max(0.0);
+        lattice.interactivity = self.interactivity * KB;
+        lattice.field = self.field * KB;
+    }
+}
+
+/// Changes of a running simulation, absent fields keep their value
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct ParamsUpdate {
+    pub temperature: Option<f64>,
+    pub interactivity: Option<f64>,
+    pub field: Option<f64>,
+    pub algorithm: Option<Algorithm>,
+}
+
+/// Running simulation as described by the API
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct SimulationInfo {
+    pub id: String,
+    /// parameters the simulation runs with, its seed included
+    #[serde(flatten)]
+    pub params: SimulationParams,
+    /// number of sweeps run so far
+    pub sweep: u64,
+    /// whether the simulation was created through the API and keeps running without clients
+    pub managed: bool,
+    /// number of connected WebSocket clients
+    pub clients: usize,
+}
+
+/// Query of the observables stream
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct SampleParams {
+    /// number of sweeps between two samples
+    pub every: u64,
+}
+
+impl Default for SampleParams {
+    fn default() -> Self {
+        Self { every: 1 }
+    }
+}
+
+/// Query of the observables computed over the latest sweeps of a simulation
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct WindowParams {
+    /// number of latest sweeps the fluctuations are computed over
+    pub window: usize,
+}
+
+impl Default for WindowParams {
+    fn default() -> Self {
+        Self { window: 1000 }
+    }
+}
+
+impl WindowParams {
+    /// Reason the window can't be computed, None when it can
+    pub fn invalid(&self) -> Option<String> {
+        if (1..=RECORDED_SWEEPS).contains(&self.window) {
+            None
+        } else {
+            Some(format!(
+                "Expected a window of 1 to {RECORDED_SWEEPS} sweeps"
+            ))
+        }
+    }
+}
+
+/// Observables of a running simulation, the fluctuations computed over its latest sweeps,
+/// so clients need not follow the lattice, units being those of `Observables`
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct WindowedObservables {
+    /// latest sweep
+    pub sweep: u64,
+    /// sweeps the window holds, fewer than asked for until the simulation ran them
+    pub window: usize,
+    /// energy per spin after the latest sweep
+    pub energy: f64,
+    /// magnetization per spin after the latest sweep
+    pub magnetization: f64,
+    /// magnetic susceptibility chi = N * (<m^2> - <m>^2) / (k_B * T) over the window
+    pub susceptibility: f64,
+    /// heat capacity per spin in units of k_B, C = N * (<e^2> - <e>^2) / (k_B * T)^2
+    /// over the window
+    pub heat_capacity: f64,
+    /// mean ratio of flipped spins to spins over the window
+    pub acceptance: f64,
+    /// correlation length of the latest lattice in lattice spacings,
+    /// None when its correlation function does not decay
+    pub correlation_length: Option<f64>,
+}
+
+/// Observables after a sweep, sent as a Server-Sent Event
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct Sample {
+    pub sweep: u64,
+    #[serde(flatten)]
+    pub observables: Observables,
+}
+
+impl Sample {
+    /// Sample of a diff frame whose sweep is a multiple of every
+    pub fn of(frame: &Frame, every: u64) -> Option<Self> {
+        match *frame {
+            Frame::Diff {
+                sweep, observables, ..
+            } if sweep % every.max(1) == 0 => Some(Sample { sweep, observables }),
+            _ => None,
+        }
+    }
+}
+
+/// Snapshot of a running simulation as described by the API
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct SimulationState {
+    #[serde(flatten)]
+    pub info: SimulationInfo,
+    /// spins row by row, one bit each, 1 for up, most significant bit first,
+    /// padded with zeros to whole bytes and encoded as standard base64
+    pub spins: String,
+    pub observables: Observables,
+}
+
+/// Observables of the lattice after the latest sweep
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize)]
+pub struct Observables {
+    /// magnetization per spin
+    pub magnetization: f64,
+    /// energy per spin
+    pub energy: f64,
+    /// ratio of flipped spins to spins in the latest sweep
+    pub acceptance: f64,
+}
+
+/// Message streamed to the clients of a simulation, as JSON text or binary, see `wire`
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Frame {
+    /// whole lattice, sent first to every client, spins as '+' and '-' row by row
+    Full {
+        sweep: u64,
+        size: usize,
+        spins: String,
+        observables: Observables,
+    },
+    /// flat indices y * size + x of the spins flipped since the previous frame
+    Diff {
+        sweep: u64,
+        flips: Vec<usize>,
+        observables: Observables,
+    },
+}
+
+impl Frame {
+    /// Full frame of a lattice at sweep
+    pub fn full(lattice: &Lattice, sweep: u64, observables: Observables) -> Self {
+        let spins = lattice
+            .value
+            .iter()
+            .flat_map(|spins| spins.value.iter())
+            .map(|spin| if *spin == 1 { '+' } else { '-' })
+            .collect();
+        Frame::Full {
+            sweep,
+            size: lattice.size,
+            spins,
+            observables,
+        }
+    }
+}
+
+/// Spins of a lattice row by row packed eight to a byte, most significant bit first, in base64
+pub fn pack_spins(lattice: &Lattice) -> String {
+    let spins = lattice
+        .value
+        .iter()
+        .flat_map(|spins| spins.value.iter())
+        .map(|spin| *spin == 1);
+    BASE64_STANDARD.encode(pack_bits(spins))
+}
+
+/// Up spins packed eight to a byte, most significant bit first, padded with zeros
+pub fn pack_bits(up: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    let up: Vec<bool> = up.into_iter().collect();
+    up.chunks(8)
+        .map(|bits| {
+            bits.iter()
+                .enumerate()
+                .filter(|(_, up)| **up)
+                .fold(0, |byte, (bit, _)| byte | 0x80 >> bit)
+        })
+        .collect()
+}
+
+/// Flat indices of the sites flipped an odd number of times, in ascending order
+pub fn net_flips(size: usize, flips: &[(usize, usize)]) -> Vec<usize> {
+    let mut flipped = vec![false; size * size];
+    for &(x, y) in flips {
+        flipped[y * size + x] ^= true;
+    }
+    flipped
+        .iter()
+        .enumerate()
+        .filter(|(_, flipped)| **flipped)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Server-Sent Events of the observables every few sweeps, until the simulation stops
+/// Samples missed by a slow client are skipped
+pub fn samples(
+    frames: Receiver<Arc<Frame>>,
+    every: u64,
+) -> impl Stream<Item = Result<Event, axum::Error>> {
+    stream::unfold(frames, move |mut frames| async move {
+        loop {
+            match frames.recv().await {
+                Ok(frame) => {
+                    if let Some(sample) = Sample::of(&frame, every) {
+                        let event = Event::default()
+                            .event("observables")
+                            .id(sample.sweep.to_string())
+                            .json_data(sample);
+                        return Some((event, frames));
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Observables stream lagged, skipping {skipped} frames");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+/// Stream the frames of the simulation id to a WebSocket client until it disconnects,
+/// starting it for owner when it is not running
+pub async fn serve_client(
+    mut socket: WebSocket,
+    manager: SimulationManager,
+    id: String,
+    owner: String,
+    params: SimulationParams,
+    encoding: Encoding,
+) {
+    let (full, mut frames) = match manager.subscribe(&id, &owner, params) {
+        Ok(subscription) => subscription,
+        Err(refusal) => {
+            warn!("Refusing simulation {id} to {owner}. {refusal}");
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    };
+    let mut encoder = FrameEncoder::default();
+    if send_frame(&mut socket, &full, encoding, &mut encoder)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    loop {
+        tokio::select! {
+            frame = frames.recv() => {
+                let sent = match frame {
+                    Ok(frame) => send_frame(&mut socket, &frame, encoding, &mut encoder).await,
+                    // A client too slow for the diffs starts over from a full frame
+                    Err(RecvError::Lagged(_)) => {
+                        let Ok((full, receiver)) = manager.subscribe(&id, &owner, params) else {
+                            return;
+                        };
+                        frames = receiver;
+                        send_frame(&mut socket, &full, encoding, &mut encoder).await
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if sent.is_err() {
+                    return;
+                }
+            }
+            message = socket.recv() => {
+                if matches!(message, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Send a frame as a JSON text message, or as a binary message of encoder
+pub async fn send_frame(
+    socket: &mut WebSocket,
+    frame: &Frame,
+    encoding: Encoding,
+    encoder: &mut FrameEncoder,
+) -> Result<(), axum::Error> {
+    match encoding {
+        Encoding::Json => send(socket, frame).await,
+        Encoding::Binary => {
+            socket
+                .send(Message::Binary(encoder.encode(frame).into()))
+                .await
+        }
+    }
+}
+
+/// Send a frame or another message as a JSON text message
+pub async fn send(
+    socket: &mut WebSocket,
+    message: &impl serde::Serialize,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).map_err(axum::Error::new)?;
+    socket.send(Message::Text(Utf8Bytes::from(text))).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_net_flips_drop_sites_flipped_twice() {
+        let flips = [(1, 0), (2, 1), (1, 0), (0, 2)];
+
+        let result = net_flips(3, &flips);
+
+        assert_eq!(result, vec![5, 6]);
+    }
+
+    #[test]
+    fn test_full_frame_encodes_spins_row_by_row() {
+        let mut lattice = Lattice::with_seed(2, 1.0, 1.0, 42);
+        lattice.set_spin(0, 0, 1);
+        lattice.set_spin(1, 0, -1);
+        lattice.set_spin(0, 1, -1);
+        lattice.set_spin(1, 1, 1);
+
+        let frame = Frame::full(&lattice, 3, Observables::default());
+        let json = serde_json::to_value(&frame).unwrap();
+
+        assert_eq!(json["type"], "full");
+        assert_eq!(json["sweep"], 3);
+        assert_eq!(json["size"], 2);
+        assert_eq!(json["spins"], "+--+");
+    }
+
+    #[test]
+    fn test_pack_spins_sets_a_bit_per_up_spin() {
+        let mut lattice = Lattice::with_seed(3, 1.0, 1.0, 42);
+        for y in 0..3 {
+            for x in 0..3 {
+                lattice.set_spin(x, y, if x == y { 1 } else { -1 });
+            }
+        }
+
+        let packed = pack_spins(&lattice);
+
+        // Up spins at 0, 4, and 8: 1000 1000 | 1000 0000
+        assert_eq!(BASE64_STANDARD.decode(packed).unwrap(), vec![0x88, 0x80]);
+    }
+
+    #[test]
+    fn test_samples_keep_every_kth_sweep() {
+        let diff = |sweep| Frame::Diff {
+            sweep,
+            flips: Vec::new(),
+            observables: Observables::default(),
+        };
+        let full = Frame::full(
+            &Lattice::with_seed(2, 1.0, 1.0, 42),
+            4,
+            Observables::default(),
+        );
+
+        let sweeps: Vec<u64> = (1..=6)
+            .filter_map(|sweep| Sample::of(&diff(sweep), 3))
+            .map(|sample| sample.sweep)
+            .collect();
+
+        assert_eq!(sweeps, vec![3, 6]);
+        assert_eq!(Sample::of(&full, 1), None);
+        assert!(Sample::of(&diff(5), 0).is_some());
+    }
+}
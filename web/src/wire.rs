@@ -0,0 +1,201 @@
+use crate::stream::{pack_bits, Frame, Observables};
+use axum::extract::ws::WebSocketUpgrade;
+use axum::http::{header, HeaderMap};
+
+/// Media type of binary frames, asked for in the Accept header of the state endpoint
+pub const MEDIA_TYPE: &str = "application/vnd.ising.frame";
+
+/// WebSocket subprotocol of clients receiving frames as binary messages
+pub const PROTOCOL: &str = "ising.frame.v1";
+
+/// Bytes before the payload of a binary frame:
+/// kind u8, sweep u64, size u32, then magnetization, energy, and acceptance f64,
+/// numbers in little endian
+pub const HEADER_LEN: usize = 37;
+
+/// Kind of a binary frame, its first byte
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Kind {
+    /// spins row by row, one bit each, 1 for up, most significant bit first,
+    /// padded with zeros to whole bytes
+    Full = 0,
+    /// spins flipped since the previous frame, XOR-ed onto its spins, packed like Full
+    Xor = 1,
+    /// flat indices y * size + x of the spins flipped since the previous frame, u32 each
+    Flips = 2,
+}
+
+/// Encoding of the frames sent to a client
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// JSON text, see `Frame`
+    #[default]
+    Json,
+    /// binary messages of `FrameEncoder`
+    Binary,
+}
+
+impl Encoding {
+    /// Binary when the Accept header lists MEDIA_TYPE, JSON otherwise
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let binary = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                parts.next() == Some(MEDIA_TYPE) && !parts.any(|part| part == "q=0")
+            });
+        if binary {
+            Encoding::Binary
+        } else {
+            Encoding::Json
+        }
+    }
+}
+
+/// Upgrade offering PROTOCOL, with the encoding of the frames of the client:
+/// binary when it asked for PROTOCOL, JSON otherwise
+pub fn negotiate_protocol(ws: WebSocketUpgrade) -> (WebSocketUpgrade, Encoding) {
+    let ws = ws.protocols([PROTOCOL]);
+    let encoding = match ws.selected_protocol() {
+        Some(protocol) if protocol == PROTOCOL => Encoding::Binary,
+        _ => Encoding::Json,
+    };
+    (ws, encoding)
+}
+
+/// Encoder of the frames of a simulation into binary messages
+/// Diffs are sent as an XOR mask or a list of flips, whichever is shorter,
+/// the size of the lattice being that of the latest full frame
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameEncoder {
+    size: usize,
+}
+
+impl FrameEncoder {
+    /// Binary message of a frame
+    pub fn encode(&mut self, frame: &Frame) -> Vec<u8> {
+        match frame {
+            Frame::Full {
+                sweep,
+                size,
+                spins,
+                observables,
+            } => {
+                self.size = *size;
+                let mut bytes = self.header(Kind::Full, *sweep, observables);
+                bytes.extend(pack_bits(spins.bytes().map(|spin| spin == b'+')));
+                bytes
+            }
+            Frame::Diff {
+                sweep,
+                flips,
+                observables,
+            } => {
+                let sites = self.size * self.size;
+                if flips.len() * 4 < sites.div_ceil(8) || flips.iter().any(|&i| i >= sites) {
+                    let mut bytes = self.header(Kind::Flips, *sweep, observables);
+                    for &index in flips {
+                        bytes.extend((index as u32).to_le_bytes());
+                    }
+                    return bytes;
+                }
+                let mut mask = vec![false; sites];
+                for &index in flips {
+                    mask[index] = true;
+                }
+                let mut bytes = self.header(Kind::Xor, *sweep, observables);
+                bytes.extend(pack_bits(mask));
+                bytes
+            }
+        }
+    }
+
+    fn header(&self, kind: Kind, sweep: u64, observables: &Observables) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.size * self.size / 8 + 1);
+        bytes.push(kind as u8);
+        bytes.extend(sweep.to_le_bytes());
+        bytes.extend((self.size as u32).to_le_bytes());
+        bytes.extend(observables.magnetization.to_le_bytes());
+        bytes.extend(observables.energy.to_le_bytes());
+        bytes.extend(observables.acceptance.to_le_bytes());
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn full(size: usize, spins: &str) -> Frame {
+        Frame::Full {
+            sweep: 1,
+            size,
+            spins: spins.to_string(),
+            observables: Observables::default(),
+        }
+    }
+
+    fn diff(flips: Vec<usize>) -> Frame {
+        Frame::Diff {
+            sweep: 2,
+            flips,
+            observables: Observables {
+                magnetization: 0.5,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_full_frames_pack_their_spins_after_the_header() {
+        let mut encoder = FrameEncoder::default();
+
+        let bytes = encoder.encode(&full(3, "+--+-+--+"));
+
+        assert_eq!(bytes.len(), HEADER_LEN + 2);
+        assert_eq!(bytes[0], Kind::Full as u8);
+        assert_eq!(bytes[1..9], 1u64.to_le_bytes());
+        assert_eq!(bytes[9..13], 3u32.to_le_bytes());
+        assert_eq!(bytes[HEADER_LEN..], [0b1001_0100, 0b1000_0000]);
+    }
+
+    #[test]
+    fn test_diffs_take_the_shorter_of_a_mask_and_flips() {
+        let mut encoder = FrameEncoder::default();
+        encoder.encode(&full(8, &"+".repeat(64)));
+
+        let few = encoder.encode(&diff(vec![9]));
+        let many = encoder.encode(&diff(vec![0, 1, 2, 9]));
+
+        assert_eq!(few[0], Kind::Flips as u8);
+        assert_eq!(few[13..21], 0.5f64.to_le_bytes());
+        assert_eq!(few[HEADER_LEN..], 9u32.to_le_bytes());
+        assert_eq!(many[0], Kind::Xor as u8);
+        assert_eq!(
+            many[HEADER_LEN..],
+            [0b1110_0000, 0b0100_0000, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_binary_is_sent_to_clients_accepting_it() {
+        let accept = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+            Encoding::negotiate(&headers)
+        };
+
+        assert_eq!(
+            accept("application/json, application/vnd.ising.frame"),
+            Encoding::Binary
+        );
+        assert_eq!(accept("application/vnd.ising.frame;q=0"), Encoding::Json);
+        assert_eq!(accept("*/*"), Encoding::Json);
+        assert_eq!(Encoding::negotiate(&HeaderMap::new()), Encoding::Json);
+    }
+}